@@ -0,0 +1,10 @@
+#version 460
+
+out vec4 frag_color;
+
+uniform vec3 line_color = vec3(1.0f, 1.0f, 1.0f);
+
+void main()
+{
+    frag_color = vec4(line_color, 1.0f);
+}
@@ -0,0 +1,10 @@
+#version 460
+
+layout (location = 0) in vec2 pos;
+
+// maps points of the plane to clip space, the inverse of the camera view transform
+uniform mat3x2 plane_to_clip;
+
+void main(){
+    gl_Position = vec4(plane_to_clip * vec3(pos, 1.0), 0.0, 1.0);
+}
@@ -5,43 +5,202 @@ in vec4 gl_FragCoord;
 out vec4 frag_color;
 
 uniform vec2 screen_size = vec2(800.0f, 600.0f);
-uniform vec2 x_axis_range = vec2(-1.0f, 1.0f);
-uniform vec2 y_axis_range = vec2(-1.0f, 1.0f);
+// bottom left pixel of the viewport, not zero while the window is split into panes
+uniform vec2 viewport_origin = vec2(0.0f, 0.0f);
+uniform mat3x2 view_transform = mat3x2(2.0f, 0.0f, 0.0f, 2.0f, -1.0f, -1.0f);
 uniform int max_iterations = 500;
+// 0 iterates z^2 + c for the mandelbrot and julia sets, 1 computes lyapunov exponents,
+// 2 iterates the phoenix, 3 and 4 the magnet formulas, see `Formula` in the scene
+uniform int formula = 0;
 uniform bool julia = true;
 uniform vec2 julia_const = vec2(-0.8f, 0.156f);
+// extra parameter of the formula, p of the phoenix fractal
+uniform vec2 formula_parameter = vec2(-0.5f, 0.0f);
+// a point escapes once its norm exceeds the bailout radius, see `EscapeNorm` in the scene for the norms
+uniform float bailout = 2.0f;
+uniform int escape_norm = 0;
 uniform vec3 hsv_scale = vec3(1.0f, 1.0f, 1.0f);
+uniform float palette_offset = 0.0f;
 uniform vec3 terminal_color = vec3(0.0f, 0.0f, 0.0f);
+// AB sequence of the lyapunov fractal as bits, B is 1 and the first letter is the lowest bit
+uniform int lyapunov_sequence = 2;
+uniform int lyapunov_length = 2;
+uniform int lyapunov_warmup = 50;
+// 0 renders colors, 1 and 2 write raw data for exporting, see `DataLayer` in the renderer
+uniform int output_mode = 0;
 
-int check_convergence()
+// result of iterating a single point
+struct Orbit {
+    int iterations;
+    // last value of z
+    vec2 z;
+    // derivative of z with respect to the starting point, used for the distance estimate
+    vec2 derivative;
+    // whether the iteration stopped at a finite attractor instead of escaping
+    bool converged;
+};
+
+// point of the plane shown at the current pixel
+vec2 pixel_point()
 {
-    float real = (gl_FragCoord.x / screen_size.x) * (x_axis_range.y - x_axis_range.x) + x_axis_range.x;
-    float imag = (gl_FragCoord.y / screen_size.y) * (y_axis_range.y - y_axis_range.x) + y_axis_range.x;
+    return view_transform * vec3((gl_FragCoord.xy - viewport_origin) / screen_size, 1.0f);
+}
 
-    int iterations = 0;
+vec2 complex_mul(vec2 a, vec2 b)
+{
+    return vec2(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
 
-    float const_real = real;
-    float const_imag = imag;
+vec2 complex_div(vec2 a, vec2 b)
+{
+    return vec2(a.x * b.x + a.y * b.y, a.y * b.x - a.x * b.y) / dot(b, b);
+}
 
-    if(julia) {
-        const_real = julia_const.x;
-        const_imag = julia_const.y;
+// whether z lies outside the bailout radius measured with the escape norm
+bool has_escaped(vec2 z)
+{
+    vec2 a = abs(z);
+    // points far out always escape, so the orbits of the one sided norms do not overflow, NaN escapes as well
+    if (!(dot(z, z) < 1e32f))
+        return true;
+
+    switch (escape_norm)
+    {
+        case 1: return max(a.x, a.y) > bailout;
+        case 2: return a.x + a.y > bailout;
+        case 3: return a.x > bailout;
+        case 4: return a.y > bailout;
+        case 5: return a.x > bailout && a.y > bailout;
+        default: return dot(z, z) > bailout * bailout;
     }
+}
 
+// whether the orbit reached the attracting fixed point 1 of the magnet formulas
+bool has_converged(vec2 z)
+{
+    vec2 offset = z - vec2(1.0f, 0.0f);
+    return formula >= 3 && dot(offset, offset) < 1e-6f;
+}
+
+// next z of the magnet formulas, the derivative is advanced by the chain rule,
+// `parameter_plane` adds the dependence on c while the plane of c is shown
+vec2 magnet_step(vec2 z, vec2 c, inout vec2 derivative, bool parameter_plane)
+{
+    vec2 one = vec2(1.0f, 0.0f);
+    vec2 numerator, denominator, numerator_dz, denominator_dz, dc;
+    if (formula == 3)
+    {
+        numerator = complex_mul(z, z) + c - one;
+        denominator = 2.0f * z + c - 2.0f * one;
+        numerator_dz = 2.0f * z;
+        denominator_dz = 2.0f * one;
+        // numerator and denominator grow alike with c
+        dc = one;
+    }
+    else
+    {
+        vec2 c1 = c - one;
+        vec2 c2 = c - 2.0f * one;
+        numerator = complex_mul(complex_mul(z, z), z) + 3.0f * complex_mul(c1, z) + complex_mul(c1, c2);
+        denominator = 3.0f * complex_mul(z, z) + 3.0f * complex_mul(c2, z) + complex_mul(c1, c2) + one;
+        numerator_dz = 3.0f * complex_mul(z, z) + 3.0f * c1;
+        denominator_dz = 6.0f * z + 3.0f * c2;
+        dc = 3.0f * z + 2.0f * c - 3.0f * one;
+    }
+
+    vec2 ratio = complex_div(numerator, denominator);
+    vec2 denominator_squared = complex_mul(denominator, denominator);
+    vec2 ratio_dz = complex_div(complex_mul(numerator_dz, denominator) - complex_mul(numerator, denominator_dz), denominator_squared);
+    derivative = 2.0f * complex_mul(ratio, complex_mul(ratio_dz, derivative));
+    if (parameter_plane)
+        derivative += 2.0f * complex_mul(ratio, complex_div(complex_mul(dc, denominator - numerator), denominator_squared));
+
+    return complex_mul(ratio, ratio);
+}
+
+// iterate the formula until the point escapes, converges or reaches the iteration limit
+Orbit check_convergence()
+{
+    vec2 point = pixel_point();
+    vec2 z = point;
+    vec2 c = julia ? julia_const : point;
+    vec2 derivative = vec2(1.0f, 0.0f);
+
+    // the parameter planes of the phoenix and magnet formulas start from the critical point 0
+    if (!julia && formula != 0)
+    {
+        z = vec2(0.0f);
+        derivative = vec2(0.0f);
+    }
+    vec2 previous = vec2(0.0f);
+    vec2 previous_derivative = vec2(0.0f);
+
+    int iterations = 0;
+    bool converged = false;
     while (iterations < max_iterations)
     {
-        float tmp_real = real;
-        real = (real * real - imag * imag) + const_real;
-        imag = (2.0 * tmp_real * imag) + const_imag;
+        if (formula >= 3)
+        {
+            z = magnet_step(z, c, derivative, !julia);
+        }
+        else
+        {
+            // derivative = 2 * z * derivative (+ 1 for the mandelbrot set, where c depends on the starting point)
+            vec2 next_derivative = 2.0f * complex_mul(z, derivative);
+            if (!julia)
+                next_derivative.x += 1.0f;
+            vec2 next = complex_mul(z, z) + c;
 
-        float dist = real * real + imag * imag;
+            // the phoenix formula adds the previous z scaled by p
+            if (formula == 2)
+            {
+                next += complex_mul(formula_parameter, previous);
+                next_derivative += complex_mul(formula_parameter, previous_derivative);
+            }
+            previous = z;
+            previous_derivative = derivative;
+            z = next;
+            derivative = next_derivative;
+        }
 
-        if (dist > 4.0)
+        if (has_escaped(z))
             break;
+        if (has_converged(z))
+        {
+            converged = true;
+            break;
+        }
 
         iterations++;
     }
-    return iterations;
+    return Orbit(iterations, z, derivative, converged);
+}
+
+// growth rate of the logistic map at the given step, x of the point is a and y is b
+float lyapunov_rate(vec2 rates, int step)
+{
+    return ((lyapunov_sequence >> (step % lyapunov_length)) & 1) == 1 ? rates.y : rates.x;
+}
+
+// lyapunov exponent of the logistic map x -> r x (1 - x) with r following the AB sequence
+// averaged over max_iterations steps after the warm-up, negative in stable and positive in chaotic regions
+float lyapunov_exponent()
+{
+    vec2 rates = pixel_point();
+    float x = 0.5f;
+
+    for (int step = 0; step < lyapunov_warmup; step++)
+        x = lyapunov_rate(rates, step) * x * (1.0f - x);
+
+    int count = max(max_iterations, 1);
+    float sum = 0.0f;
+    for (int step = 0; step < count; step++)
+    {
+        float r = lyapunov_rate(rates, lyapunov_warmup + step);
+        sum += log(max(abs(r * (1.0f - 2.0f * x)), 1e-12f));
+        x = r * x * (1.0f - x);
+    }
+    return sum / float(count);
 }
 
 vec3 hsv2rgb(vec3 c)
@@ -51,16 +210,51 @@ vec3 hsv2rgb(vec3 c)
     return c.z * mix(K.xxx, clamp(p - K.xxx, 0.0, 1.0), c.y);
 }
 
-vec4 return_color()
+// stable regions are colored from the palette, getting brighter the more stable they are,
+// chaotic regions fade into the terminal color
+vec4 lyapunov_color(float exponent)
+{
+    if (exponent >= 0.0f)
+        return vec4(terminal_color * (1.0f - exp(-exponent)), 1.0f);
+
+    float stability = 1.0f - exp(exponent);
+    vec3 color_rgb = hsv2rgb(vec3(palette_offset + hsv_scale.x * stability, hsv_scale.y, hsv_scale.z * stability));
+    return vec4(color_rgb, 1.0f);
+}
+
+// raw data of the orbit, only used when exporting
+// mode 1: smooth iteration count, distance estimate, escape flag, iteration count
+// mode 2: last z and its derivative
+vec4 return_data(Orbit orbit)
+{
+    bool escaped = orbit.iterations < max_iterations;
+
+    if (output_mode == 2)
+        return vec4(orbit.z, orbit.derivative);
+
+    float smooth_iterations = float(orbit.iterations);
+    float distance = 0.0f;
+    // points converging to an attractor keep the whole iteration count and lie at no distance to the set
+    if (escaped && !orbit.converged)
+    {
+        // measured relative to the bailout, so the count stays continuous whatever the radius
+        float radius = max(length(orbit.z), 1.0001f);
+        smooth_iterations += 1.0f - log2(log(radius) / log(max(bailout, 1.0001f)));
+        distance = 0.5f * radius * log(radius) / length(orbit.derivative);
+    }
+
+    return vec4(smooth_iterations, distance, escaped ? 1.0f : 0.0f, float(orbit.iterations));
+}
+
+vec4 return_color(int iter)
 {
-    int iter = check_convergence();
     if (iter == max_iterations)
     {
         gl_FragDepth = 0.0f;
         return vec4(terminal_color.xyz, 1.0f);
     }
 
-    float hue = hsv_scale.x * float(iter) / float(max_iterations);
+    float hue = palette_offset + hsv_scale.x * float(iter) / float(max_iterations);
     float saturation = hsv_scale.y ;
     float value = hsv_scale.z;
 
@@ -71,5 +265,21 @@ vec4 return_color()
 
 void main()
 {
-    frag_color = return_color();
+    if (formula == 1)
+    {
+        // the data layers hold the exponent and whether the point is stable
+        float exponent = lyapunov_exponent();
+        if (output_mode == 0)
+            frag_color = lyapunov_color(exponent);
+        else
+            frag_color = vec4(exponent, 0.0f, exponent < 0.0f ? 1.0f : 0.0f, float(max_iterations));
+        return;
+    }
+
+    Orbit orbit = check_convergence();
+
+    if (output_mode == 0)
+        frag_color = return_color(orbit.iterations);
+    else
+        frag_color = return_data(orbit);
 }
\ No newline at end of file
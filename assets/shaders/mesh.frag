@@ -0,0 +1,23 @@
+#version 460
+
+in vec3 vertex_position;
+in vec3 vertex_normal;
+in vec3 vertex_color;
+
+out vec4 frag_color;
+
+uniform vec3 camera_position = vec3(0.0f, 0.0f, 3.0f);
+
+const vec3 light_direction = normalize(vec3(0.6f, 0.8f, 0.4f));
+
+void main()
+{
+    vec3 normal = normalize(vertex_normal);
+    vec3 view = normalize(camera_position - vertex_position);
+
+    // sun light with a sky light from above and a small highlight
+    float diffuse = max(dot(normal, light_direction), 0.0f);
+    float ambient = 0.3f * (0.5f + 0.5f * normal.y);
+    float specular = pow(max(dot(reflect(-light_direction, normal), view), 0.0f), 32.0f) * diffuse;
+    frag_color = vec4(vertex_color * (diffuse * 0.8f + ambient) + vec3(0.2f) * specular, 1.0f);
+}
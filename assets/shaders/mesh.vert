@@ -0,0 +1,18 @@
+#version 460
+
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec3 normal;
+layout (location = 2) in vec3 color;
+
+uniform mat4 view_projection;
+
+out vec3 vertex_position;
+out vec3 vertex_normal;
+out vec3 vertex_color;
+
+void main(){
+    vertex_position = pos;
+    vertex_normal = normal;
+    vertex_color = color;
+    gl_Position = view_projection * vec4(pos, 1.0);
+}
@@ -0,0 +1,208 @@
+#version 460
+
+in vec4 gl_FragCoord;
+
+out vec4 frag_color;
+
+uniform vec2 screen_size = vec2(800.0f, 600.0f);
+uniform vec2 viewport_origin = vec2(0.0f, 0.0f);
+
+// camera basis and the tangent of half the vertical field of view
+uniform vec3 camera_position = vec3(0.0f, 0.0f, 3.0f);
+uniform vec3 camera_forward = vec3(0.0f, 0.0f, -1.0f);
+uniform vec3 camera_right = vec3(1.0f, 0.0f, 0.0f);
+uniform vec3 camera_up = vec3(0.0f, 1.0f, 0.0f);
+uniform float fov_scale = 0.577f;
+
+// 0 mandelbulb, 1 mandelbox, 2 menger sponge, see `Shape` in the 3d scene
+uniform int shape = 0;
+uniform float power = 8.0f;
+uniform float box_scale = -1.5f;
+uniform int fractal_iterations = 12;
+uniform int max_steps = 200;
+
+uniform float shadow_softness = 16.0f;
+uniform float ambient_occlusion = 1.0f;
+uniform float fog_density = 0.05f;
+uniform vec3 fog_color = vec3(0.55f, 0.6f, 0.7f);
+uniform float palette_offset = 0.0f;
+
+const vec3 light_direction = normalize(vec3(0.6f, 0.8f, 0.4f));
+const float max_distance = 100.0f;
+
+// how close the orbit came to the origin, used for coloring
+float trap;
+
+float mandelbulb(vec3 point)
+{
+    vec3 z = point;
+    float derivative = 1.0f;
+    float r = length(z);
+    trap = r;
+
+    for (int i = 0; i < fractal_iterations && r < 2.0f; i++)
+    {
+        // raise z to the power in spherical coordinates and add the point
+        float theta = acos(clamp(z.z / r, -1.0f, 1.0f)) * power;
+        float phi = atan(z.y, z.x) * power;
+        derivative = pow(r, power - 1.0f) * power * derivative + 1.0f;
+        z = pow(r, power) * vec3(sin(theta) * cos(phi), sin(phi) * sin(theta), cos(theta)) + point;
+
+        r = length(z);
+        trap = min(trap, r);
+    }
+    return 0.5f * log(max(r, 1e-6f)) * r / derivative;
+}
+
+float mandelbox(vec3 point)
+{
+    vec3 z = point;
+    float derivative = 1.0f;
+    trap = 1e10f;
+
+    for (int i = 0; i < fractal_iterations; i++)
+    {
+        // box fold followed by a sphere fold
+        z = clamp(z, -1.0f, 1.0f) * 2.0f - z;
+        float r2 = dot(z, z);
+        if (r2 < 0.25f)
+        {
+            z *= 4.0f;
+            derivative *= 4.0f;
+        }
+        else if (r2 < 1.0f)
+        {
+            z /= r2;
+            derivative /= r2;
+        }
+
+        z = z * box_scale + point;
+        derivative = derivative * abs(box_scale) + 1.0f;
+        trap = min(trap, sqrt(r2));
+    }
+    return length(z) / abs(derivative);
+}
+
+float menger_sponge(vec3 point)
+{
+    vec3 q = abs(point) - vec3(1.0f);
+    float dist = length(max(q, 0.0f)) + min(max(q.x, max(q.y, q.z)), 0.0f);
+    float scale = 1.0f;
+    trap = 1.0f;
+
+    for (int i = 0; i < fractal_iterations; i++)
+    {
+        // carve out the cross of the next level
+        vec3 a = mod(point * scale, 2.0f) - 1.0f;
+        scale *= 3.0f;
+        vec3 r = abs(1.0f - 3.0f * abs(a));
+        float carved = (min(max(r.x, r.y), min(max(r.y, r.z), max(r.z, r.x))) - 1.0f) / scale;
+        if (carved > dist)
+        {
+            dist = carved;
+            trap = float(i) / float(max(fractal_iterations, 1));
+        }
+    }
+    return dist;
+}
+
+float distance_estimate(vec3 point)
+{
+    if (shape == 1)
+        return mandelbox(point);
+    if (shape == 2)
+        return menger_sponge(point);
+    return mandelbulb(point);
+}
+
+vec3 surface_normal(vec3 point, float epsilon)
+{
+    vec2 e = vec2(epsilon, 0.0f);
+    return normalize(vec3(
+        distance_estimate(point + e.xyy) - distance_estimate(point - e.xyy),
+        distance_estimate(point + e.yxy) - distance_estimate(point - e.yxy),
+        distance_estimate(point + e.yyx) - distance_estimate(point - e.yyx)));
+}
+
+// march towards the light, the closest miss along the way darkens the penumbra
+float soft_shadow(vec3 point, vec3 direction)
+{
+    if (shadow_softness <= 0.0f)
+        return 1.0f;
+
+    float light = 1.0f;
+    float t = 0.01f;
+    for (int i = 0; i < 64 && t < 10.0f; i++)
+    {
+        float dist = distance_estimate(point + direction * t);
+        if (dist < 1e-4f)
+            return 0.0f;
+        light = min(light, shadow_softness * dist / t);
+        t += clamp(dist, 0.005f, 0.5f);
+    }
+    return clamp(light, 0.0f, 1.0f);
+}
+
+// compare the distance to the surface with the distance along the normal, creases are closer than expected
+float occlusion(vec3 point, vec3 normal)
+{
+    float occluded = 0.0f;
+    float weight = 1.0f;
+    for (int i = 1; i <= 5; i++)
+    {
+        float along = 0.02f * float(i);
+        occluded += weight * (along - distance_estimate(point + normal * along));
+        weight *= 0.5f;
+    }
+    return clamp(1.0f - ambient_occlusion * 8.0f * occluded, 0.0f, 1.0f);
+}
+
+vec3 hsv2rgb(vec3 c)
+{
+    vec4 K = vec4(1.0, 2.0 / 3.0, 1.0 / 3.0, 3.0);
+    vec3 p = abs(fract(c.xxx + K.xyz) * 6.0 - K.www);
+    return c.z * mix(K.xxx, clamp(p - K.xxx, 0.0, 1.0), c.y);
+}
+
+void main()
+{
+    // direction of the ray through the pixel
+    vec2 uv = ((gl_FragCoord.xy - viewport_origin) * 2.0f - screen_size) / screen_size.y;
+    vec3 direction = normalize(camera_forward + (uv.x * camera_right + uv.y * camera_up) * fov_scale);
+    float pixel_angle = 2.0f * fov_scale / screen_size.y;
+
+    float t = 0.0f;
+    bool hit = false;
+    for (int i = 0; i < max_steps && t < max_distance; i++)
+    {
+        float dist = distance_estimate(camera_position + direction * t);
+        // stop once the surface is closer than the size of a pixel at that distance
+        if (dist < max(pixel_angle * t, 1e-5f))
+        {
+            hit = true;
+            break;
+        }
+        t += dist;
+    }
+
+    if (!hit)
+    {
+        frag_color = vec4(fog_color, 1.0f);
+        return;
+    }
+
+    vec3 point = camera_position + direction * t;
+    float coloring = trap;
+    vec3 normal = surface_normal(point, max(pixel_angle * t, 1e-5f) * 0.5f);
+    vec3 offset_point = point + normal * max(pixel_angle * t, 1e-5f) * 2.0f;
+
+    vec3 albedo = hsv2rgb(vec3(palette_offset + 0.6f + coloring * 0.4f, 0.5f, 0.9f));
+    float diffuse = max(dot(normal, light_direction), 0.0f) * soft_shadow(offset_point, light_direction);
+    float ambient = 0.25f * (0.5f + 0.5f * normal.y) * occlusion(point, normal);
+    float specular = pow(max(dot(reflect(direction, normal), light_direction), 0.0f), 32.0f) * diffuse;
+    vec3 color = albedo * (diffuse + ambient) + vec3(0.3f) * specular;
+
+    // fade into the fog with the distance travelled
+    float fog = 1.0f - exp(-fog_density * t);
+    frag_color = vec4(mix(color, fog_color, fog), 1.0f);
+}
@@ -0,0 +1,209 @@
+use crate::renderer::Camera;
+use crate::scene::Scene;
+
+/// Shape of the progress between two keyframes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 4] = [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Easing::Linear => "Linear",
+            Easing::EaseIn => "Ease in",
+            Easing::EaseOut => "Ease out",
+            Easing::EaseInOut => "Ease in and out",
+        }
+    }
+
+    /// map the linear progress `t` between 0 and 1 to the eased progress
+    pub fn apply(&self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Scene the animation passes through
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Keyframe {
+    pub scene: Scene,
+    /// seconds it takes to move from this keyframe to the next one
+    pub duration: f64,
+    /// easing of the movement to the next keyframe
+    pub easing: Easing,
+}
+
+impl Keyframe {
+    pub fn new(scene: Scene) -> Self {
+        Self {
+            scene,
+            duration: 5.0,
+            easing: Easing::EaseInOut,
+        }
+    }
+}
+
+/// Sequence of keyframes that is played back by interpolating between them
+#[derive(Default)]
+pub struct Animation {
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Animation {
+    /// total length in seconds, the duration of the last keyframe is not used
+    pub fn duration(&self) -> f64 {
+        let count = self.keyframes.len().saturating_sub(1);
+        self.keyframes.iter().take(count).map(|keyframe| keyframe.duration).sum()
+    }
+
+    /// scene at the given time in seconds, clamped to the first and last keyframe
+    pub fn sample(&self, time: f64) -> Option<Scene> {
+        let mut start = 0.0;
+        for pair in self.keyframes.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            if time < start + from.duration {
+                let t = ((time - start) / from.duration).clamp(0.0, 1.0);
+                return Some(interpolate(&from.scene, &to.scene, from.easing.apply(t)));
+            }
+            start += from.duration;
+        }
+
+        self.keyframes.last().map(|keyframe| keyframe.scene)
+    }
+
+    /// number of frames when playing back at the given frame rate, including the last keyframe
+    pub fn frame_count(&self, fps: f64) -> usize {
+        if self.keyframes.is_empty() {
+            return 0;
+        }
+        (self.duration() * fps).round() as usize + 1
+    }
+
+    /// scenes of all frames when playing back at the given frame rate
+    pub fn frames(&self, fps: f64) -> Vec<Scene> {
+        (0..self.frame_count(fps)).filter_map(|frame| self.sample(frame as f64 / fps)).collect()
+    }
+}
+
+/// blend between two scenes, `t` goes from 0 at `from` to 1 at `to`
+///
+/// the zoom is interpolated exponentially, so every second zooms by the same factor,
+/// and the focus moves proportional to the visible width, so it does not shoot past while zoomed in
+/// the fractal, the formula and the escape norm switch halfway, all other values are interpolated linearly
+pub fn interpolate(from: &Scene, to: &Scene, t: f64) -> Scene {
+    let lerp = |a: f64, b: f64| a + (b - a) * t;
+    let lerp_f32 = |a: f32, b: f32| a + (b - a) * t as f32;
+    let (a, b) = (&from.camera, &to.camera);
+
+    // a zoom that is not positive has no logarithm, such keyframes are blended linearly
+    let positive = a.zoom > 0.0 && b.zoom > 0.0;
+    let zoom = if positive { a.zoom * (b.zoom / a.zoom).powf(t) } else { lerp(a.zoom, b.zoom) };
+    // fraction of the way, measured in the visible width
+    let travel = if positive && (b.zoom - a.zoom).abs() > f64::EPSILON * a.zoom {
+        (1.0 / zoom - 1.0 / a.zoom) / (1.0 / b.zoom - 1.0 / a.zoom)
+    } else {
+        t
+    };
+
+    // rotate the shorter way around
+    let mut rotation = b.rotation - a.rotation;
+    if rotation > std::f64::consts::PI {
+        rotation -= std::f64::consts::TAU;
+    } else if rotation < -std::f64::consts::PI {
+        rotation += std::f64::consts::TAU;
+    }
+
+    let nearest = if t < 0.5 { from } else { to };
+    Scene {
+        fractal: nearest.fractal,
+        julia_constant: [lerp_f32(from.julia_constant[0], to.julia_constant[0]), lerp_f32(from.julia_constant[1], to.julia_constant[1])],
+        formula: nearest.formula,
+        formula_parameter: [0, 1].map(|i| lerp_f32(from.formula_parameter[i], to.formula_parameter[i])),
+        bailout: lerp_f32(from.bailout, to.bailout),
+        escape_norm: nearest.escape_norm,
+        camera: Camera {
+            focus: [a.focus[0] + (b.focus[0] - a.focus[0]) * travel, a.focus[1] + (b.focus[1] - a.focus[1]) * travel],
+            zoom,
+            width: lerp(a.width, b.width),
+            height: lerp(a.height, b.height),
+            rotation: (a.rotation + rotation * t).rem_euclid(std::f64::consts::TAU),
+        },
+        max_iterations: lerp(from.max_iterations as f64, to.max_iterations as f64).round() as i32,
+        hsv_scale: [0, 1, 2].map(|i| lerp_f32(from.hsv_scale[i], to.hsv_scale[i])),
+        palette_offset: lerp_f32(from.palette_offset, to.palette_offset),
+        terminal_color: [0, 1, 2].map(|i| lerp_f32(from.terminal_color[i], to.terminal_color[i])),
+        lyapunov_sequence: nearest.lyapunov_sequence,
+        lyapunov_warmup: lerp(from.lyapunov_warmup as f64, to.lyapunov_warmup as f64).round() as i32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene(focus: [f64; 2], zoom: f64) -> Scene {
+        let mut scene = Scene::default();
+        scene.camera.focus = focus;
+        scene.camera.zoom = zoom;
+        scene
+    }
+
+    #[test]
+    fn easing_ends() {
+        for easing in Easing::ALL {
+            assert_eq!(easing.apply(0.0), 0.0, "{:?}", easing);
+            assert_eq!(easing.apply(1.0), 1.0, "{:?}", easing);
+            let samples: Vec<f64> = (0..=10).map(|i| easing.apply(i as f64 / 10.0)).collect();
+            assert!(samples.windows(2).all(|pair| pair[0] <= pair[1]), "{:?} is not monotonic", easing);
+        }
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn interpolates_zoom_exponentially() {
+        let (from, to) = (scene([0.0, 0.0], 1.0), scene([1.0, -2.0], 100.0));
+        assert_eq!(interpolate(&from, &to, 0.0).camera, from.camera);
+        let end = interpolate(&from, &to, 1.0).camera;
+        assert!((end.zoom - 100.0).abs() < 1e-9);
+        assert!((end.focus[0] - 1.0).abs() < 1e-12 && (end.focus[1] + 2.0).abs() < 1e-12);
+
+        let halfway = interpolate(&from, &to, 0.5).camera;
+        assert!((halfway.zoom - 10.0).abs() < 1e-9);
+        // the focus moved as far as the visible width shrank, 0.9 of the 0.99 in total
+        assert!((halfway.focus[0] - 10.0 / 11.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn interpolates_rotation_the_shorter_way() {
+        let mut from = scene([0.0, 0.0], 1.0);
+        let mut to = from;
+        from.camera.rotation = 0.1;
+        to.camera.rotation = std::f64::consts::TAU - 0.1;
+        let rotation = interpolate(&from, &to, 0.5).camera.rotation;
+        assert!(rotation < 1e-12 || std::f64::consts::TAU - rotation < 1e-12);
+    }
+
+    #[test]
+    fn non_positive_zoom_is_linear() {
+        let (from, to) = (scene([0.0, 0.0], 0.0), scene([1.0, 0.0], 4.0));
+        let camera = interpolate(&from, &to, 0.25).camera;
+        assert_eq!(camera.zoom, 1.0);
+        assert_eq!(camera.focus, [0.25, 0.0]);
+        let camera = interpolate(&to, &scene([0.0, 0.0], -4.0), 0.5).camera;
+        assert_eq!(camera.zoom, 0.0);
+        assert!(camera.focus.iter().all(|value| value.is_finite()));
+    }
+}
@@ -1,9 +1,29 @@
 use std::cell::RefCell;
 use std::rc::Rc;
-use crate::renderer::FractalRenderer;
+use sdl2::event::Event;
+use crate::renderer::{FractalRenderer, LineRenderer, MeshRenderer};
+use crate::ui::bookmarks::Bookmarks;
+use crate::ui::bookmarks_window::BookmarksWindow;
+use crate::ui::buddhabrot_window::BuddhabrotWindow;
+use crate::ui::flame_window::FlameWindow;
+use crate::ui::lsystem_window::LSystemWindow;
+use crate::ui::fractal_3d_window::Fractal3DWindow;
+use crate::ui::height_field_window::HeightFieldWindow;
+use crate::ui::export_window::ExportWindow;
+use crate::ui::explorer_window::ExplorerWindow;
+use crate::ui::animation_window::AnimationWindow;
+use crate::ui::julia_morph_window::JuliaMorphWindow;
+use crate::ui::history::History;
+use crate::ui::history_window::HistoryWindow;
 use crate::ui::input_handler::InputHandler;
+use crate::ui::key_bindings::KeyBindings;
+use crate::ui::key_bindings_window::KeyBindingsWindow;
+use crate::ui::menu_bar::MenuBar;
+use crate::ui::minimap_window::MinimapWindow;
+use crate::ui::orbit_overlay::OrbitOverlay;
 use crate::ui::properties_window::PropertiesWindow;
-use crate::ui::event_observer::Observable;
+use crate::ui::status_bar::StatusBar;
+use crate::ui::event_observer::{Observable, ObserverEvent};
 use crate::ui::window::Window;
 
 /// Main application struct
@@ -14,7 +34,27 @@ use crate::ui::window::Window;
 pub struct Application {
     window: Window,
     fractal_renderer: Rc<RefCell<FractalRenderer>>,
+    line_renderer: LineRenderer,
+    mesh_renderer: MeshRenderer,
     properties_window: Rc<RefCell<PropertiesWindow>>,
+    key_bindings_window: KeyBindingsWindow,
+    history: Rc<RefCell<History>>,
+    history_window: HistoryWindow,
+    bookmarks: Rc<RefCell<Bookmarks>>,
+    bookmarks_window: BookmarksWindow,
+    export_window: ExportWindow,
+    animation_window: AnimationWindow,
+    explorer_window: ExplorerWindow,
+    julia_morph_window: JuliaMorphWindow,
+    minimap_window: MinimapWindow,
+    buddhabrot_window: BuddhabrotWindow,
+    flame_window: FlameWindow,
+    lsystem_window: LSystemWindow,
+    fractal_3d_window: Rc<RefCell<Fractal3DWindow>>,
+    height_field_window: Rc<RefCell<HeightFieldWindow>>,
+    menu_bar: Rc<RefCell<MenuBar>>,
+    orbit_overlay: Rc<RefCell<OrbitOverlay>>,
+    status_bar: StatusBar,
     input_handler: InputHandler,
 }
 
@@ -26,17 +66,73 @@ impl Application {
         // Rc is a reference-counted box for sharing the properties window and fractal renderer
         // between the input handler and the window
         let fractal_renderer = Rc::new(RefCell::new(FractalRenderer::new()));
-        let properties_window = Rc::new(RefCell::new(PropertiesWindow::default()));
-        let mut input_handler = InputHandler::default();
+        // the history is shared between the properties window recording it and the panel showing it
+        let history = Rc::new(RefCell::new(History::default()));
+        let properties_window = Rc::new(RefCell::new(PropertiesWindow::new(history.clone(), window.clipboard())));
+        let mut history_window = HistoryWindow::new(history.clone());
+        let bookmarks = Rc::new(RefCell::new(Bookmarks::load()));
+        let mut bookmarks_window = BookmarksWindow::new(bookmarks.clone());
+        let mut animation_window = AnimationWindow::default();
+        let mut explorer_window = ExplorerWindow::default();
+        let mut julia_morph_window = JuliaMorphWindow::default();
+        let mut minimap_window = MinimapWindow::default();
+        let mut lsystem_window = LSystemWindow::default();
+        let fractal_3d_window = Rc::new(RefCell::new(Fractal3DWindow::default()));
+        let height_field_window = Rc::new(RefCell::new(HeightFieldWindow::default()));
+        let menu_bar = Rc::new(RefCell::new(MenuBar::default()));
+        let orbit_overlay = Rc::new(RefCell::new(OrbitOverlay::default()));
+        let status_bar = StatusBar::new(window.clipboard());
 
-        // register the fractal renderer and properties window as observers
+        // the bindings are shared between the input handler using them and the panel editing them
+        let key_bindings = Rc::new(RefCell::new(KeyBindings::load()));
+        let key_bindings_window = KeyBindingsWindow::new(key_bindings.clone());
+        let mut input_handler = InputHandler::new(key_bindings);
+
+        // register the fractal renderer, properties window and menu bar as observers
         properties_window.borrow_mut().register_observer(fractal_renderer.clone());
         input_handler.register_observer(properties_window.clone());
+        input_handler.register_observer(fractal_renderer.clone());
+        input_handler.register_observer(menu_bar.clone());
+        input_handler.register_observer(orbit_overlay.clone());
+        input_handler.register_observer(fractal_3d_window.clone());
+        input_handler.register_observer(height_field_window.clone());
+        fractal_3d_window.borrow_mut().register_observer(fractal_renderer.clone());
+        history_window.register_observer(properties_window.clone());
+        bookmarks_window.register_observer(properties_window.clone());
+        animation_window.register_observer(properties_window.clone());
+        explorer_window.register_observer(properties_window.clone());
+        julia_morph_window.register_observer(properties_window.clone());
+        minimap_window.register_observer(properties_window.clone());
+        lsystem_window.register_observer(properties_window.clone());
+
+        // the window does not report its initial size, so pass it on manually
+        let (width, height) = window.window.size();
+        input_handler.notify_observers(ObserverEvent::WindowSizeChanged{width: width as i32, height: height as i32});
 
         Self {
             window,
             fractal_renderer: fractal_renderer.clone(),
+            line_renderer: LineRenderer::new(),
+            mesh_renderer: MeshRenderer::new(),
             properties_window: properties_window.clone(),
+            key_bindings_window,
+            history,
+            history_window,
+            bookmarks,
+            bookmarks_window,
+            export_window: ExportWindow::default(),
+            animation_window,
+            explorer_window,
+            julia_morph_window,
+            minimap_window,
+            buddhabrot_window: BuddhabrotWindow::default(),
+            flame_window: FlameWindow::default(),
+            lsystem_window,
+            fractal_3d_window,
+            height_field_window,
+            menu_bar,
+            orbit_overlay,
+            status_bar,
             input_handler,
         }
     }
@@ -44,15 +140,75 @@ impl Application {
     // Process handling events
     pub fn handle_events(&mut self) -> bool {
         let events = self.window.handle_events();
+        // key presses belong to imgui while a text field is focused, so typing does not trigger bindings
+        let text_input = self.window.imgui.io().want_text_input;
         for event in events {
+            if text_input && matches!(event, Event::KeyDown { .. }) { continue }
             if !self.input_handler.handle_input(&event) { return false }
         }
 
         true
     }
 
+    // Advance time dependent state by `dt` seconds
+    pub fn update(&mut self, dt: f64) {
+        self.status_bar.update(dt);
+        self.animation_window.update(dt);
+        self.julia_morph_window.update(dt);
+        self.properties_window.borrow_mut().update(dt);
+        let scene = *self.properties_window.borrow().scene();
+        self.explorer_window.update(dt, &scene);
+
+        // the 3D views take over the main view and its navigation while their windows are open, the height field first
+        let (landscape_shown, shown_3d) = self.shown_3d_views();
+        self.input_handler.set_navigation_3d(landscape_shown || shown_3d);
+        self.height_field_window.borrow_mut().update(dt, landscape_shown);
+        self.fractal_3d_window.borrow_mut().update(dt, shown_3d);
+    }
+
+    /// whether the height field and the 3D fractal are shown in the main view
+    fn shown_3d_views(&self) -> (bool, bool) {
+        let menu_bar = self.menu_bar.borrow();
+        let landscape_shown = menu_bar.height_field_open && self.height_field_window.borrow().shows_landscape();
+        let shown_3d = !landscape_shown && menu_bar.fractal_3d_open && self.fractal_3d_window.borrow().shows_3d();
+        (landscape_shown, shown_3d)
+    }
+
     // Render the window
     pub fn render(&mut self) {
+        // render the missing thumbnails before drawing into the window
+        self.history.borrow_mut().update_thumbnails(&self.fractal_renderer.borrow());
+        self.bookmarks.borrow_mut().update_thumbnails(&self.fractal_renderer.borrow());
+        self.export_window.update(&self.fractal_renderer.borrow());
+        self.animation_window.update_export(&self.fractal_renderer.borrow());
+        self.julia_morph_window.update_export(&self.fractal_renderer.borrow());
+        self.buddhabrot_window.update();
+        self.flame_window.update();
+        if self.menu_bar.borrow().lsystem_open {
+            self.lsystem_window.update(&mut self.line_renderer);
+        }
+        if self.menu_bar.borrow().height_field_open {
+            let scene = *self.properties_window.borrow().scene();
+            self.height_field_window.borrow_mut().update_mesh(&self.fractal_renderer.borrow(), &scene, &mut self.mesh_renderer);
+        }
+        let scene = *self.properties_window.borrow().scene();
+        if self.menu_bar.borrow().explorer_open {
+            self.explorer_window.update_target(&self.fractal_renderer.borrow(), &scene);
+        } else {
+            self.explorer_window.close(&scene);
+        }
+        for keyframe in self.explorer_window.take_keyframes() {
+            self.animation_window.add_keyframe(keyframe);
+        }
+        let max_iterations = self.properties_window.borrow_mut().probe_iterations(&self.fractal_renderer.borrow());
+        if let Some(max_iterations) = max_iterations {
+            self.properties_window.borrow_mut().set_max_iterations(max_iterations);
+        }
+        if self.menu_bar.borrow().minimap_open {
+            let scene = *self.properties_window.borrow().scene();
+            self.minimap_window.update(&self.fractal_renderer.borrow(), &scene);
+        }
+
         // Clear the viewport
         unsafe {
             gl::Viewport(0, 0, self.window.window.size().0 as i32, self.window.window.size().1 as i32);
@@ -60,13 +216,101 @@ impl Application {
             gl::Clear(gl::COLOR_BUFFER_BIT);
         };
 
+        let (landscape_shown, _) = self.shown_3d_views();
+
         // Render imgui gui and fractal
         self.window.platform.prepare_frame(&mut self.window.imgui, &mut self.window.window, &mut self.window.event_pump);
         let mut ui = self.window.imgui.new_frame();
-        self.properties_window.borrow_mut().draw(&mut ui);
+        let mut menu_bar = self.menu_bar.borrow_mut();
+        if menu_bar.ui_visible {
+            menu_bar.draw(ui);
+            self.properties_window.borrow_mut().draw(&mut ui);
+            {
+                let properties_window = self.properties_window.borrow();
+                let (width, height) = self.window.window.size();
+                self.status_bar.draw(ui, properties_window.scene(), properties_window.view_size(), [width, height]);
+            }
+            if menu_bar.bookmarks_open {
+                let scene = *self.properties_window.borrow().scene();
+                self.bookmarks_window.draw(ui, &mut menu_bar.bookmarks_open, &scene);
+            }
+            if menu_bar.export_open {
+                let scene = *self.properties_window.borrow().scene();
+                self.export_window.draw(ui, &mut menu_bar.export_open, &scene);
+            }
+            if menu_bar.animation_open {
+                let scene = *self.properties_window.borrow().scene();
+                self.animation_window.draw(ui, &mut menu_bar.animation_open, &scene);
+            }
+            if menu_bar.explorer_open {
+                let scene = *self.properties_window.borrow().scene();
+                self.explorer_window.draw(ui, &mut menu_bar.explorer_open, &scene);
+            }
+            if menu_bar.julia_morph_open {
+                let scene = *self.properties_window.borrow().scene();
+                self.julia_morph_window.draw(ui, &mut menu_bar.julia_morph_open, &scene);
+            }
+            if menu_bar.buddhabrot_open {
+                let scene = *self.properties_window.borrow().scene();
+                self.buddhabrot_window.draw(ui, &mut menu_bar.buddhabrot_open, &scene);
+            }
+            if menu_bar.flames_open {
+                self.flame_window.draw(ui, &mut menu_bar.flames_open);
+            }
+            if menu_bar.lsystem_open {
+                let scene = *self.properties_window.borrow().scene();
+                self.lsystem_window.draw(ui, &mut menu_bar.lsystem_open, &scene);
+            }
+            if menu_bar.fractal_3d_open {
+                self.fractal_3d_window.borrow_mut().draw(ui, &mut menu_bar.fractal_3d_open);
+            }
+            if menu_bar.height_field_open {
+                self.height_field_window.borrow_mut().draw(ui, &mut menu_bar.height_field_open);
+            }
+            if menu_bar.minimap_open {
+                let scene = *self.properties_window.borrow().scene();
+                self.minimap_window.draw(ui, &mut menu_bar.minimap_open, &scene);
+            }
+            if menu_bar.history_open {
+                self.history_window.draw(ui, &mut menu_bar.history_open);
+            }
+            if menu_bar.key_bindings_open {
+                self.key_bindings_window.draw(ui, &mut menu_bar.key_bindings_open);
+            }
+        }
+        let orbit_overlay_enabled = menu_bar.orbit_overlay;
+        let lsystem_shown = menu_bar.lsystem_open && self.lsystem_window.shows_lines();
+        drop(menu_bar);
+        self.properties_window.borrow_mut().draw_overlay(ui);
+        let (scene, view_size) = {
+            let properties_window = self.properties_window.borrow();
+            (*properties_window.scene(), properties_window.view_size())
+        };
+        self.orbit_overlay.borrow_mut().draw(ui, orbit_overlay_enabled, &scene, view_size);
         let draw_data = self.window.imgui.render();
 
-        self.fractal_renderer.borrow_mut().render(self.window.window.size().0 as f32, self.window.window.size().1 as f32);
+        if landscape_shown {
+            // the landscape replaces the fractal in the whole window
+            let (width, height) = self.window.window.size();
+            unsafe {
+                gl::ClearColor(0.1, 0.1, 0.12, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
+            self.mesh_renderer.render(self.height_field_window.borrow().camera(), width as f32, height as f32);
+        } else if lsystem_shown {
+            // the lines replace the fractal, drawn into the main view with its camera
+            unsafe {
+                gl::Viewport(0, 0, view_size[0] as i32, view_size[1] as i32);
+                gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
+            self.line_renderer.render(&scene.camera, self.lsystem_window.line_color());
+            unsafe {
+                gl::Viewport(0, 0, self.window.window.size().0 as i32, self.window.window.size().1 as i32);
+            }
+        } else {
+            self.fractal_renderer.borrow_mut().render(self.window.window.size().0 as f32, self.window.window.size().1 as f32);
+        }
 
         self.window.renderer.render(draw_data).unwrap();
         self.window.window.gl_swap_window();
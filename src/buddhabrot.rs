@@ -0,0 +1,320 @@
+use std::time::{Duration, Instant};
+use crate::renderer::Camera;
+
+/// How the starting points c are chosen
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sampling {
+    /// uniformly over the disk of radius 2, fine for views of the whole set
+    Uniform,
+    /// Metropolis-Hastings, prefers points whose orbits pass through the view, needed for zoomed views
+    Metropolis,
+}
+
+impl Sampling {
+    pub const ALL: [Sampling; 2] = [Sampling::Uniform, Sampling::Metropolis];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sampling::Uniform => "Uniform",
+            Sampling::Metropolis => "Metropolis-Hastings",
+        }
+    }
+}
+
+/// Curve mapping the normalized density of a pixel to its brightness
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ToneCurve {
+    Linear,
+    SquareRoot,
+    Logarithmic,
+}
+
+impl ToneCurve {
+    pub const ALL: [ToneCurve; 3] = [ToneCurve::Linear, ToneCurve::SquareRoot, ToneCurve::Logarithmic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToneCurve::Linear => "Linear",
+            ToneCurve::SquareRoot => "Square root",
+            ToneCurve::Logarithmic => "Logarithmic",
+        }
+    }
+
+    fn apply(&self, x: f32) -> f32 {
+        match self {
+            ToneCurve::Linear => x,
+            ToneCurve::SquareRoot => x.sqrt(),
+            ToneCurve::Logarithmic => (1.0 + 1000.0 * x).ln() / 1001.0_f32.ln(),
+        }
+    }
+}
+
+/// Settings that change what is accumulated, changing them requires starting over
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DensitySettings {
+    /// iteration limit of the red, green and blue channel, different limits give a Nebulabrot
+    pub limits: [i32; 3],
+    /// accumulate the orbits that stay bounded instead of the escaping ones
+    pub anti: bool,
+    pub sampling: Sampling,
+}
+
+impl Default for DensitySettings {
+    fn default() -> Self {
+        Self {
+            limits: [2000, 200, 20],
+            anti: false,
+            sampling: Sampling::Uniform,
+        }
+    }
+}
+
+/// Settings turning the accumulated density into colors, they can be changed at any time
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ToneMapping {
+    pub curve: ToneCurve,
+    pub exposure: f32,
+    pub gamma: f32,
+    /// scale every channel by its own maximum instead of the maximum of all channels
+    pub normalize_channels: bool,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self {
+            curve: ToneCurve::SquareRoot,
+            exposure: 1.0,
+            gamma: 1.0,
+            normalize_channels: true,
+        }
+    }
+}
+
+/// Density renderer for the Buddhabrot and its variants
+/// accumulates the trajectories of many random starting points into a histogram of the view,
+/// so the image keeps improving the longer it runs
+pub struct Buddhabrot {
+    settings: DensitySettings,
+    camera: Camera,
+    width: i32,
+    height: i32,
+    /// density of the red, green and blue channel for every pixel, rows from top to bottom
+    histogram: Vec<[f32; 3]>,
+    samples: u64,
+
+    rng: fastrand::Rng,
+    orbit: Vec<[f64; 2]>,
+    /// pixels and channels the last traced orbit lands in
+    hits: Vec<(usize, usize)>,
+    /// current state of the Metropolis-Hastings chain
+    chain: Option<ChainState>,
+}
+
+/// Starting point the Metropolis-Hastings chain is at, with the hits of its orbit
+/// the hits are kept, so staying at the point does not iterate its orbit again
+struct ChainState {
+    c: [f64; 2],
+    hits: Vec<(usize, usize)>,
+}
+
+impl Buddhabrot {
+    /// probability of a Metropolis-Hastings step jumping to a uniform random point instead of a nearby one
+    const JUMP_PROBABILITY: f64 = 0.2;
+    /// iterations between checks of the time budget, high limits check after every orbit
+    const BATCH_ITERATIONS: i32 = 100_000;
+
+    /// start accumulating the view of the camera, its width is adjusted to the aspect ratio of the image
+    pub fn new(settings: DensitySettings, camera: Camera, width: i32, height: i32) -> Self {
+        let camera = Camera { width: camera.height * width as f64 / height as f64, ..camera };
+        Self {
+            settings,
+            camera,
+            width,
+            height,
+            histogram: vec![[0.0; 3]; (width * height) as usize],
+            samples: 0,
+            rng: fastrand::Rng::new(),
+            orbit: Vec::new(),
+            hits: Vec::new(),
+            chain: None,
+        }
+    }
+
+    pub fn settings(&self) -> &DensitySettings {
+        &self.settings
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// number of starting points sampled so far
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// sample starting points for the given time
+    pub fn accumulate(&mut self, budget: Duration) {
+        // check the time only every few samples while the orbits are short
+        let limit = self.settings.limits.iter().copied().max().unwrap_or(1).max(1);
+        let batch = (Self::BATCH_ITERATIONS / limit).clamp(1, 64);
+
+        let start = Instant::now();
+        while start.elapsed() < budget {
+            for _ in 0..batch {
+                match self.settings.sampling {
+                    Sampling::Uniform => {
+                        let c = self.random_point();
+                        self.trace(c);
+                        add_hits(&mut self.histogram, &self.hits, 1.0);
+                    },
+                    Sampling::Metropolis => self.metropolis_step(),
+                }
+                self.samples += 1;
+            }
+        }
+    }
+
+    /// uniformly distributed point of the disk of radius 2, which contains the whole set
+    fn random_point(&mut self) -> [f64; 2] {
+        loop {
+            let c = [self.rng.f64() * 4.0 - 2.0, self.rng.f64() * 4.0 - 2.0];
+            if c[0] * c[0] + c[1] * c[1] <= 4.0 {
+                return c;
+            }
+        }
+    }
+
+    /// normally distributed number with mean 0 and standard deviation 1
+    fn gaussian(&mut self) -> f64 {
+        let u = self.rng.f64().max(f64::MIN_POSITIVE);
+        (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * self.rng.f64()).cos()
+    }
+
+    /// one step of the Metropolis-Hastings chain over the starting points
+    /// the chain visits points in proportion to how many of their orbit points land in the view,
+    /// the splats are weighted by the inverse of that, so the density stays the same as with uniform sampling
+    fn metropolis_step(&mut self) {
+        let Some(chain) = &self.chain else {
+            // start the chain at a uniform random point, points outside the view do not count yet
+            let c = self.random_point();
+            self.trace(c);
+            if !self.hits.is_empty() {
+                self.chain = Some(ChainState { c, hits: std::mem::take(&mut self.hits) });
+            }
+            return;
+        };
+        let (current, contribution) = (chain.c, chain.hits.len() as f64);
+
+        let proposal = if self.rng.f64() < Self::JUMP_PROBABILITY {
+            self.random_point()
+        } else {
+            let scale = 0.1 * self.camera.width.min(self.camera.height) / self.camera.zoom;
+            [current[0] + self.gaussian() * scale, current[1] + self.gaussian() * scale]
+        };
+
+        self.trace(proposal);
+        let proposed = self.hits.len() as f64;
+        let accepted = proposed > 0.0 && self.rng.f64() < proposed / contribution;
+        let Some(chain) = &mut self.chain else { return };
+        if accepted {
+            chain.c = proposal;
+            std::mem::swap(&mut chain.hits, &mut self.hits);
+        }
+        add_hits(&mut self.histogram, &chain.hits, 1.0 / chain.hits.len() as f32);
+    }
+
+    /// iterate the starting point `c` into the orbit buffer
+    /// returns the number of iterations before escaping, or `None` if it stayed bounded for the highest limit
+    fn iterate(&mut self, c: [f64; 2]) -> Option<usize> {
+        let limit = self.settings.limits.iter().copied().max().unwrap_or(0).max(0) as usize;
+        self.orbit.clear();
+
+        // points in the main cardioid and the period 2 bulb never escape, skip them right away
+        if !self.settings.anti {
+            let q = (c[0] - 0.25) * (c[0] - 0.25) + c[1] * c[1];
+            if q * (q + c[0] - 0.25) <= 0.25 * c[1] * c[1] || (c[0] + 1.0) * (c[0] + 1.0) + c[1] * c[1] <= 0.0625 {
+                return None;
+            }
+        }
+
+        let mut z = [0.0, 0.0];
+        for iteration in 0..limit {
+            z = [z[0] * z[0] - z[1] * z[1] + c[0], 2.0 * z[0] * z[1] + c[1]];
+            if z[0] * z[0] + z[1] * z[1] > 4.0 {
+                return Some(iteration);
+            }
+            self.orbit.push(z);
+        }
+        None
+    }
+
+
+    /// how many points of the orbit in the buffer a channel accumulates
+    fn channel_length(&self, channel: usize, escape: Option<usize>) -> usize {
+        let limit = self.settings.limits[channel].max(0) as usize;
+        match escape {
+            Some(iterations) if !self.settings.anti && iterations < limit => iterations,
+            Some(iterations) if self.settings.anti && iterations >= limit => limit,
+            None if self.settings.anti => limit.min(self.orbit.len()),
+            _ => 0,
+        }
+    }
+
+    /// iterate `c` and collect the pixels and channels its orbit points land in
+    /// the number of hits is the contribution of the point the Metropolis-Hastings chain is weighted by
+    fn trace(&mut self, c: [f64; 2]) {
+        let escape = self.iterate(c);
+        self.hits.clear();
+        for channel in 0..3 {
+            let length = self.channel_length(channel, escape);
+            for index in 0..length {
+                if let Some(pixel) = self.pixel(self.orbit[index]) {
+                    self.hits.push((pixel, channel));
+                }
+            }
+        }
+    }
+
+    /// index of the pixel the point of the complex plane falls into
+    fn pixel(&self, z: [f64; 2]) -> Option<usize> {
+        let [x, y] = self.camera.complex_to_screen(z, self.width as f64, self.height as f64);
+        if x < 0.0 || y < 0.0 || x >= self.width as f64 || y >= self.height as f64 {
+            return None;
+        }
+        Some(y as usize * self.width as usize + x as usize)
+    }
+
+    /// tone map the density into RGBA pixels, rows from top to bottom
+    pub fn to_rgba(&self, tone: &ToneMapping) -> Vec<u8> {
+        let mut maximum = [0.0f32; 3];
+        for density in &self.histogram {
+            for channel in 0..3 {
+                maximum[channel] = maximum[channel].max(density[channel]);
+            }
+        }
+        if !tone.normalize_channels {
+            maximum = [maximum[0].max(maximum[1]).max(maximum[2]); 3];
+        }
+
+        self.histogram.iter().flat_map(|density| {
+            let [r, g, b] = [0, 1, 2].map(|channel| {
+                let normalized = if maximum[channel] > 0.0 { density[channel] / maximum[channel] } else { 0.0 };
+                let value = tone.curve.apply((normalized * tone.exposure).min(1.0)).powf(1.0 / tone.gamma);
+                (value * 255.0).round() as u8
+            });
+            [r, g, b, 255]
+        }).collect()
+    }
+}
+
+/// add the hits of an orbit to the histogram with the given weight per point
+fn add_hits(histogram: &mut [[f32; 3]], hits: &[(usize, usize)], weight: f32) {
+    for &(pixel, channel) in hits {
+        histogram[pixel][channel] += weight;
+    }
+}
@@ -0,0 +1,145 @@
+/// Measure of how much detail a part of the view holds
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DetailMeasure {
+    /// share of neighbouring pixels whose iteration counts differ, high near the boundary of the set
+    EdgeDensity,
+    /// variance of the logarithm of the smooth iteration count, points inside the set count as the maximum
+    IterationVariance,
+}
+
+impl DetailMeasure {
+    pub const ALL: [DetailMeasure; 2] = [DetailMeasure::EdgeDensity, DetailMeasure::IterationVariance];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DetailMeasure::EdgeDensity => "Edge density",
+            DetailMeasure::IterationVariance => "Iteration variance",
+        }
+    }
+}
+
+/// Detail of the view measured on a grid of cells, rows go from the top to the bottom of the view
+pub struct DetailGrid {
+    pub cells: [usize; 2],
+    pub scores: Vec<f64>,
+}
+
+impl DetailGrid {
+    /// measure the detail of every cell from the escape data layer, four values per pixel of an image of `size` pixels
+    pub fn from_data(size: [usize; 2], data: &[f32], cells: [usize; 2], measure: DetailMeasure, max_iterations: i32) -> Self {
+        let [width, height] = size;
+        let cells = [cells[0].clamp(1, width.max(1)), cells[1].clamp(1, height.max(1))];
+        let pixel = |x: usize, y: usize| &data[(y * width + x) * 4..(y * width + x) * 4 + 4];
+        let max_log = (1.0 + max_iterations.max(1) as f64).ln();
+
+        let mut scores = Vec::with_capacity(cells[0] * cells[1]);
+        for row in 0..cells[1] {
+            for column in 0..cells[0] {
+                let xs = column * width / cells[0]..(column + 1) * width / cells[0];
+                let ys = row * height / cells[1]..(row + 1) * height / cells[1];
+
+                let score = match measure {
+                    DetailMeasure::EdgeDensity => {
+                        // compare every pixel to its right and lower neighbour inside the cell
+                        let [mut edges, mut pairs] = [0usize, 0usize];
+                        for y in ys.clone() {
+                            for x in xs.clone() {
+                                let here = pixel(x, y);
+                                for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                                    if !xs.contains(&nx) || !ys.contains(&ny) {
+                                        continue;
+                                    }
+                                    let there = pixel(nx, ny);
+                                    pairs += 1;
+                                    if here[2] != there[2] || here[3] != there[3] {
+                                        edges += 1;
+                                    }
+                                }
+                            }
+                        }
+                        edges as f64 / pairs.max(1) as f64
+                    },
+                    DetailMeasure::IterationVariance => {
+                        let values: Vec<f64> = ys.clone()
+                            .flat_map(|y| xs.clone().map(move |x| (x, y)))
+                            .map(|(x, y)| {
+                                let values = pixel(x, y);
+                                if values[2] == 0.0 { 1.0 } else { (1.0 + values[0].max(0.0) as f64).ln() / max_log }
+                            })
+                            .collect();
+                        let count = values.len().max(1) as f64;
+                        let mean = values.iter().sum::<f64>() / count;
+                        values.iter().map(|value| (value - mean) * (value - mean)).sum::<f64>() / count
+                    },
+                };
+                scores.push(score);
+            }
+        }
+
+        Self { cells, scores }
+    }
+
+    /// pick a cell at random, weighted by the square of its score so the most detailed cells are preferred
+    /// returns the center of the cell in normalized screen coordinates, (0, 0) in the bottom left corner,
+    /// or nothing if the whole view is without detail
+    pub fn pick(&self, rng: &mut fastrand::Rng) -> Option<[f64; 2]> {
+        let best = self.scores.iter().cloned().fold(0.0, f64::max);
+        if best <= 1e-6 {
+            return None;
+        }
+
+        // cells with little detail compared to the best one would lead into plain areas
+        let weights: Vec<f64> = self.scores.iter()
+            .map(|score| if *score >= best * 0.25 { score * score } else { 0.0 })
+            .collect();
+        let mut choice = rng.f64() * weights.iter().sum::<f64>();
+        let index = weights.iter().position(|weight| {
+            choice -= weight;
+            *weight > 0.0 && choice <= 0.0
+        }).unwrap_or_else(|| weights.iter().rposition(|weight| *weight > 0.0).unwrap_or(0));
+
+        let [column, row] = [index % self.cells[0], index / self.cells[0]];
+        Some([
+            (column as f64 + 0.5) / self.cells[0] as f64,
+            1.0 - (row as f64 + 0.5) / self.cells[1] as f64,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// escape data of an 8 by 8 image that escapes after 10 iterations everywhere but in the cell at `detailed`
+    /// of a 4 by 4 grid, where every other pixel belongs to the set
+    fn data(detailed: Option<[usize; 2]>) -> Vec<f32> {
+        let mut data = Vec::new();
+        for y in 0..8 {
+            for x in 0..8 {
+                let inside = detailed == Some([x / 2, y / 2]) && (x + y) % 2 == 0;
+                data.extend(if inside { [0.0, 0.0, 0.0, 100.0] } else { [10.0, 0.0, 1.0, 10.0] });
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn picks_the_detailed_cell() {
+        for measure in DetailMeasure::ALL {
+            let grid = DetailGrid::from_data([8, 8], &data(Some([1, 0])), [4, 4], measure, 100);
+            assert_eq!(grid.scores.iter().filter(|score| **score > 0.0).count(), 1);
+            for seed in 0..10 {
+                // the cell in the second column of the top row
+                assert_eq!(grid.pick(&mut fastrand::Rng::with_seed(seed)), Some([0.375, 0.875]));
+            }
+        }
+    }
+
+    #[test]
+    fn flat_view_has_no_pick() {
+        for measure in DetailMeasure::ALL {
+            let grid = DetailGrid::from_data([8, 8], &data(None), [4, 4], measure, 100);
+            assert_eq!(grid.pick(&mut fastrand::Rng::with_seed(0)), None);
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use std::f64::consts::PI;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// Nonlinear functions applied after the affine part of a transform, named and numbered like in flam3
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Variation {
+    Linear,
+    Sinusoidal,
+    Spherical,
+    Swirl,
+    Horseshoe,
+    Polar,
+    Handkerchief,
+    Heart,
+    Disc,
+    Spiral,
+    Hyperbolic,
+    Diamond,
+    Bent,
+}
+
+impl Variation {
+    pub const ALL: [Variation; 13] = [
+        Variation::Linear, Variation::Sinusoidal, Variation::Spherical, Variation::Swirl, Variation::Horseshoe,
+        Variation::Polar, Variation::Handkerchief, Variation::Heart, Variation::Disc, Variation::Spiral,
+        Variation::Hyperbolic, Variation::Diamond, Variation::Bent,
+    ];
+
+    /// name of the variation, also the attribute holding its weight in flame files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variation::Linear => "linear",
+            Variation::Sinusoidal => "sinusoidal",
+            Variation::Spherical => "spherical",
+            Variation::Swirl => "swirl",
+            Variation::Horseshoe => "horseshoe",
+            Variation::Polar => "polar",
+            Variation::Handkerchief => "handkerchief",
+            Variation::Heart => "heart",
+            Variation::Disc => "disc",
+            Variation::Spiral => "spiral",
+            Variation::Hyperbolic => "hyperbolic",
+            Variation::Diamond => "diamond",
+            Variation::Bent => "bent",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Variation> {
+        Self::ALL.into_iter().find(|variation| variation.name() == name)
+    }
+
+    /// apply the variation to a point, the formulas follow the flame paper
+    fn apply(&self, [x, y]: [f64; 2]) -> [f64; 2] {
+        let r2 = x * x + y * y + 1e-12;
+        let r = r2.sqrt();
+        // the angle is measured from the y axis like in flam3
+        let theta = x.atan2(y);
+
+        match self {
+            Variation::Linear => [x, y],
+            Variation::Sinusoidal => [x.sin(), y.sin()],
+            Variation::Spherical => [x / r2, y / r2],
+            Variation::Swirl => [x * r2.sin() - y * r2.cos(), x * r2.cos() + y * r2.sin()],
+            Variation::Horseshoe => [(x - y) * (x + y) / r, 2.0 * x * y / r],
+            Variation::Polar => [theta / PI, r - 1.0],
+            Variation::Handkerchief => [r * (theta + r).sin(), r * (theta - r).cos()],
+            Variation::Heart => [r * (theta * r).sin(), -r * (theta * r).cos()],
+            Variation::Disc => [theta / PI * (PI * r).sin(), theta / PI * (PI * r).cos()],
+            Variation::Spiral => [(theta.cos() + r.sin()) / r, (theta.sin() - r.cos()) / r],
+            Variation::Hyperbolic => [theta.sin() / r, r * theta.cos()],
+            Variation::Diamond => [theta.sin() * r.cos(), theta.cos() * r.sin()],
+            Variation::Bent => [if x < 0.0 { 2.0 * x } else { x }, if y < 0.0 { y / 2.0 } else { y }],
+        }
+    }
+}
+
+/// One function of an iterated function system, an affine map followed by a weighted sum of variations
+#[derive(Clone, PartialEq, Debug)]
+pub struct Transform {
+    /// relative probability of the transform being picked by the chaos game
+    pub weight: f64,
+    /// position in the palette the color of a point moves halfway towards when the transform is applied
+    pub color: f64,
+    /// affine coefficients in the order of flame files, x' = c0 x + c2 y + c4 and y' = c1 x + c3 y + c5
+    pub coefs: [f64; 6],
+    pub variations: Vec<(Variation, f64)>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            weight: 0.5,
+            color: 0.0,
+            coefs: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            variations: vec![(Variation::Linear, 1.0)],
+        }
+    }
+}
+
+impl Transform {
+    /// purely affine transform, written as x' = a x + b y + e and y' = c x + d y + f like most IFS tables
+    fn affine(weight: f64, color: f64, [a, b, c, d, e, f]: [f64; 6]) -> Self {
+        Self { weight, color, coefs: [a, c, b, d, e, f], ..Self::default() }
+    }
+
+    fn apply(&self, [x, y]: [f64; 2]) -> [f64; 2] {
+        let c = &self.coefs;
+        let point = [c[0] * x + c[2] * y + c[4], c[1] * x + c[3] * y + c[5]];
+
+        self.variations.iter().fold([0.0, 0.0], |sum, (variation, weight)| {
+            let [vx, vy] = variation.apply(point);
+            [sum[0] + weight * vx, sum[1] + weight * vy]
+        })
+    }
+}
+
+/// Iterated function system or fractal flame, with everything needed to render it
+/// the y axis points down like in flame files, so they look the same as in other flame renderers
+#[derive(Clone, PartialEq, Debug)]
+pub struct Flame {
+    pub name: String,
+    pub transforms: Vec<Transform>,
+    /// 256 colors the color index of the points is looked up in
+    pub palette: Vec<[u8; 3]>,
+    /// point in the middle of the image
+    pub center: [f64; 2],
+    /// pixels per unit
+    pub scale: f64,
+    /// width and height of the image in pixels
+    pub size: [i32; 2],
+    pub brightness: f32,
+    pub gamma: f32,
+}
+
+impl Default for Flame {
+    fn default() -> Self {
+        Self::swirl()
+    }
+}
+
+impl Flame {
+    /// number of colors in a palette
+    pub const PALETTE_SIZE: usize = 256;
+    /// smallest and largest width or height of the image in pixels
+    pub const MIN_SIZE: i32 = 16;
+    pub const MAX_SIZE: i32 = 4096;
+
+    /// the presets shown in the flame window
+    pub fn presets() -> [Flame; 3] {
+        [Self::barnsley_fern(), Self::sierpinski(), Self::swirl()]
+    }
+
+    fn preset(name: &str, transforms: Vec<Transform>, center: [f64; 2], units: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            transforms,
+            palette: Self::rainbow_palette(),
+            center,
+            scale: 600.0 / units,
+            size: [600, 600],
+            brightness: 4.0,
+            gamma: 2.5,
+        }
+    }
+
+    /// the fern of Barnsley, mirrored vertically so it stands upright
+    pub fn barnsley_fern() -> Self {
+        Self::preset("Barnsley fern", vec![
+            Transform::affine(0.01, 0.0, [0.0, 0.0, 0.0, 0.16, 0.0, 0.0]),
+            Transform::affine(0.85, 0.3, [0.85, -0.04, 0.04, 0.85, 0.0, -1.6]),
+            Transform::affine(0.07, 0.6, [0.2, 0.26, -0.23, 0.22, 0.0, -1.6]),
+            Transform::affine(0.07, 0.9, [-0.15, -0.28, -0.26, 0.24, 0.0, -0.44]),
+        ], [0.0, -5.0], 11.0)
+    }
+
+    pub fn sierpinski() -> Self {
+        Self::preset("Sierpinski triangle", vec![
+            Transform::affine(1.0, 0.0, [0.5, 0.0, 0.0, 0.5, 0.0, 0.0]),
+            Transform::affine(1.0, 0.5, [0.5, 0.0, 0.0, 0.5, 0.5, 0.0]),
+            Transform::affine(1.0, 1.0, [0.5, 0.0, 0.0, 0.5, 0.25, -0.433]),
+        ], [0.5, -0.433], 1.1)
+    }
+
+    /// a small flame mixing several variations
+    pub fn swirl() -> Self {
+        Self::preset("Swirl", vec![
+            Transform {
+                weight: 0.5,
+                color: 0.0,
+                coefs: [0.56, -0.41, 0.41, 0.56, 0.2, 0.1],
+                variations: vec![(Variation::Linear, 0.5), (Variation::Swirl, 0.5)],
+            },
+            Transform {
+                weight: 0.3,
+                color: 0.5,
+                coefs: [-0.3, 0.7, -0.6, -0.2, 0.6, -0.3],
+                variations: vec![(Variation::Spherical, 1.0)],
+            },
+            Transform {
+                weight: 0.2,
+                color: 1.0,
+                coefs: [0.5, 0.0, 0.0, 0.5, -0.5, 0.5],
+                variations: vec![(Variation::Sinusoidal, 1.0)],
+            },
+        ], [0.0, 0.0], 4.0)
+    }
+
+    /// palette going once around the hue circle
+    pub fn rainbow_palette() -> Vec<[u8; 3]> {
+        (0..Self::PALETTE_SIZE).map(|index| {
+            let hue = index as f32 / Self::PALETTE_SIZE as f32 * 6.0;
+            let channel = |offset: f32| {
+                let k = (offset + hue).rem_euclid(6.0);
+                ((1.0 - k.min(4.0 - k).clamp(0.0, 1.0)) * 255.0).round() as u8
+            };
+            [channel(5.0), channel(3.0), channel(1.0)]
+        }).collect()
+    }
+
+    /// whether rendering `other` gives a different density than rendering this flame,
+    /// the brightness and gamma only change the tone mapping
+    pub fn same_density(&self, other: &Flame) -> bool {
+        self.transforms == other.transforms && self.palette == other.palette
+            && self.center == other.center && self.scale == other.scale && self.size == other.size
+    }
+
+    /// write the flame in the XML format of flam3 and Apophysis
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        let _ = writeln!(xml, "<flame name=\"{}\" version=\"fractal_renderer\" size=\"{} {}\" center=\"{} {}\" scale=\"{}\" brightness=\"{}\" gamma=\"{}\">",
+            escape(&self.name), self.size[0], self.size[1], self.center[0], self.center[1], self.scale, self.brightness, self.gamma);
+
+        for transform in &self.transforms {
+            let coefs: Vec<String> = transform.coefs.iter().map(|coef| coef.to_string()).collect();
+            let _ = write!(xml, "   <xform weight=\"{}\" color=\"{}\" coefs=\"{}\"", transform.weight, transform.color, coefs.join(" "));
+            for (variation, weight) in &transform.variations {
+                let _ = write!(xml, " {}=\"{}\"", variation.name(), weight);
+            }
+            let _ = writeln!(xml, "/>");
+        }
+
+        for (index, color) in self.palette.iter().enumerate() {
+            let _ = writeln!(xml, "   <color index=\"{}\" rgb=\"{} {} {}\"/>", index, color[0], color[1], color[2]);
+        }
+        xml.push_str("</flame>\n");
+        xml
+    }
+
+    /// read the first flame of a flam3 or Apophysis file
+    /// final transforms, post transforms and rotation are not supported and ignored
+    pub fn from_xml(text: &str) -> Result<Flame, String> {
+        let document = roxmltree::Document::parse(text).map_err(|err| err.to_string())?;
+        let node = document.descendants().find(|node| node.has_tag_name("flame")).ok_or("no <flame> element found")?;
+
+        let mut flame = Flame {
+            name: node.attribute("name").unwrap_or("Untitled").to_string(),
+            transforms: Vec::new(),
+            palette: Self::rainbow_palette(),
+            ..Flame::default()
+        };
+        if let Some(size) = node.attribute("size") {
+            flame.size = parse_numbers(size, "size")?;
+            if flame.size.iter().any(|size| !(Self::MIN_SIZE..=Self::MAX_SIZE).contains(size)) {
+                return Err(format!("size `{}` is outside of {} to {} pixels", size, Self::MIN_SIZE, Self::MAX_SIZE));
+            }
+        }
+        if let Some(center) = node.attribute("center") {
+            flame.center = parse_numbers(center, "center")?;
+        }
+        if let Some(scale) = node.attribute("scale") {
+            [flame.scale] = parse_numbers(scale, "scale")?;
+        }
+        if let Some(brightness) = node.attribute("brightness") {
+            [flame.brightness] = parse_numbers(brightness, "brightness")?;
+        }
+        if let Some(gamma) = node.attribute("gamma") {
+            [flame.gamma] = parse_numbers(gamma, "gamma")?;
+        }
+
+        for child in node.children().filter(|child| child.is_element()) {
+            match child.tag_name().name() {
+                "xform" => flame.transforms.push(parse_transform(child)?),
+                "color" => {
+                    let [index]: [usize; 1] = parse_numbers(child.attribute("index").unwrap_or(""), "color index")?;
+                    let rgb: [f32; 3] = parse_numbers(child.attribute("rgb").unwrap_or(""), "color")?;
+                    if let Some(color) = flame.palette.get_mut(index) {
+                        *color = rgb.map(|channel| channel.round().clamp(0.0, 255.0) as u8);
+                    }
+                },
+                // newer files store the palette as hex digits, six per color
+                "palette" => {
+                    let digits: String = child.text().unwrap_or("").chars().filter(|c| !c.is_whitespace()).collect();
+                    for (color, hex) in flame.palette.iter_mut().zip(digits.as_bytes().chunks_exact(6)) {
+                        let value = std::str::from_utf8(hex).ok().and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                            .ok_or("invalid hex palette")?;
+                        *color = [value >> 16, value >> 8, value].map(|channel| (channel & 0xff) as u8);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        if flame.transforms.is_empty() {
+            return Err("the flame has no transforms".to_string());
+        }
+        Ok(flame)
+    }
+}
+
+fn parse_transform(node: roxmltree::Node) -> Result<Transform, String> {
+    let mut transform = Transform { variations: Vec::new(), ..Transform::default() };
+    for attribute in node.attributes() {
+        let value = attribute.value();
+        match attribute.name() {
+            "weight" => [transform.weight] = parse_numbers(value, "weight")?,
+            // some files store a second color coordinate, only the first one is used
+            "color" => transform.color = value.split_whitespace().next().and_then(|color| color.parse().ok())
+                .ok_or(format!("invalid color `{}`", value))?,
+            "coefs" => transform.coefs = parse_numbers(value, "coefs")?,
+            name => if let Some(variation) = Variation::from_name(name) {
+                let [weight] = parse_numbers(value, name)?;
+                transform.variations.push((variation, weight));
+            },
+        }
+    }
+    Ok(transform)
+}
+
+/// parse exactly `N` numbers separated by whitespace
+fn parse_numbers<T: std::str::FromStr, const N: usize>(text: &str, name: &str) -> Result<[T; N], String> {
+    let values: Vec<T> = text.split_whitespace()
+        .map(|value| value.parse().map_err(|_| format!("invalid {} `{}`", name, text)))
+        .collect::<Result<_, _>>()?;
+    values.try_into().map_err(|_| format!("expected {} values for {}, got `{}`", N, name, text))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Chaos game renderer for flames
+/// a point jumps between randomly picked transforms and every position it visits is counted in a histogram
+/// together with the palette color it carries, so the image keeps improving the longer it runs
+pub struct FlameRenderer {
+    flame: Flame,
+    /// summed color and number of hits of every pixel, rows from top to bottom
+    histogram: Vec<[f32; 4]>,
+    samples: u64,
+
+    rng: fastrand::Rng,
+    /// running sums of the transform weights, for picking transforms
+    cumulative_weights: Vec<f64>,
+}
+
+impl FlameRenderer {
+    /// iterations after every restart of the point before it is drawn, so it has settled onto the attractor
+    const FUSE: usize = 20;
+    /// iterations before the point starts over at a random position
+    const RUN_LENGTH: usize = 10_000;
+
+    pub fn new(flame: &Flame) -> Self {
+        let [width, height] = flame.size.map(|size| size.clamp(1, Flame::MAX_SIZE));
+        let mut total = 0.0;
+        let cumulative_weights = flame.transforms.iter().map(|transform| {
+            total += transform.weight.max(0.0);
+            total
+        }).collect();
+
+        Self {
+            flame: Flame { size: [width, height], ..flame.clone() },
+            histogram: vec![[0.0; 4]; (width * height) as usize],
+            samples: 0,
+            rng: fastrand::Rng::new(),
+            cumulative_weights,
+        }
+    }
+
+    pub fn flame(&self) -> &Flame {
+        &self.flame
+    }
+
+    /// number of points drawn so far
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// play the chaos game for the given time
+    pub fn accumulate(&mut self, budget: Duration) {
+        let total = self.cumulative_weights.last().copied().unwrap_or(0.0);
+        if total <= 0.0 {
+            return;
+        }
+
+        let start = Instant::now();
+        while start.elapsed() < budget {
+            let mut point = [self.rng.f64() * 2.0 - 1.0, self.rng.f64() * 2.0 - 1.0];
+            let mut color = self.rng.f64();
+
+            for iteration in 0..Self::RUN_LENGTH {
+                let pick = self.rng.f64() * total;
+                let index = self.cumulative_weights.partition_point(|weight| *weight <= pick).min(self.flame.transforms.len() - 1);
+                let transform = &self.flame.transforms[index];
+                point = transform.apply(point);
+                color = (color + transform.color) / 2.0;
+
+                if !point[0].is_finite() || !point[1].is_finite() {
+                    break;
+                }
+                if iteration >= Self::FUSE {
+                    self.splat(point, color);
+                }
+            }
+        }
+    }
+
+    fn splat(&mut self, point: [f64; 2], color: f64) {
+        let [width, height] = self.flame.size;
+        let x = (point[0] - self.flame.center[0]) * self.flame.scale + width as f64 / 2.0;
+        let y = (point[1] - self.flame.center[1]) * self.flame.scale + height as f64 / 2.0;
+        self.samples += 1;
+        if x < 0.0 || y < 0.0 || x >= width as f64 || y >= height as f64 {
+            return;
+        }
+
+        let palette = &self.flame.palette;
+        let [r, g, b] = palette[((color.clamp(0.0, 1.0) * (palette.len() - 1) as f64) as usize).min(palette.len() - 1)]
+            .map(|channel| channel as f32 / 255.0);
+        let bin = &mut self.histogram[y as usize * width as usize + x as usize];
+        *bin = [bin[0] + r, bin[1] + g, bin[2] + b, bin[3] + 1.0];
+    }
+
+    /// tone map the histogram with log density into RGBA pixels, rows from top to bottom
+    /// the brightness of a pixel grows with the logarithm of its hits, its color is the average of them
+    pub fn to_rgba(&self, brightness: f32, gamma: f32) -> Vec<u8> {
+        let maximum = self.histogram.iter().map(|bin| bin[3]).fold(0.0, f32::max);
+        let scale = if maximum > 0.0 { brightness / (1.0 + maximum).ln() } else { 0.0 };
+
+        self.histogram.iter().flat_map(|bin| {
+            let hits = bin[3];
+            if hits <= 0.0 {
+                return [0, 0, 0, 255];
+            }
+            let alpha = ((1.0 + hits).ln() * scale).min(1.0).powf(1.0 / gamma.max(0.01));
+            let [r, g, b] = [0, 1, 2].map(|channel| (bin[channel] / hits * alpha * 255.0).round().min(255.0) as u8);
+            [r, g, b, 255]
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml_round_trip() {
+        let mut flame = Flame::swirl();
+        flame.name = String::from("Swirl & \"friends\" <1>");
+        flame.size = [640, 480];
+        flame.center = [0.125, -1.0 / 3.0];
+        flame.palette[7] = [1, 2, 3];
+        flame.transforms[1].variations.push((Variation::Bent, 0.25));
+
+        assert_eq!(Flame::from_xml(&flame.to_xml()).unwrap(), flame);
+    }
+
+    #[test]
+    fn presets_round_trip() {
+        for flame in Flame::presets() {
+            assert_eq!(Flame::from_xml(&flame.to_xml()).unwrap(), flame);
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_sizes() {
+        let xml = |size: &str| format!("<flame size=\"{}\"><xform weight=\"1\" coefs=\"1 0 0 1 0 0\" linear=\"1\"/></flame>", size);
+        assert!(Flame::from_xml(&xml("800 600")).is_ok());
+        assert!(Flame::from_xml(&xml("100000 100000")).is_err());
+        assert!(Flame::from_xml(&xml("0 600")).is_err());
+        assert!(Flame::from_xml(&xml("-5 600")).is_err());
+    }
+
+    #[test]
+    fn rejects_flames_without_transforms() {
+        assert!(Flame::from_xml("<flame size=\"100 100\"></flame>").is_err());
+        assert!(Flame::from_xml("not xml").is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt::Write;
+
+/// Value of the escape-time data the heights are taken from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HeightSource {
+    /// logarithm of the smooth iteration count, the set is a plateau at the top
+    SmoothIterations,
+    /// logarithm of the distance estimate, the terrain rises towards the boundary of the set
+    DistanceEstimate,
+}
+
+impl HeightSource {
+    pub const ALL: [HeightSource; 2] = [HeightSource::SmoothIterations, HeightSource::DistanceEstimate];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HeightSource::SmoothIterations => "Smooth iterations",
+            HeightSource::DistanceEstimate => "Distance estimate",
+        }
+    }
+}
+
+/// Grid of heights from 0 to 1 sampled over the view, with the color of the rendered fractal at every sample
+/// rows go from the top to the bottom of the view
+pub struct HeightField {
+    pub size: [usize; 2],
+    pub heights: Vec<f32>,
+    pub colors: Vec<[f32; 3]>,
+}
+
+/// Triangle mesh with a normal and a color per vertex, triangles wind counter-clockwise seen from outside
+#[derive(Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub colors: Vec<[f32; 3]>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl HeightField {
+    /// build the heights from the escape data layer, four values per sample, and the RGBA colors of the same samples
+    /// `pixel_size` is the size of a sample on the complex plane, distances are measured in samples
+    pub fn from_data(size: [usize; 2], data: &[f32], colors: &[u8], source: HeightSource, max_iterations: i32, pixel_size: f64) -> Self {
+        let max_log = (1.0 + max_iterations.max(1) as f32).ln();
+        let width_log = (1.0 + size[0] as f32).ln();
+
+        let heights = data.chunks_exact(4).map(|values| {
+            let [iterations, distance, escaped, _] = [values[0], values[1], values[2], values[3]];
+            if escaped == 0.0 {
+                return 1.0;
+            }
+            match source {
+                HeightSource::SmoothIterations => ((1.0 + iterations.max(0.0)).ln() / max_log).min(1.0),
+                HeightSource::DistanceEstimate => {
+                    let samples = (distance as f64 / pixel_size).max(0.0) as f32;
+                    1.0 - ((1.0 + samples).ln() / width_log).min(1.0)
+                },
+            }
+        }).collect();
+        let colors = colors.chunks_exact(4).map(|pixel| [0, 1, 2].map(|channel| pixel[channel] as f32 / 255.0)).collect();
+
+        Self { size, heights, colors }
+    }
+
+    /// closed solid of the terrain standing on a flat base, `x` goes from -1 to 1 across the view and `y` points up
+    /// `height_scale` is the height of the tallest peak and `base` the thickness below the lowest point
+    pub fn mesh(&self, height_scale: f32, base: f32) -> Mesh {
+        let [width, height] = self.size;
+        let mut mesh = Mesh::default();
+        if width < 2 || height < 2 {
+            return mesh;
+        }
+
+        // the top surface shares its vertices, so its normals are smooth
+        let depth = (height - 1) as f32 / (width - 1) as f32;
+        for row in 0..height {
+            for column in 0..width {
+                let x = 2.0 * column as f32 / (width - 1) as f32 - 1.0;
+                let z = (2.0 * row as f32 / (height - 1) as f32 - 1.0) * depth;
+                mesh.positions.push([x, self.heights[row * width + column] * height_scale, z]);
+                mesh.colors.push(self.colors[row * width + column]);
+            }
+        }
+        let index = |column: usize, row: usize| (row * width + column) as u32;
+        for row in 0..height - 1 {
+            for column in 0..width - 1 {
+                mesh.triangles.push([index(column, row), index(column, row + 1), index(column + 1, row)]);
+                mesh.triangles.push([index(column + 1, row), index(column, row + 1), index(column + 1, row + 1)]);
+            }
+        }
+
+        // walk around the border, the walls and the bottom get their own vertices, so their edges stay sharp
+        let border: Vec<usize> = (0..width).map(|column| index(column, 0))
+            .chain((1..height).map(|row| index(width - 1, row)))
+            .chain((0..width - 1).rev().map(|column| index(column, height - 1)))
+            .chain((1..height - 1).rev().map(|row| index(0, row)))
+            .map(|vertex| vertex as usize)
+            .collect();
+        let floor = -base;
+        for (position, &top) in border.iter().enumerate() {
+            let next = border[(position + 1) % border.len()];
+            let [a, b] = [mesh.positions[top], mesh.positions[next]];
+            let color = mesh.colors[top].map(|channel| channel * 0.6);
+            let first = mesh.positions.len() as u32;
+            mesh.positions.extend([a, b, [b[0], floor, b[2]], [a[0], floor, a[2]]]);
+            mesh.colors.extend([color; 4]);
+            let outward = [(a[0] + b[0]) / 2.0, 0.0, (a[2] + b[2]) / 2.0];
+            mesh.push_oriented([first, first + 2, first + 1], outward);
+            mesh.push_oriented([first, first + 3, first + 2], outward);
+        }
+
+        // fan around the middle of the bottom
+        let center = mesh.positions.len() as u32;
+        mesh.positions.push([0.0, floor, 0.0]);
+        mesh.colors.push([0.2, 0.2, 0.2]);
+        for &top in &border {
+            let [x, _, z] = mesh.positions[top];
+            mesh.positions.push([x, floor, z]);
+            mesh.colors.push([0.2, 0.2, 0.2]);
+        }
+        for position in 0..border.len() as u32 {
+            let next = (position + 1) % border.len() as u32;
+            mesh.push_oriented([center, center + 1 + position, center + 1 + next], [0.0, -1.0, 0.0]);
+        }
+
+        mesh.compute_normals();
+        mesh
+    }
+}
+
+impl Mesh {
+    /// add a triangle, flipped if it does not face the outward direction
+    fn push_oriented(&mut self, triangle: [u32; 3], outward: [f32; 3]) {
+        let normal = self.face_normal(triangle);
+        if dot(normal, outward) < 0.0 {
+            self.triangles.push([triangle[0], triangle[2], triangle[1]]);
+        } else {
+            self.triangles.push(triangle);
+        }
+    }
+
+    /// normal of a triangle scaled by twice its area
+    fn face_normal(&self, [a, b, c]: [u32; 3]) -> [f32; 3] {
+        let [a, b, c] = [a, b, c].map(|vertex| self.positions[vertex as usize]);
+        let [u, v] = [[0, 1, 2].map(|i| b[i] - a[i]), [0, 1, 2].map(|i| c[i] - a[i])];
+        [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]]
+    }
+
+    /// vertex normals averaged over the adjacent triangles, weighted by their area
+    fn compute_normals(&mut self) {
+        let mut normals = vec![[0.0f32; 3]; self.positions.len()];
+        for &triangle in &self.triangles {
+            let normal = self.face_normal(triangle);
+            for vertex in triangle {
+                let sum = &mut normals[vertex as usize];
+                *sum = [0, 1, 2].map(|i| sum[i] + normal[i]);
+            }
+        }
+        self.normals = normals.into_iter().map(normalize).collect();
+    }
+
+    /// Wavefront OBJ text with vertex colors after the positions, an extension most programs read
+    pub fn to_obj(&self) -> String {
+        let mut obj = String::from("# height field of the escape time\n");
+        for (position, color) in self.positions.iter().zip(&self.colors) {
+            let _ = writeln!(obj, "v {:.6} {:.6} {:.6} {:.4} {:.4} {:.4}", position[0], position[1], position[2], color[0], color[1], color[2]);
+        }
+        for normal in &self.normals {
+            let _ = writeln!(obj, "vn {:.5} {:.5} {:.5}", normal[0], normal[1], normal[2]);
+        }
+        // OBJ indices start at 1
+        for triangle in &self.triangles {
+            let [a, b, c] = triangle.map(|vertex| vertex + 1);
+            let _ = writeln!(obj, "f {}//{} {}//{} {}//{}", a, a, b, b, c, c);
+        }
+        obj
+    }
+
+    /// binary STL, a header followed by every triangle with its face normal
+    pub fn to_stl(&self) -> Vec<u8> {
+        let mut stl = Vec::with_capacity(84 + self.triangles.len() * 50);
+        let mut header = [0u8; 80];
+        let title = b"height field of the escape time";
+        header[..title.len()].copy_from_slice(title);
+        stl.extend_from_slice(&header);
+        stl.extend_from_slice(&(self.triangles.len() as u32).to_le_bytes());
+
+        for &triangle in &self.triangles {
+            let normal = normalize(self.face_normal(triangle));
+            let corners = triangle.map(|vertex| self.positions[vertex as usize]);
+            for value in [normal, corners[0], corners[1], corners[2]].iter().flatten() {
+                stl.extend_from_slice(&value.to_le_bytes());
+            }
+            // attribute byte count, unused
+            stl.extend_from_slice(&[0, 0]);
+        }
+        stl
+    }
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(vector: [f32; 3]) -> [f32; 3] {
+    let length = dot(vector, vector).sqrt();
+    if length > 0.0 { vector.map(|value| value / length) } else { [0.0, 1.0, 0.0] }
+}
@@ -0,0 +1,114 @@
+use std::f32::consts::TAU;
+
+/// Closed path through the parameter plane of the Julia sets
+/// animating the julia constant along it morphs one Julia set into the next
+///
+/// every path is sampled with `t` from 0 to 1 and returns to its start at `t = 1`,
+/// so the resulting animations loop without a jump
+#[derive(Clone, PartialEq, Debug)]
+pub enum JuliaPath {
+    Circle { center: [f32; 2], radius: f32 },
+    /// goes from `from` to `to` and back again
+    Line { from: [f32; 2], to: [f32; 2] },
+    /// smooth closed curve through the control points
+    Spline { points: Vec<[f32; 2]> },
+    /// path recorded by moving the mouse over the Mandelbrot set, played back at constant speed
+    Trail { points: Vec<[f32; 2]> },
+    /// boundary of the main cardioid of the Mandelbrot set, scaled towards its cusp by `scale`
+    /// at 1.0 the path passes through all the bulbs attached to the cardioid
+    MainCardioid { scale: f32 },
+    /// boundary of the period 2 bulb, the disk of radius 1/4 around -1, scaled by `scale`
+    PeriodTwoBulb { scale: f32 },
+}
+
+impl JuliaPath {
+    /// one path of every kind with reasonable parameters, used to switch between kinds
+    pub fn defaults() -> [JuliaPath; 6] {
+        [
+            JuliaPath::MainCardioid { scale: 0.98 },
+            JuliaPath::PeriodTwoBulb { scale: 0.98 },
+            JuliaPath::Circle { center: [0.0, 0.0], radius: 0.7885 },
+            JuliaPath::Line { from: [-0.8, 0.156], to: [-0.7, 0.35] },
+            JuliaPath::Spline { points: Vec::new() },
+            JuliaPath::Trail { points: Vec::new() },
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            JuliaPath::Circle { .. } => "Circle",
+            JuliaPath::Line { .. } => "Line",
+            JuliaPath::Spline { .. } => "Spline",
+            JuliaPath::Trail { .. } => "Mouse trail",
+            JuliaPath::MainCardioid { .. } => "Main cardioid",
+            JuliaPath::PeriodTwoBulb { .. } => "Period 2 bulb",
+        }
+    }
+
+    /// point on the path at `t`, the path repeats every 1.0
+    /// returns `None` for a spline or trail without points
+    pub fn point(&self, t: f32) -> Option<[f32; 2]> {
+        let t = t.rem_euclid(1.0);
+        let (sin, cos) = (TAU * t).sin_cos();
+
+        match self {
+            JuliaPath::Circle { center, radius } => Some([center[0] + radius * cos, center[1] + radius * sin]),
+            JuliaPath::Line { from, to } => {
+                // ease in and out at both ends instead of bouncing back
+                let s = (1.0 - cos) / 2.0;
+                Some([from[0] + (to[0] - from[0]) * s, from[1] + (to[1] - from[1]) * s])
+            },
+            JuliaPath::Spline { points } => catmull_rom(points, t),
+            JuliaPath::Trail { points } => along_polygon(points, t),
+            JuliaPath::MainCardioid { scale } => {
+                // c = w/2 - w^2/4 with w = scale * e^(i 2 pi t)
+                let w = [scale * cos, scale * sin];
+                let w2 = [w[0] * w[0] - w[1] * w[1], 2.0 * w[0] * w[1]];
+                Some([w[0] / 2.0 - w2[0] / 4.0, w[1] / 2.0 - w2[1] / 4.0])
+            },
+            JuliaPath::PeriodTwoBulb { scale } => Some([-1.0 + 0.25 * scale * cos, 0.25 * scale * sin]),
+        }
+    }
+
+    /// `count` evenly spaced points along the whole path, the first point is repeated at the end
+    pub fn outline(&self, count: usize) -> Vec<[f32; 2]> {
+        (0..=count).filter_map(|i| self.point(i as f32 / count as f32)).collect()
+    }
+}
+
+/// closed uniform Catmull-Rom spline through the points, every segment takes the same time
+fn catmull_rom(points: &[[f32; 2]], t: f32) -> Option<[f32; 2]> {
+    let n = points.len();
+    if n < 2 {
+        return points.first().copied();
+    }
+
+    let position = t * n as f32;
+    let segment = (position.floor() as usize).min(n - 1);
+    let u = position - segment as f32;
+    let [p0, p1, p2, p3] = [n - 1, 0, 1, 2].map(|offset| points[(segment + offset) % n]);
+
+    let blend = |a: f32, b: f32, c: f32, d: f32| {
+        0.5 * (2.0 * b + (c - a) * u + (2.0 * a - 5.0 * b + 4.0 * c - d) * u * u + (3.0 * b - a - 3.0 * c + d) * u * u * u)
+    };
+    Some([blend(p0[0], p1[0], p2[0], p3[0]), blend(p0[1], p1[1], p2[1], p3[1])])
+}
+
+/// point at the fraction `t` of the length of the closed polygon through the points
+fn along_polygon(points: &[[f32; 2]], t: f32) -> Option<[f32; 2]> {
+    let first = *points.first()?;
+    let edges: Vec<([f32; 2], [f32; 2], f32)> = points.iter().zip(points.iter().skip(1).chain([&first]))
+        .map(|(a, b)| (*a, *b, (b[0] - a[0]).hypot(b[1] - a[1])))
+        .collect();
+
+    let total: f32 = edges.iter().map(|edge| edge.2).sum();
+    let mut remaining = t * total;
+    for (a, b, length) in edges {
+        if remaining <= length && length > 0.0 {
+            let s = remaining / length;
+            return Some([a[0] + (b[0] - a[0]) * s, a[1] + (b[1] - a[1]) * s]);
+        }
+        remaining -= length;
+    }
+    Some(first)
+}
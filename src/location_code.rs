@@ -0,0 +1,294 @@
+//! Compact text codes for sharing a location
+//!
+//! A scene is packed into a little endian binary record, encoded as URL safe base64
+//! and prefixed with `fractal://`, so it can be pasted into a chat and read back exactly.
+//!
+//! Layout of version 5, older versions are a prefix of it and still decode:
+//! | bytes | content                                   |
+//! |-------|-------------------------------------------|
+//! | 1     | version                                   |
+//! | 1     | fractal, index into [`FractalKind::ALL`]  |
+//! | 16    | focus as two f64                          |
+//! | 8     | zoom as f64                               |
+//! | 8     | rotation as f64                           |
+//! | 8     | camera width and height as two f32        |
+//! | 4     | max iterations as i32                     |
+//! | 8     | julia constant as two f32                 |
+//! | 12    | hsv scale as three f32                    |
+//! | 12    | terminal color as three f32               |
+//! | 4     | palette offset as f32, since version 2    |
+//! | 4     | lyapunov sequence bits as u32, version 3  |
+//! | 1     | lyapunov sequence length, version 3       |
+//! | 4     | lyapunov warm-up steps as i32, version 3  |
+//! | 1     | formula index, version 4                  |
+//! | 8     | formula parameter as two f32, version 4   |
+//! | 4     | bailout radius as f32, version 5          |
+//! | 1     | escape norm index, version 5              |
+
+use std::fmt;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use crate::lyapunov::LyapunovSequence;
+use crate::scene::{EscapeNorm, Formula, FractalKind, Scene};
+
+pub const PREFIX: &str = "fractal://";
+const VERSION: u8 = 5;
+/// length of the codes of each version, starting with version 1
+const LENGTHS: [usize; 5] = [78, 82, 91, 100, 105];
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidEncoding,
+    UnsupportedVersion(u8),
+    InvalidLength { expected: usize, found: usize },
+    UnknownFractal(u8),
+    InvalidSequenceLength(u8),
+    UnknownFormula(u8),
+    UnknownEscapeNorm(u8),
+    /// a value that cannot be shown, named after its scene field
+    InvalidValue(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidEncoding => write!(f, "not a location code"),
+            Error::UnsupportedVersion(version) => write!(f, "unsupported version {}", version),
+            Error::InvalidLength { expected, found } => write!(f, "expected {} bytes, found {}", expected, found),
+            Error::UnknownFractal(index) => write!(f, "unknown fractal {}", index),
+            Error::InvalidSequenceLength(length) => write!(f, "invalid lyapunov sequence length {}", length),
+            Error::UnknownFormula(index) => write!(f, "unknown formula {}", index),
+            Error::UnknownEscapeNorm(index) => write!(f, "unknown escape norm {}", index),
+            Error::InvalidValue(name) => write!(f, "invalid {}", name),
+        }
+    }
+}
+
+/// encode the scene into a `fractal://` location code
+pub fn encode(scene: &Scene) -> String {
+    let camera = &scene.camera;
+    let mut bytes = Vec::with_capacity(LENGTHS[VERSION as usize - 1]);
+
+    bytes.push(VERSION);
+    bytes.push(FractalKind::ALL.iter().position(|kind| *kind == scene.fractal).unwrap_or(0) as u8);
+    for value in [camera.focus[0], camera.focus[1], camera.zoom, camera.rotation] {
+        bytes.extend(value.to_le_bytes());
+    }
+    for value in [camera.width as f32, camera.height as f32] {
+        bytes.extend(value.to_le_bytes());
+    }
+    bytes.extend(scene.max_iterations.to_le_bytes());
+    for value in scene.julia_constant.iter().chain(&scene.hsv_scale).chain(&scene.terminal_color) {
+        bytes.extend(value.to_le_bytes());
+    }
+    bytes.extend(scene.palette_offset.to_le_bytes());
+    bytes.extend(scene.lyapunov_sequence.bits().to_le_bytes());
+    bytes.push(scene.lyapunov_sequence.length());
+    bytes.extend(scene.lyapunov_warmup.to_le_bytes());
+    bytes.push(Formula::ALL.iter().position(|formula| *formula == scene.formula).unwrap_or(0) as u8);
+    for value in scene.formula_parameter {
+        bytes.extend(value.to_le_bytes());
+    }
+    bytes.extend(scene.bailout.to_le_bytes());
+    bytes.push(scene.escape_norm.index() as u8);
+
+    format!("{}{}", PREFIX, URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// decode a location code, the `fractal://` prefix and surrounding whitespace are optional
+pub fn decode(code: &str) -> Result<Scene, Error> {
+    let code = code.trim();
+    let code = code.strip_prefix(PREFIX).unwrap_or(code);
+    let bytes = URL_SAFE_NO_PAD.decode(code.trim_end_matches('/')).map_err(|_| Error::InvalidEncoding)?;
+
+    let version = *bytes.first().ok_or(Error::InvalidEncoding)?;
+    if version == 0 || version > VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+    let expected = LENGTHS[version as usize - 1];
+    if bytes.len() != expected {
+        return Err(Error::InvalidLength { expected, found: bytes.len() });
+    }
+
+    let mut reader = Reader { bytes: &bytes[2..] };
+    let mut scene = Scene {
+        fractal: *FractalKind::ALL.get(bytes[1] as usize).ok_or(Error::UnknownFractal(bytes[1]))?,
+        ..Scene::default()
+    };
+
+    let camera = &mut scene.camera;
+    camera.focus = [f64::from_le_bytes(reader.take()), f64::from_le_bytes(reader.take())];
+    camera.zoom = f64::from_le_bytes(reader.take());
+    camera.rotation = f64::from_le_bytes(reader.take());
+    camera.width = f32::from_le_bytes(reader.take()) as f64;
+    camera.height = f32::from_le_bytes(reader.take()) as f64;
+    scene.max_iterations = i32::from_le_bytes(reader.take());
+    for value in scene.julia_constant.iter_mut().chain(&mut scene.hsv_scale).chain(&mut scene.terminal_color) {
+        *value = f32::from_le_bytes(reader.take());
+    }
+    if version >= 2 {
+        scene.palette_offset = f32::from_le_bytes(reader.take());
+    }
+    if version >= 3 {
+        let bits = u32::from_le_bytes(reader.take());
+        let [length] = reader.take();
+        scene.lyapunov_sequence = LyapunovSequence::from_bits(bits, length).ok_or(Error::InvalidSequenceLength(length))?;
+        scene.lyapunov_warmup = i32::from_le_bytes(reader.take());
+    }
+    if version >= 4 {
+        let [index] = reader.take();
+        scene.formula = *Formula::ALL.get(index as usize).ok_or(Error::UnknownFormula(index))?;
+        scene.formula_parameter = [f32::from_le_bytes(reader.take()), f32::from_le_bytes(reader.take())];
+    }
+    if version >= 5 {
+        scene.bailout = f32::from_le_bytes(reader.take());
+        let [index] = reader.take();
+        scene.escape_norm = *EscapeNorm::ALL.get(index as usize).ok_or(Error::UnknownEscapeNorm(index))?;
+    } else {
+        // older codes used the fixed radius of their formula
+        scene.bailout = scene.formula.default_bailout();
+    }
+
+    validate(&scene)?;
+    Ok(scene)
+}
+
+/// reject values the renderer cannot show, a broken camera would leave the view empty with no way back but a reset
+fn validate(scene: &Scene) -> Result<(), Error> {
+    let camera = &scene.camera;
+    if !camera.focus.iter().chain([&camera.rotation]).all(|value| value.is_finite()) {
+        return Err(Error::InvalidValue("camera position"));
+    }
+    for (name, value) in [("zoom", camera.zoom), ("camera width", camera.width), ("camera height", camera.height)] {
+        if !(value.is_finite() && value > 0.0) {
+            return Err(Error::InvalidValue(name));
+        }
+    }
+    if scene.max_iterations < 1 {
+        return Err(Error::InvalidValue("max iterations"));
+    }
+    Ok(())
+}
+
+/// reads fixed size values from the front of a byte slice
+/// the length is checked up front, so running out of bytes is a bug
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let (value, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        value.try_into().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::Camera;
+
+    fn scene() -> Scene {
+        Scene {
+            fractal: FractalKind::Julia,
+            julia_constant: [-0.4, 0.6],
+            formula: Formula::MagnetI,
+            formula_parameter: [0.3, -0.2],
+            bailout: 100.0,
+            escape_norm: EscapeNorm::Manhattan,
+            camera: Camera { focus: [-0.743643887037151, 0.131825904205330], zoom: 1.5e9, width: 2.5, height: 2.0, rotation: 1.25 },
+            max_iterations: 4000,
+            hsv_scale: [0.5, 1.0, 2.0],
+            palette_offset: 0.75,
+            terminal_color: [0.1, 0.2, 0.3],
+            lyapunov_sequence: LyapunovSequence::from_bits(0b0110, 4).unwrap(),
+            lyapunov_warmup: 120,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let scene = scene();
+        let code = encode(&scene);
+        assert!(code.starts_with(PREFIX));
+        assert_eq!(decode(&code).unwrap(), scene);
+    }
+
+    #[test]
+    fn round_trip_without_prefix() {
+        let scene = scene();
+        let code = encode(&scene);
+        let bare = format!("  {}/ ", code.strip_prefix(PREFIX).unwrap());
+        assert_eq!(decode(&bare).unwrap(), scene);
+    }
+
+    #[test]
+    fn current_version_length() {
+        let code = encode(&Scene::default());
+        let bytes = URL_SAFE_NO_PAD.decode(code.strip_prefix(PREFIX).unwrap()).unwrap();
+        assert_eq!(bytes[0], VERSION);
+        assert_eq!(bytes.len(), LENGTHS[VERSION as usize - 1]);
+    }
+
+    #[test]
+    fn decodes_version_1() {
+        let scene = decode("fractal://AQEAAAAAAADov5qZmZmZmbk_AAAAAAAAEEAAAAAAAADgPwAAIEAAAABALAEAAM3MTL93vh8-AACAPwAAAD8AAABAzczMPc3MTD6amZk-").unwrap();
+        assert_eq!(scene.fractal, FractalKind::Julia);
+        assert_eq!(scene.camera, Camera { focus: [-0.75, 0.1], zoom: 4.0, rotation: 0.5, width: 2.5, height: 2.0 });
+        assert_eq!(scene.max_iterations, 300);
+        assert_eq!(scene.julia_constant, [-0.8, 0.156]);
+        assert_eq!(scene.hsv_scale, [1.0, 0.5, 2.0]);
+        assert_eq!(scene.terminal_color, [0.1, 0.2, 0.3]);
+        // the fields added later keep their defaults
+        let default = Scene::default();
+        assert_eq!(scene.palette_offset, default.palette_offset);
+        assert_eq!(scene.lyapunov_sequence, default.lyapunov_sequence);
+        assert_eq!(scene.formula, Formula::Quadratic);
+        assert_eq!(scene.bailout, Formula::Quadratic.default_bailout());
+        assert_eq!(scene.escape_norm, EscapeNorm::Modulus);
+    }
+
+    #[test]
+    fn decodes_version_4() {
+        let scene = decode("fractal://BAEAAAAAAADov5qZmZmZmbk_AAAAAAAAEEAAAAAAAADgPwAAIEAAAABALAEAAM3MTL93vh8-AACAPwAAAD8AAABAzczMPc3MTD6amZk-AACAPgIAAAACUAAAAAEAAAC_AACAPg").unwrap();
+        assert_eq!(scene.fractal, FractalKind::Julia);
+        assert_eq!(scene.camera.focus, [-0.75, 0.1]);
+        assert_eq!(scene.max_iterations, 300);
+        assert_eq!(scene.palette_offset, 0.25);
+        assert_eq!(scene.lyapunov_sequence, LyapunovSequence::from_bits(0b10, 2).unwrap());
+        assert_eq!(scene.lyapunov_warmup, 80);
+        assert_eq!(scene.formula, Formula::Phoenix);
+        assert_eq!(scene.formula_parameter, [-0.5, 0.25]);
+        // version 4 had no bailout, the radius of the formula is used
+        assert_eq!(scene.bailout, Formula::Phoenix.default_bailout());
+        assert_eq!(scene.escape_norm, EscapeNorm::Modulus);
+    }
+
+    #[test]
+    fn rejects_invalid_codes() {
+        assert!(matches!(decode("fractal://not base64!"), Err(Error::InvalidEncoding)));
+        assert!(matches!(decode(""), Err(Error::InvalidEncoding)));
+        let future = format!("{}{}", PREFIX, URL_SAFE_NO_PAD.encode([VERSION + 1, 0, 0]));
+        assert!(matches!(decode(&future), Err(Error::UnsupportedVersion(_))));
+        let truncated = format!("{}{}", PREFIX, URL_SAFE_NO_PAD.encode([1, 0, 0]));
+        assert!(matches!(decode(&truncated), Err(Error::InvalidLength { expected: 78, found: 3 })));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let base = scene();
+        let invalid = [
+            Scene { camera: Camera { zoom: 0.0, ..base.camera }, ..base },
+            Scene { camera: Camera { zoom: f64::INFINITY, ..base.camera }, ..base },
+            Scene { camera: Camera { width: -2.5, ..base.camera }, ..base },
+            Scene { camera: Camera { height: f64::NAN, ..base.camera }, ..base },
+            Scene { camera: Camera { focus: [f64::NAN, 0.0], ..base.camera }, ..base },
+            Scene { max_iterations: 0, ..base },
+            Scene { max_iterations: -5, ..base },
+        ];
+        for scene in invalid {
+            assert!(matches!(decode(&encode(&scene)), Err(Error::InvalidValue(_))), "{:?}", scene);
+        }
+    }
+}
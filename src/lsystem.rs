@@ -0,0 +1,210 @@
+use std::fmt::Write;
+
+/// Lindenmayer system drawn with turtle graphics
+/// every iteration replaces each symbol of the string by its rule, the result is read as turtle commands:
+/// `F` and `G` draw a step, `f` moves without drawing, `+` and `-` turn left and right by the angle,
+/// `|` turns around, `[` and `]` push and pop the position and heading, other symbols are ignored
+#[derive(Clone, PartialEq, Debug)]
+pub struct LSystem {
+    pub name: String,
+    pub axiom: String,
+    pub rules: Vec<(char, String)>,
+    /// turning angle in degrees
+    pub angle: f64,
+    /// heading of the turtle at the start in degrees, 0 points right and 90 up
+    pub heading: f64,
+    pub iterations: u32,
+}
+
+impl LSystem {
+    /// longest string that is expanded, deeper iterations are refused
+    pub const MAX_SYMBOLS: usize = 4_000_000;
+
+    pub fn presets() -> [LSystem; 6] {
+        [
+            Self::preset("Koch snowflake", "F--F--F", &[('F', "F+F--F+F")], 60.0, 0.0, 4),
+            Self::preset("Dragon curve", "FX", &[('X', "X+YF+"), ('Y', "-FX-Y")], 90.0, 0.0, 12),
+            Self::preset("Hilbert curve", "A", &[('A', "+BF-AFA-FB+"), ('B', "-AF+BFB+FA-")], 90.0, 0.0, 6),
+            Self::preset("Sierpinski arrowhead", "F", &[('F', "G-F-G"), ('G', "F+G+F")], 60.0, 0.0, 7),
+            Self::preset("Fractal plant", "X", &[('X', "F+[[X]-X]-F[-FX]+X"), ('F', "FF")], 25.0, 65.0, 6),
+            Self::preset("Bush", "F", &[('F', "FF+[+F-F-F]-[-F+F+F]")], 22.5, 90.0, 4),
+        ]
+    }
+
+    fn preset(name: &str, axiom: &str, rules: &[(char, &str)], angle: f64, heading: f64, iterations: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            axiom: axiom.to_string(),
+            rules: rules.iter().map(|(symbol, replacement)| (*symbol, replacement.to_string())).collect(),
+            angle,
+            heading,
+            iterations,
+        }
+    }
+
+    /// the rules written one per line as `F=F+F--F+F`
+    pub fn rules_text(&self) -> String {
+        self.rules.iter().map(|(symbol, replacement)| format!("{}={}\n", symbol, replacement)).collect()
+    }
+
+    /// parse rules written one per line as `F=F+F--F+F`, empty lines are skipped
+    pub fn parse_rules(text: &str) -> Result<Vec<(char, String)>, String> {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (symbol, replacement) = line.split_once('=').ok_or(format!("missing `=` in rule `{}`", line))?;
+                let mut chars = symbol.trim().chars();
+                match (chars.next(), chars.next()) {
+                    (Some(symbol), None) => Ok((symbol, replacement.trim().to_string())),
+                    _ => Err(format!("the rule `{}` has to replace a single symbol", line)),
+                }
+            })
+            .collect()
+    }
+
+    /// apply the rules to the axiom for the number of iterations
+    pub fn expand(&self) -> Result<String, String> {
+        let mut current = self.axiom.clone();
+        for _ in 0..self.iterations {
+            let mut next = String::with_capacity(current.len() * 2);
+            for symbol in current.chars() {
+                match self.rules.iter().find(|(rule, _)| *rule == symbol) {
+                    Some((_, replacement)) => next.push_str(replacement),
+                    None => next.push(symbol),
+                }
+                if next.len() > Self::MAX_SYMBOLS {
+                    return Err(format!("more than {} symbols, use fewer iterations", Self::MAX_SYMBOLS));
+                }
+            }
+            current = next;
+        }
+        Ok(current)
+    }
+
+    /// expand the system and walk the turtle along it
+    /// the lines are scaled to fit into the square from -1 to 1, centered on the origin
+    pub fn segments(&self) -> Result<Vec<[[f64; 2]; 2]>, String> {
+        let commands = self.expand()?;
+        let turn = self.angle.to_radians();
+        let mut position = [0.0, 0.0];
+        let mut heading = self.heading.to_radians();
+        let mut stack = Vec::new();
+        let mut segments = Vec::new();
+
+        for command in commands.chars() {
+            match command {
+                'F' | 'G' | 'f' => {
+                    let next = [position[0] + heading.cos(), position[1] + heading.sin()];
+                    if command != 'f' {
+                        segments.push([position, next]);
+                    }
+                    position = next;
+                },
+                '+' => heading += turn,
+                '-' => heading -= turn,
+                '|' => heading += std::f64::consts::PI,
+                '[' => stack.push((position, heading)),
+                ']' => (position, heading) = stack.pop().ok_or("`]` without matching `[`")?,
+                _ => {},
+            }
+        }
+
+        let [min, max] = bounds(&segments);
+        let center = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+        let scale = 2.0 / (max[0] - min[0]).max(max[1] - min[1]).max(f64::MIN_POSITIVE);
+        for point in segments.iter_mut().flatten() {
+            *point = [(point[0] - center[0]) * scale, (point[1] - center[1]) * scale];
+        }
+        Ok(segments)
+    }
+}
+
+/// smallest and largest coordinates of the lines
+fn bounds(segments: &[[[f64; 2]; 2]]) -> [[f64; 2]; 2] {
+    segments.iter().flatten().fold([[f64::MAX; 2], [f64::MIN; 2]], |[min, max], point| {
+        [[min[0].min(point[0]), min[1].min(point[1])], [max[0].max(point[0]), max[1].max(point[1])]]
+    })
+}
+
+/// write the lines as an SVG image whose longer side is `size` pixels, connected lines are joined into one path
+pub fn to_svg(segments: &[[[f64; 2]; 2]], size: f64, color: [f32; 3]) -> String {
+    let [min, max] = if segments.is_empty() { [[0.0; 2], [1.0; 2]] } else { bounds(segments) };
+    let scale = size / (max[0] - min[0]).max(max[1] - min[1]).max(f64::MIN_POSITIVE);
+    let margin = 0.02 * size;
+    // SVG has the y axis pointing down
+    let to_svg = |point: [f64; 2]| [(point[0] - min[0]) * scale + margin, (max[1] - point[1]) * scale + margin];
+    let [width, height] = [(max[0] - min[0]) * scale + 2.0 * margin, (max[1] - min[1]) * scale + 2.0 * margin];
+    let [r, g, b] = color.map(|channel| (channel * 255.0).round() as u8);
+
+    let mut svg = String::new();
+    let _ = writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.2} {:.2}\">", width, height, width, height);
+    let _ = write!(svg, "<path fill=\"none\" stroke=\"rgb({},{},{})\" stroke-width=\"1\" stroke-linecap=\"round\" stroke-linejoin=\"round\" d=\"", r, g, b);
+
+    let mut last = None;
+    for [from, to] in segments {
+        if last != Some(*from) {
+            let [x, y] = to_svg(*from);
+            let _ = write!(svg, "M{:.2} {:.2}", x, y);
+        }
+        let [x, y] = to_svg(*to);
+        let _ = write!(svg, "L{:.2} {:.2}", x, y);
+        last = Some(*to);
+    }
+    svg.push_str("\"/>\n</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system(axiom: &str, rules: &[(char, &str)], iterations: u32) -> LSystem {
+        LSystem::preset("test", axiom, rules, 90.0, 0.0, iterations)
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rules = LSystem::parse_rules("F=F+F\n\n  X = X+YF+ \nY=\n").unwrap();
+        assert_eq!(rules, [('F', "F+F".to_string()), ('X', "X+YF+".to_string()), ('Y', String::new())]);
+        assert!(LSystem::parse_rules("").unwrap().is_empty());
+
+        assert_eq!(LSystem::parse_rules("F F").unwrap_err(), "missing `=` in rule `F F`");
+        assert_eq!(LSystem::parse_rules("FX=F").unwrap_err(), "the rule `FX=F` has to replace a single symbol");
+        assert!(LSystem::parse_rules("=F").is_err());
+
+        let koch = &LSystem::presets()[0];
+        assert_eq!(LSystem::parse_rules(&koch.rules_text()).unwrap(), koch.rules);
+    }
+
+    #[test]
+    fn expands_rules() {
+        let dragon = system("FX", &[('X', "X+YF+"), ('Y', "-FX-Y")], 2);
+        assert_eq!(dragon.expand().unwrap(), "FX+YF++-FX-YF+");
+    }
+
+    #[test]
+    fn stops_at_max_symbols() {
+        // every iteration doubles the string, 2^21 symbols fit, 2^22 do not
+        assert_eq!(system("F", &[('F', "FF")], 21).expand().unwrap().len(), 1 << 21);
+        let error = system("F", &[('F', "FF")], 22).expand().unwrap_err();
+        assert_eq!(error, format!("more than {} symbols, use fewer iterations", LSystem::MAX_SYMBOLS));
+    }
+
+    #[test]
+    fn koch_segments() {
+        let koch = LSystem { iterations: 2, ..LSystem::presets()[0].clone() };
+        let segments = koch.segments().unwrap();
+        // every iteration turns each of the 3 sides into 4
+        assert_eq!(segments.len(), 3 * 4 * 4);
+        let [min, max] = bounds(&segments);
+        assert!(min.iter().chain(&max).all(|value| (-1.0 - 1e-9..=1.0 + 1e-9).contains(value)));
+        assert!((max[0] - min[0] - 2.0).abs() < 1e-9 || (max[1] - min[1] - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_unmatched_pop() {
+        assert_eq!(system("F]", &[], 0).segments().unwrap_err(), "`]` without matching `[`");
+        assert_eq!(system("[F]F", &[], 0).segments().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+
+/// Sequence of the growth rates a and b the logistic map alternates between, written like `AABAB`
+/// stored as bits, with `A` as 0 and `B` as 1 starting at the lowest bit, so scenes can stay `Copy`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LyapunovSequence {
+    bits: u32,
+    length: u8,
+}
+
+impl Default for LyapunovSequence {
+    fn default() -> Self {
+        Self { bits: 0b10, length: 2 }
+    }
+}
+
+impl LyapunovSequence {
+    /// longest sequence that can be stored
+    pub const MAX_LENGTH: usize = 32;
+
+    /// parse a sequence of `A` and `B`, case-insensitive
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() || text.len() > Self::MAX_LENGTH {
+            return Err(format!("the sequence needs 1 to {} letters", Self::MAX_LENGTH));
+        }
+
+        let mut bits = 0;
+        for (index, letter) in text.chars().enumerate() {
+            match letter.to_ascii_uppercase() {
+                'A' => {},
+                'B' => bits |= 1 << index,
+                _ => return Err(format!("invalid letter `{}`, only A and B are allowed", letter)),
+            }
+        }
+        Ok(Self { bits, length: text.len() as u8 })
+    }
+
+    /// create a sequence from its stored form, used when decoding location codes
+    /// bits beyond the length are dropped, so equal sequences compare equal
+    pub fn from_bits(bits: u32, length: u8) -> Option<Self> {
+        if !(1..=Self::MAX_LENGTH).contains(&(length as usize)) {
+            return None;
+        }
+        Some(Self { bits: bits & (u32::MAX >> (Self::MAX_LENGTH - length as usize)), length })
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub fn length(&self) -> u8 {
+        self.length
+    }
+
+    /// whether the step `index` of the logistic map uses the rate b
+    pub fn is_b(&self, index: usize) -> bool {
+        (self.bits >> (index % self.length as usize)) & 1 == 1
+    }
+}
+
+impl fmt::Display for LyapunovSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for index in 0..self.length as usize {
+            write!(f, "{}", if self.is_b(index) { 'B' } else { 'A' })?;
+        }
+        Ok(())
+    }
+}
+
+/// Lyapunov exponent of the logistic map x -> r x (1 - x) with r switching between `a` and `b`,
+/// computed the same way as in the shader
+/// negative exponents belong to stable, positive ones to chaotic behavior
+pub fn exponent(sequence: LyapunovSequence, warmup: i32, iterations: i32, [a, b]: [f64; 2]) -> f64 {
+    let rate = |index: usize| if sequence.is_b(index) { b } else { a };
+    let mut x = 0.5;
+
+    for index in 0..warmup.max(0) as usize {
+        x = rate(index) * x * (1.0 - x);
+    }
+
+    let mut sum = 0.0;
+    for index in 0..iterations.max(1) as usize {
+        let r = rate(warmup.max(0) as usize + index);
+        // derivative of the map at the current x
+        sum += (r * (1.0 - 2.0 * x)).abs().max(1e-12).ln();
+        x = r * x * (1.0 - x);
+    }
+    sum / iterations.max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sequences() {
+        let sequence = LyapunovSequence::parse(" aBbA ").unwrap();
+        assert_eq!(sequence.length(), 4);
+        assert_eq!(sequence.bits(), 0b0110);
+        assert_eq!(sequence.to_string(), "ABBA");
+        assert!((0..8).map(|index| sequence.is_b(index)).eq([false, true, true, false, false, true, true, false]));
+    }
+
+    #[test]
+    fn rejects_invalid_sequences() {
+        assert!(LyapunovSequence::parse("").is_err());
+        assert!(LyapunovSequence::parse("   ").is_err());
+        assert!(LyapunovSequence::parse(&"AB".repeat(16)).is_ok());
+        assert!(LyapunovSequence::parse(&format!("{}A", "AB".repeat(16))).is_err());
+        assert!(LyapunovSequence::parse("ABC").unwrap_err().contains('C'));
+        assert!(LyapunovSequence::parse("A B").is_err());
+        assert!(LyapunovSequence::parse("ÄB").is_err());
+    }
+
+    #[test]
+    fn bits_round_trip() {
+        for text in ["A", "B", "AB", "BBA", "AABAB", &"AB".repeat(16), &"B".repeat(32)] {
+            let sequence = LyapunovSequence::parse(text).unwrap();
+            assert_eq!(LyapunovSequence::from_bits(sequence.bits(), sequence.length()), Some(sequence), "{}", text);
+            assert_eq!(sequence.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn from_bits_checks_the_length() {
+        assert_eq!(LyapunovSequence::from_bits(0b1, 0), None);
+        assert_eq!(LyapunovSequence::from_bits(0b1, 33), None);
+        assert_eq!(LyapunovSequence::from_bits(u32::MAX, 32).unwrap().to_string(), "B".repeat(32));
+        // bits beyond the length do not change the sequence
+        assert_eq!(LyapunovSequence::from_bits(0b110, 2), LyapunovSequence::parse("AB").ok());
+    }
+}
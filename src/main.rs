@@ -2,14 +2,35 @@ mod resources;
 mod renderer;
 mod ui;
 mod application;
+mod scene;
+mod location_code;
+mod animation;
+mod julia_path;
+mod orbit;
+mod buddhabrot;
+mod lyapunov;
+mod flame;
+mod lsystem;
+mod scene3d;
+mod heightfield;
+mod explorer;
+
+use std::time::Instant;
 
 pub fn main() {
     let mut application = application::Application::new();
+    let mut last_frame = Instant::now();
 
     loop {
         if !application.handle_events() {
             break;
         }
+
+        // measure the frame time so movement does not depend on the framerate
+        let now = Instant::now();
+        application.update(now.duration_since(last_frame).as_secs_f64());
+        last_frame = now;
+
         application.render();
     }
 }
@@ -0,0 +1,161 @@
+use crate::scene::{Formula, FractalKind, Scene};
+
+/// Sequence of z values a single point goes through, computed on the CPU with the formula of the shader
+/// used to show how a point escapes or which cycle it falls into
+#[derive(Clone, PartialEq, Debug)]
+pub struct Orbit {
+    /// starting point followed by every iterate, including the one that escaped
+    pub points: Vec<[f64; 2]>,
+    /// number of iterations before the point escaped or converged, the value the shader colors by
+    pub iterations: i32,
+    pub escaped: bool,
+    /// whether the point reached the attracting fixed point 1 of the magnet formulas
+    pub converged: bool,
+    /// length of the cycle the orbit settled into, if one was found
+    pub period: Option<usize>,
+}
+
+impl Orbit {
+    /// longest cycle that is searched for
+    const MAX_PERIOD: usize = 64;
+    /// distance below which two iterates count as the same point of a cycle
+    const PERIOD_TOLERANCE: f64 = 1e-9;
+
+    /// squared distance to 1 below which a point of the magnet formulas has converged
+    const CONVERGENCE_TOLERANCE: f64 = 1e-6;
+
+    /// iterate the point `start` of the complex plane in the given scene
+    pub fn compute(scene: &Scene, start: [f64; 2]) -> Orbit {
+        let formula = scene.formula;
+        let (c, mut z) = match scene.fractal {
+            // the parameter planes of the Phoenix and magnet formulas start from the critical point 0
+            FractalKind::Mandelbrot if formula != Formula::Quadratic => (start, [0.0, 0.0]),
+            FractalKind::Mandelbrot => (start, start),
+            FractalKind::Julia => ([scene.julia_constant[0] as f64, scene.julia_constant[1] as f64], start),
+            // the plane of the Lyapunov fractal holds growth rates, its points have no orbit
+            FractalKind::Lyapunov => return Orbit { points: vec![start], iterations: 0, escaped: false, converged: false, period: None },
+        };
+        let p = [scene.formula_parameter[0] as f64, scene.formula_parameter[1] as f64];
+        let bailout = scene.bailout as f64;
+
+        let mut points = vec![z];
+        let mut previous = [0.0, 0.0];
+        let mut iterations = 0;
+        let mut escaped = false;
+        let mut converged = false;
+        while iterations < scene.max_iterations {
+            let next = step(formula, z, previous, c, p);
+            previous = z;
+            z = next;
+            points.push(z);
+
+            if scene.escape_norm.escaped(z, bailout) {
+                escaped = true;
+                break;
+            }
+            if formula.converges() && (z[0] - 1.0).powi(2) + z[1] * z[1] < Self::CONVERGENCE_TOLERANCE {
+                converged = true;
+                break;
+            }
+            iterations += 1;
+        }
+
+        let period = if escaped || converged { None } else { Self::detect_period(&points) };
+        Orbit { points, iterations, escaped, converged, period }
+    }
+
+    /// smallest number of steps after which the last iterate returns to itself
+    fn detect_period(points: &[[f64; 2]]) -> Option<usize> {
+        let last = *points.last()?;
+        (1..=Self::MAX_PERIOD.min(points.len() - 1)).find(|&period| {
+            let earlier = points[points.len() - 1 - period];
+            (last[0] - earlier[0]).hypot(last[1] - earlier[1]) < Self::PERIOD_TOLERANCE
+        })
+    }
+
+    /// the points of the cycle, empty if no period was detected
+    pub fn cycle(&self) -> &[[f64; 2]] {
+        match self.period {
+            Some(period) => &self.points[self.points.len() - period..],
+            None => &[],
+        }
+    }
+}
+
+/// next z of the formula, `previous` is the z before the current one
+fn step(formula: Formula, z: [f64; 2], previous: [f64; 2], c: [f64; 2], p: [f64; 2]) -> [f64; 2] {
+    let one = [1.0, 0.0];
+    match formula {
+        Formula::Quadratic => add(mul(z, z), c),
+        Formula::Phoenix => add(add(mul(z, z), c), mul(p, previous)),
+        Formula::MagnetI => {
+            let numerator = sub(add(mul(z, z), c), one);
+            let denominator = sub(add(scale(z, 2.0), c), scale(one, 2.0));
+            let ratio = div(numerator, denominator);
+            mul(ratio, ratio)
+        },
+        Formula::MagnetII => {
+            let [c1, c2] = [sub(c, one), sub(c, scale(one, 2.0))];
+            let numerator = add(add(mul(mul(z, z), z), scale(mul(c1, z), 3.0)), mul(c1, c2));
+            let denominator = add(add(add(scale(mul(z, z), 3.0), scale(mul(c2, z), 3.0)), mul(c1, c2)), one);
+            let ratio = div(numerator, denominator);
+            mul(ratio, ratio)
+        },
+    }
+}
+
+fn add(a: [f64; 2], b: [f64; 2]) -> [f64; 2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn sub(a: [f64; 2], b: [f64; 2]) -> [f64; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn scale(a: [f64; 2], factor: f64) -> [f64; 2] {
+    [a[0] * factor, a[1] * factor]
+}
+
+fn mul(a: [f64; 2], b: [f64; 2]) -> [f64; 2] {
+    [a[0] * b[0] - a[1] * b[1], a[0] * b[1] + a[1] * b[0]]
+}
+
+fn div(a: [f64; 2], b: [f64; 2]) -> [f64; 2] {
+    let norm = b[0] * b[0] + b[1] * b[1];
+    [(a[0] * b[0] + a[1] * b[1]) / norm, (a[1] * b[0] - a[0] * b[1]) / norm]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn origin_is_a_fixed_point() {
+        let orbit = Orbit::compute(&Scene::default(), [0.0, 0.0]);
+        assert!(!orbit.escaped && !orbit.converged);
+        assert_eq!(orbit.period, Some(1));
+        assert_eq!(orbit.cycle(), [[0.0, 0.0]]);
+    }
+
+    #[test]
+    fn minus_one_has_period_two() {
+        let orbit = Orbit::compute(&Scene::default(), [-1.0, 0.0]);
+        assert_eq!(orbit.period, Some(2));
+        let mut cycle = orbit.cycle().to_vec();
+        cycle.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        assert_eq!(cycle, [[-1.0, 0.0], [0.0, 0.0]]);
+    }
+
+    #[test]
+    fn escaping_point_has_no_period() {
+        let scene = Scene::default();
+        let orbit = Orbit::compute(&scene, [1.0, 0.0]);
+        assert!(orbit.escaped);
+        assert_eq!(orbit.period, None);
+        assert!(orbit.cycle().is_empty());
+        // 1, 2, 5, 26, ... the last point is the first one beyond the bailout radius
+        assert_eq!(orbit.points[..3], [[1.0, 0.0], [2.0, 0.0], [5.0, 0.0]]);
+        assert!(scene.escape_norm.escaped(*orbit.points.last().unwrap(), scene.bailout as f64));
+        assert_eq!(orbit.iterations as usize, orbit.points.len() - 2);
+    }
+}
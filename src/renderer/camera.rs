@@ -0,0 +1,132 @@
+/// Camera looking at the complex plane
+/// the camera is centered at `focus`, covers `width` x `height` units at zoom 1.0
+/// and is rotated counter-clockwise by `rotation` radians around its center
+///
+/// both the shader and the CPU side map screen positions through [`Camera::view_transform`],
+/// so everything that converts between pixels and the complex plane agrees on the same mapping
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub focus: [f64; 2],
+    pub zoom: f64,
+    pub width: f64,
+    pub height: f64,
+    pub rotation: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            focus: [0.0, 0.0],
+            zoom: 1.0,
+            width: 2.0,
+            height: 2.0,
+            rotation: 0.0,
+        }
+    }
+}
+
+impl Camera {
+    /// 2x3 affine transform from normalized screen coordinates to the complex plane
+    /// normalized coordinates go from (0, 0) in the bottom left to (1, 1) in the top right corner
+    /// the matrix is stored column major, the same layout as a GLSL `mat3x2`
+    pub fn view_transform(&self) -> [f64; 6] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let span_x = self.width / self.zoom;
+        let span_y = self.height / self.zoom;
+
+        [
+            // direction of the screen x axis
+            cos * span_x, sin * span_x,
+            // direction of the screen y axis
+            -sin * span_y, cos * span_y,
+            // point in the bottom left corner
+            self.focus[0] - (cos * span_x - sin * span_y) / 2.0,
+            self.focus[1] - (sin * span_x + cos * span_y) / 2.0,
+        ]
+    }
+
+    /// 2x3 affine transform from the complex plane to normalized screen coordinates, the inverse of [`Camera::view_transform`]
+    pub fn inverse_view_transform(&self) -> [f64; 6] {
+        let m = self.view_transform();
+        let determinant = m[0] * m[3] - m[2] * m[1];
+        let inverse = [m[3] / determinant, -m[1] / determinant, -m[2] / determinant, m[0] / determinant];
+        [
+            inverse[0], inverse[1],
+            inverse[2], inverse[3],
+            -(inverse[0] * m[4] + inverse[2] * m[5]),
+            -(inverse[1] * m[4] + inverse[3] * m[5]),
+        ]
+    }
+
+    /// map normalized screen coordinates to a point on the complex plane
+    pub fn transform_point(&self, u: f64, v: f64) -> [f64; 2] {
+        let m = self.view_transform();
+        [
+            m[0] * u + m[2] * v + m[4],
+            m[1] * u + m[3] * v + m[5],
+        ]
+    }
+
+    /// camera showing only the part of the view between the normalized screen coordinates `from` and `to`
+    pub fn sub_view(&self, from: [f64; 2], to: [f64; 2]) -> Camera {
+        Camera {
+            focus: self.transform_point((from[0] + to[0]) / 2.0, (from[1] + to[1]) / 2.0),
+            width: self.width * (to[0] - from[0]),
+            height: self.height * (to[1] - from[1]),
+            ..*self
+        }
+    }
+
+    /// camera showing a tile of an image of the whole view, used to render large images in parts
+    /// the tile starts at pixel `position` (origin in the top left corner) and is shifted by `offset` pixels
+    pub fn tile(&self, position: [i32; 2], tile_size: [i32; 2], image_size: [i32; 2], offset: [f64; 2]) -> Camera {
+        let [width, height] = [image_size[0] as f64, image_size[1] as f64];
+        let left = position[0] as f64 + offset[0];
+        let top = position[1] as f64 + offset[1];
+
+        self.sub_view(
+            [left / width, 1.0 - (top + tile_size[1] as f64) / height],
+            [(left + tile_size[0] as f64) / width, 1.0 - top / height],
+        )
+    }
+
+    /// convert a position in window pixels (origin in the top left corner) to a point on the complex plane
+    pub fn screen_to_complex(&self, x: f64, y: f64, screen_width: f64, screen_height: f64) -> [f64; 2] {
+        self.transform_point(x / screen_width, 1.0 - y / screen_height)
+    }
+
+    /// convert a point on the complex plane to a position in window pixels (origin in the top left corner)
+    /// the inverse of [`Camera::screen_to_complex`]
+    pub fn complex_to_screen(&self, point: [f64; 2], screen_width: f64, screen_height: f64) -> [f64; 2] {
+        let m = self.view_transform();
+        let x = point[0] - m[4];
+        let y = point[1] - m[5];
+        let determinant = m[0] * m[3] - m[2] * m[1];
+
+        let u = (x * m[3] - y * m[2]) / determinant;
+        let v = (m[0] * y - m[1] * x) / determinant;
+        [u * screen_width, (1.0 - v) * screen_height]
+    }
+
+    /// move the camera by the given amount of window pixels, respecting the rotation
+    pub fn translate_pixels(&mut self, xrel: f64, yrel: f64, screen_width: f64, screen_height: f64) {
+        let m = self.view_transform();
+        let u = xrel / screen_width;
+        let v = yrel / screen_height;
+
+        self.focus[0] += m[0] * u + m[2] * v;
+        self.focus[1] += m[1] * u + m[3] * v;
+    }
+
+    /// multiply the zoom by the given factor while keeping `anchor` at the same place on the screen
+    pub fn zoom_at(&mut self, factor: f64, anchor: [f64; 2]) {
+        self.zoom *= factor;
+        self.focus[0] = anchor[0] + (self.focus[0] - anchor[0]) / factor;
+        self.focus[1] = anchor[1] + (self.focus[1] - anchor[1]) / factor;
+    }
+
+    /// rotate the camera counter-clockwise around its center
+    pub fn rotate(&mut self, angle: f64) {
+        self.rotation = (self.rotation + angle).rem_euclid(std::f64::consts::TAU);
+    }
+}
@@ -0,0 +1,225 @@
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use crate::location_code;
+use crate::renderer::{image_writer, DataLayer, Error, FractalRenderer, RenderTarget};
+use crate::renderer::image_export::{poll_saving, ExportJob};
+use crate::scene::Scene;
+
+/// File formats for raw data
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DataFormat {
+    /// all channels as 32-bit floats
+    Exr,
+    /// all channels as 32-bit floats
+    Npy,
+    /// smooth iteration count divided by the maximum iterations as 16-bit grayscale
+    Png16,
+    /// smooth iteration count divided by the maximum iterations as 16-bit grayscale
+    Tiff16,
+}
+
+impl DataFormat {
+    pub const ALL: [DataFormat; 4] = [DataFormat::Exr, DataFormat::Npy, DataFormat::Png16, DataFormat::Tiff16];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataFormat::Exr => "OpenEXR, all channels",
+            DataFormat::Npy => "NumPy .npy, all channels",
+            DataFormat::Png16 => "16-bit PNG, smooth iterations",
+            DataFormat::Tiff16 => "16-bit TIFF, smooth iterations",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DataFormat::Exr => "exr",
+            DataFormat::Npy => "npy",
+            DataFormat::Png16 => "png",
+            DataFormat::Tiff16 => "tiff",
+        }
+    }
+}
+
+/// Export of the raw per pixel values of a scene
+/// rendered in tiles into a floating point render target like [`crate::renderer::image_export::ImageExport`],
+/// but with a single sample per pixel, since averaging the values would make them meaningless
+pub struct DataExport {
+    scene: Scene,
+    size: [i32; 2],
+    format: DataFormat,
+    path: PathBuf,
+
+    target: RenderTarget,
+    tiles_x: i32,
+    tiles_y: i32,
+    /// index into [`DataExport::LAYERS`] of the layer being rendered
+    layer: usize,
+    next_tile: i32,
+    /// one plane per channel in the order of [`DataExport::CHANNELS`], rows top to bottom
+    /// planes are only allocated while they are needed, `.npy` files get each layer written before the next is rendered
+    channels: Vec<Vec<f32>>,
+
+    saving: Option<JoinHandle<Result<(), Error>>>,
+}
+
+impl DataExport {
+    /// names of the exported channels, also the order of the planes in `.npy` files
+    pub const CHANNELS: [&'static str; 5] = ["smooth_iterations", "distance_estimate", "z.re", "z.im", "escaped"];
+    /// data layers rendered one after the other and the channels receiving their RGBA values
+    const LAYERS: [(DataLayer, [Option<usize>; 4]); 2] = [
+        (DataLayer::Escape, [Some(0), Some(1), Some(4), None]),
+        (DataLayer::Orbit, [Some(2), Some(3), None, None]),
+    ];
+    /// maximum size of a tile in pixels
+    const TILE_SIZE: i32 = 1024;
+
+    /// prepare the export of the scene, the camera is widened or narrowed to the aspect ratio of the image
+    pub fn new(scene: &Scene, width: i32, height: i32, format: DataFormat, path: PathBuf) -> Result<Self, Error> {
+        if width <= 0 || height <= 0 {
+            return Err(Error::ImageWrite {
+                name: path.to_string_lossy().into_owned(),
+                message: format!("Invalid image size {}x{}", width, height),
+            });
+        }
+
+        let mut scene = *scene;
+        scene.camera.width = scene.camera.height * width as f64 / height as f64;
+
+        let tile_width = width.min(Self::TILE_SIZE);
+        let tile_height = height.min(Self::TILE_SIZE);
+
+        Ok(Self {
+            scene,
+            size: [width, height],
+            format,
+            path,
+            target: RenderTarget::new_float(tile_width, tile_height)?,
+            tiles_x: (width + tile_width - 1) / tile_width,
+            tiles_y: (height + tile_height - 1) / tile_height,
+            layer: 0,
+            next_tile: 0,
+            channels: vec![Vec::new(); Self::CHANNELS.len()],
+            saving: None,
+        })
+    }
+
+    /// number of layers the format needs, the grayscale images only use the escape layer
+    fn layer_count(&self) -> usize {
+        match self.format {
+            DataFormat::Exr | DataFormat::Npy => Self::LAYERS.len(),
+            DataFormat::Png16 | DataFormat::Tiff16 => 1,
+        }
+    }
+
+    /// render the current layer of the next tile and copy it into the channels
+    fn render_tile(&mut self, renderer: &FractalRenderer) {
+        let (layer, targets) = Self::LAYERS[self.layer];
+        if self.next_tile == 0 {
+            for channel in targets.iter().flatten() {
+                self.channels[*channel] = vec![0.0; self.size[0] as usize * self.size[1] as usize];
+            }
+        }
+
+        let [tile_width, tile_height] = [self.target.width(), self.target.height()];
+        let x = self.next_tile % self.tiles_x * tile_width;
+        let y = self.next_tile / self.tiles_x * tile_height;
+        let crop_width = tile_width.min(self.size[0] - x);
+        let crop_height = tile_height.min(self.size[1] - y);
+
+        let camera = self.scene.camera.tile([x, y], [tile_width, tile_height], self.size, [0.0, 0.0]);
+        let tile_scene = Scene { camera, ..self.scene };
+
+        renderer.render_data_to(&tile_scene, layer, &self.target);
+        self.target.bind();
+        let values = image_writer::read_pixels_f32(0, tile_height - crop_height, crop_width, crop_height);
+        self.target.unbind();
+
+        for (index, pixel) in values.chunks_exact(4).enumerate() {
+            let (row, column) = (index / crop_width as usize, index % crop_width as usize);
+            let position = (y as usize + row) * self.size[0] as usize + x as usize + column;
+            for (channel, value) in targets.iter().zip(pixel) {
+                if let Some(channel) = *channel {
+                    self.channels[channel][position] = *value;
+                }
+            }
+        }
+
+        self.next_tile += 1;
+    }
+
+    /// write the channels on a background thread in the chosen format
+    /// `.npy` files are written after every layer, the planes of the layers written before are empty by then
+    fn start_saving(&mut self) {
+        let path = self.path.clone();
+        let format = self.format;
+        let (width, height) = (self.size[0] as usize, self.size[1] as usize);
+        let max_iterations = self.scene.max_iterations as f32;
+        let location = location_code::encode(&self.scene);
+        let channels = std::mem::replace(&mut self.channels, vec![Vec::new(); Self::CHANNELS.len()]);
+
+        self.saving = Some(std::thread::spawn(move || match format {
+            DataFormat::Exr => {
+                let named = Self::CHANNELS.into_iter().zip(channels).collect();
+                image_writer::save_exr(&path, width, height, named, &location)
+            },
+            DataFormat::Npy => {
+                let shape = [Self::CHANNELS.len(), height, width];
+                let planes: Vec<(usize, Vec<f32>)> = channels.into_iter().enumerate().filter(|(_, plane)| !plane.is_empty()).collect();
+                image_writer::write_npy_planes(&path, shape, &planes)
+            },
+            DataFormat::Png16 | DataFormat::Tiff16 => {
+                // points inside the set are white
+                let values: Vec<u16> = channels[0].iter().zip(&channels[4])
+                    .map(|(smooth, escaped)| if *escaped > 0.5 { (smooth / max_iterations).clamp(0.0, 1.0) } else { 1.0 })
+                    .map(|value| (value * u16::MAX as f32).round() as u16)
+                    .collect();
+
+                if format == DataFormat::Png16 {
+                    image_writer::save_png16(&path, width as u32, height as u32, &values, &[("Location", location)])
+                } else {
+                    image_writer::save_tiff16(&path, width as u32, height as u32, &values)
+                }
+            },
+        }));
+    }
+}
+
+impl ExportJob for DataExport {
+    fn step(&mut self, renderer: &FractalRenderer, budget: Duration) -> Option<Result<(), Error>> {
+        if self.saving.is_some() {
+            let result = poll_saving(&mut self.saving, &self.path)?;
+            if result.is_err() || self.layer == self.layer_count() {
+                return Some(result);
+            }
+        }
+
+        let tiles = self.tiles_x * self.tiles_y;
+        let start = Instant::now();
+        while self.next_tile < tiles && start.elapsed() < budget {
+            self.render_tile(renderer);
+        }
+
+        if self.next_tile == tiles {
+            self.layer += 1;
+            self.next_tile = 0;
+            if self.format == DataFormat::Npy || self.layer == self.layer_count() {
+                self.start_saving();
+            }
+        }
+        None
+    }
+
+    fn progress(&self) -> f32 {
+        let tiles = self.tiles_x * self.tiles_y;
+        (self.layer as i32 * tiles + self.next_tile) as f32 / (self.layer_count() as i32 * tiles) as f32
+    }
+
+    fn is_saving(&self) -> bool {
+        self.saving.is_some()
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
@@ -1,20 +1,44 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use gl;
 use gl::types::{GLuint, GLvoid};
 
+use crate::lyapunov::LyapunovSequence;
+use crate::scene::{EscapeNorm, Formula, FractalKind, Scene};
+use crate::scene3d::Scene3D;
 use crate::ui::event_observer::{FractalType, Observer, ObserverEvent};
-use crate::renderer::{Program, Shader};
+use crate::renderer::{image_writer, Camera, Error, Program, RenderTarget, Shader};
 use crate::resources::Resources;
 
+/// Raw values the shader can write instead of colors, used for exporting data
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DataLayer {
+    /// smooth iteration count, distance estimate, escape flag (1 or 0) and iteration count
+    /// for the Lyapunov fractal the exponent, 0, stable flag (1 or 0) and iteration count
+    Escape = 1,
+    /// real and imaginary part of the last z and of its derivative
+    Orbit = 2,
+}
+
 /// OpenGL wrapper around the fractal rendering shader program
 /// hides the details of the shader program and provides a simple interface to set the uniforms
+/// keeps a copy of the live scene, so the uniforms can be restored after rendering a different scene offscreen
 pub struct FractalRenderer {
     program: Program,
+    /// program ray marching the 3D fractals on the same quad
+    raymarch_program: Program,
+    scene: Scene,
+    /// scene of the Julia pane shown next to the live scene while the window is split
+    julia_pane: Option<Scene>,
+    /// 3D scene shown instead of the live scene while the 3D mode is on
+    scene_3d: Option<Scene3D>,
 
     vertex_array: GLuint,
     vertex_buffer: GLuint,
     element_buffer: GLuint,
+
+    screenshot_requested: bool,
 }
 
 impl FractalRenderer {
@@ -58,6 +82,14 @@ impl FractalRenderer {
         let program = Program::from_shaders(
             &[&vertex_shader, &fragment_shader]).unwrap();
 
+        // the 3D fractals use the same vertex shader with the ray marching fragment shader
+        let raymarch_shader = Shader::from_resources(
+            &shaders_resources,
+            "raymarch.frag",
+            gl::FRAGMENT_SHADER).unwrap();
+        let raymarch_program = Program::from_shaders(
+            &[&vertex_shader, &raymarch_shader]).unwrap();
+
         // variables to store the vertex array and buffers
         let mut vertex_array = 0;
         let mut vertex_buffer= 0;
@@ -104,27 +136,29 @@ impl FractalRenderer {
             gl::BindBuffer(gl::ARRAY_BUFFER, 0);
         }
 
-        Self {
+        let renderer = Self {
             program,
+            raymarch_program,
+            scene: Scene::default(),
+            julia_pane: None,
+            scene_3d: None,
             vertex_array,
             vertex_buffer,
             element_buffer,
-        }
-    }
+            screenshot_requested: false,
+        };
 
-    /// set the x-axis range for the fractal
-    pub fn set_x_axis_range(&self, x: f32, y: f32) {
-        self.program.use_program();
-        if let Err(err) =  self.program.set_f32_2(c"x_axis_range", x, y) {
-            eprintln!("Error setting x_axis_range: {:?}", err);
-        }
+        renderer.apply_scene(&renderer.scene);
+        renderer
     }
 
-    /// set the y-axis range for the fractal
-    pub fn set_y_axis_range(&self, x: f32, y: f32) {
+    /// set the view transform mapping normalized screen coordinates to the complex plane
+    pub fn set_view_transform(&self, camera: &Camera) {
+        let matrix = camera.view_transform().map(|value| value as f32);
+
         self.program.use_program();
-        if let Err(err) = self.program.set_f32_2(c"y_axis_range", x, y) {
-            eprintln!("Error setting y_axis_range: {:?}", err);
+        if let Err(err) = self.program.set_mat3x2(c"view_transform", &matrix) {
+            eprintln!("Error setting view_transform: {:?}", err);
         }
     }
 
@@ -136,6 +170,48 @@ impl FractalRenderer {
         }
     }
 
+    /// select the formula of the shader, the Lyapunov fractal has its own, the Mandelbrot and Julia kinds iterate the given formula
+    fn set_formula(&self, fractal: FractalKind, formula: Formula) {
+        self.program.use_program();
+        let index = if fractal == FractalKind::Lyapunov { 1 } else { formula.shader_index() };
+        if let Err(err) = self.program.set_i32(c"formula", index) {
+            eprintln!("Error setting formula: {:?}", err);
+        }
+    }
+
+    /// set the extra complex parameter of the formula
+    pub fn set_formula_parameter(&self, x: f32, y: f32) {
+        self.program.use_program();
+        if let Err(err) = self.program.set_f32_2(c"formula_parameter", x, y) {
+            eprintln!("Error setting formula_parameter: {:?}", err);
+        }
+    }
+
+    /// set the radius and the norm deciding when a point escapes
+    pub fn set_bailout(&self, radius: f32, norm: EscapeNorm) {
+        self.program.use_program();
+        if let Err(err) = self.program.set_f32(c"bailout", radius) {
+            eprintln!("Error setting bailout: {:?}", err);
+        }
+        if let Err(err) = self.program.set_i32(c"escape_norm", norm.index()) {
+            eprintln!("Error setting escape_norm: {:?}", err);
+        }
+    }
+
+    /// set the AB sequence and the warm-up steps of the Lyapunov fractal
+    pub fn set_lyapunov(&self, sequence: LyapunovSequence, warmup: i32) {
+        self.program.use_program();
+        if let Err(err) = self.program.set_i32(c"lyapunov_sequence", sequence.bits() as i32) {
+            eprintln!("Error setting lyapunov_sequence: {:?}", err);
+        }
+        if let Err(err) = self.program.set_i32(c"lyapunov_length", sequence.length() as i32) {
+            eprintln!("Error setting lyapunov_length: {:?}", err);
+        }
+        if let Err(err) = self.program.set_i32(c"lyapunov_warmup", warmup) {
+            eprintln!("Error setting lyapunov_warmup: {:?}", err);
+        }
+    }
+
     /// set the julia constant for the fractal
     pub fn set_julia_constant(&self, x: f32, y: f32) {
         self.program.use_program();
@@ -160,6 +236,14 @@ impl FractalRenderer {
         }
     }
 
+    /// set the hue offset of the palette
+    pub fn set_palette_offset(&self, offset: f32) {
+        self.program.use_program();
+        if let Err(err) = self.program.set_f32(c"palette_offset", offset) {
+            eprintln!("Error setting palette_offset: {:?}", err);
+        }
+    }
+
     /// set the terminal color for the fractal
     pub fn set_terminal_color(&self, h: f32, s: f32, v: f32) {
         self.program.use_program();
@@ -168,10 +252,139 @@ impl FractalRenderer {
         }
     }
 
+    /// select whether the shader writes colors (0) or one of the data layers
+    fn set_output_mode(&self, mode: i32) {
+        self.program.use_program();
+        if let Err(err) = self.program.set_i32(c"output_mode", mode) {
+            eprintln!("Error setting output_mode: {:?}", err);
+        }
+    }
+
+    /// set all uniforms describing the given scene
+    pub fn apply_scene(&self, scene: &Scene) {
+        self.set_formula(scene.fractal, scene.formula);
+        self.set_formula_parameter(scene.formula_parameter[0], scene.formula_parameter[1]);
+        self.set_bailout(scene.bailout, scene.escape_norm);
+        self.set_julia(scene.fractal == FractalKind::Julia);
+        self.set_lyapunov(scene.lyapunov_sequence, scene.lyapunov_warmup);
+        self.set_julia_constant(scene.julia_constant[0], scene.julia_constant[1]);
+        self.set_view_transform(&scene.camera);
+        self.set_max_iterations(scene.max_iterations);
+        self.set_hsv_scale(scene.hsv_scale[0], scene.hsv_scale[1], scene.hsv_scale[2]);
+        self.set_palette_offset(scene.palette_offset);
+        self.set_terminal_color(scene.terminal_color[0], scene.terminal_color[1], scene.terminal_color[2]);
+    }
+
+    /// set all uniforms of the ray marching program describing the given 3D scene
+    fn apply_scene_3d(&self, scene: &Scene3D) {
+        let program = &self.raymarch_program;
+        let camera = &scene.camera;
+        let [position, forward, right, up] = [camera.position(), camera.forward(), camera.right(), camera.up()];
+
+        program.use_program();
+        let results = [
+            ("camera_position", program.set_f32_3(c"camera_position", position[0], position[1], position[2])),
+            ("camera_forward", program.set_f32_3(c"camera_forward", forward[0], forward[1], forward[2])),
+            ("camera_right", program.set_f32_3(c"camera_right", right[0], right[1], right[2])),
+            ("camera_up", program.set_f32_3(c"camera_up", up[0], up[1], up[2])),
+            ("fov_scale", program.set_f32(c"fov_scale", (camera.fov.to_radians() / 2.0).tan())),
+            ("shape", program.set_i32(c"shape", scene.shape.index())),
+            ("power", program.set_f32(c"power", scene.power)),
+            ("box_scale", program.set_f32(c"box_scale", scene.box_scale)),
+            ("fractal_iterations", program.set_i32(c"fractal_iterations", scene.iterations)),
+            ("max_steps", program.set_i32(c"max_steps", scene.max_steps)),
+            ("shadow_softness", program.set_f32(c"shadow_softness", scene.shadow_softness)),
+            ("ambient_occlusion", program.set_f32(c"ambient_occlusion", scene.ambient_occlusion)),
+            ("fog_density", program.set_f32(c"fog_density", scene.fog_density)),
+            ("fog_color", program.set_f32_3(c"fog_color", scene.fog_color[0], scene.fog_color[1], scene.fog_color[2])),
+            ("palette_offset", program.set_f32(c"palette_offset", scene.palette_offset)),
+        ];
+        for (name, result) in results {
+            if let Err(err) = result {
+                eprintln!("Error setting {}: {:?}", name, err);
+            }
+        }
+    }
+
+    /// render a scene into an offscreen render target
+    /// the uniforms of the live scene are restored afterwards
+    pub fn render_scene_to(&self, scene: &Scene, target: &RenderTarget) {
+        target.bind();
+        self.apply_scene(scene);
+        self.draw_quad(&self.program, target.width() as f32, target.height() as f32);
+        target.unbind();
+
+        self.apply_scene(&self.scene);
+    }
+
+    /// render raw data of a scene into an offscreen render target, usually one created with [`RenderTarget::new_float`]
+    pub fn render_data_to(&self, scene: &Scene, layer: DataLayer, target: &RenderTarget) {
+        self.set_output_mode(layer as i32);
+        self.render_scene_to(scene, target);
+        self.set_output_mode(0);
+    }
+
+    /// render a small preview of the scene into a new render target of the given width
+    /// the height follows the aspect ratio of the scene camera
+    pub fn render_thumbnail(&self, scene: &Scene, width: i32) -> Result<RenderTarget, Error> {
+        let camera = &scene.camera;
+        let height = (width as f64 * camera.height / camera.width).clamp(1.0, 4.0 * width as f64);
+
+        let target = RenderTarget::new(width, height as i32)?;
+        self.render_scene_to(scene, &target);
+        Ok(target)
+    }
+
     /// render the fractal to the screen with the given screen size
-    pub fn render(&self, x: f32, y: f32) {
+    /// while the window is split the live scene fills the left and the Julia pane the right half,
+    /// in the 3D mode the 3D scene fills the whole window
+    /// saves a screenshot of the fractal if one was requested
+    pub fn render(&mut self, x: f32, y: f32) {
+        match (&self.scene_3d, &self.julia_pane) {
+            (Some(scene_3d), _) => {
+                self.apply_scene_3d(scene_3d);
+                self.draw_quad(&self.raymarch_program, x, y);
+            },
+            (None, Some(julia_pane)) => {
+                let split = (x / 2.0).floor();
+                self.draw_viewport([0.0, 0.0], [split, y]);
+                self.apply_scene(julia_pane);
+                self.draw_viewport([split, 0.0], [x - split, y]);
+                self.apply_scene(&self.scene);
+                self.set_viewport([0.0, 0.0], [x, y]);
+            },
+            (None, None) => self.draw_quad(&self.program, x, y),
+        }
+
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            self.save_screenshot(x as i32, y as i32);
+        }
+    }
+
+    /// draw the quad into the part of the window starting at the pixel `origin` (origin in the bottom left corner)
+    /// the viewport is left at that part of the window
+    fn draw_viewport(&self, origin: [f32; 2], size: [f32; 2]) {
+        self.set_viewport(origin, size);
+        self.draw_quad(&self.program, size[0], size[1]);
+    }
+
+    /// point the viewport and the fragment coordinates of the fractal program at a part of the window without drawing
+    fn set_viewport(&self, origin: [f32; 2], size: [f32; 2]) {
+        unsafe {
+            gl::Viewport(origin[0] as i32, origin[1] as i32, size[0] as i32, size[1] as i32);
+        }
+
         self.program.use_program();
-        if let Err(err) = self.program.set_f32_2(c"screen_size", x, y) {
+        if let Err(err) = self.program.set_f32_2(c"viewport_origin", origin[0], origin[1]) {
+            eprintln!("Error setting viewport_origin: {:?}", err);
+        }
+    }
+
+    /// draw the fullscreen quad into the current viewport of the given size
+    fn draw_quad(&self, program: &Program, x: f32, y: f32) {
+        program.use_program();
+        if let Err(err) = program.set_f32_2(c"screen_size", x, y) {
             eprintln!("Error setting screen_size: {:?}", err);
         }
 
@@ -182,30 +395,82 @@ impl FractalRenderer {
             gl::BindVertexArray(0);
         }
     }
+
+    /// save the current contents of the framebuffer to a timestamped PNG file in the working directory
+    fn save_screenshot(&self, width: i32, height: i32) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_millis());
+        let path = PathBuf::from(format!("screenshot_{}.png", timestamp));
+
+        let pixels = image_writer::read_pixels(0, 0, width, height);
+        match image_writer::save_png(&path, width as u32, height as u32, &pixels, &[]) {
+            Ok(()) => println!("Saved screenshot to {}", path.display()),
+            Err(err) => eprintln!("Error saving screenshot: {:?}", err),
+        }
+    }
 }
 
 /// Implement the observer trait for the fractal renderer
 /// the fractal renderer listens for changes in the fractal settings and updates the uniforms accordingly
 impl Observer for FractalRenderer {
     fn notify(&mut self, event: &ObserverEvent) {
-        // pattern match the event and update the uniforms accordingly
+        // pattern match the event, remember the new value and update the uniforms accordingly
         match event {
-            ObserverEvent::FractalIterations(iterations) => self.set_max_iterations(*iterations),
+            ObserverEvent::FractalIterations(iterations) => {
+                self.scene.max_iterations = *iterations;
+                self.set_max_iterations(*iterations);
+            },
             ObserverEvent::FractalChoice(fractal) => {
                 match fractal {
                     FractalType::Julia(constant) => {
+                        self.scene.fractal = FractalKind::Julia;
+                        self.scene.julia_constant = *constant;
                         self.set_julia(true);
                         self.set_julia_constant(constant[0], constant[1]);
                     }
-                    FractalType::Mandelbrot => self.set_julia(false)
+                    FractalType::Mandelbrot => {
+                        self.scene.fractal = FractalKind::Mandelbrot;
+                        self.set_julia(false)
+                    }
+                    FractalType::Lyapunov { sequence, warmup } => {
+                        self.scene.fractal = FractalKind::Lyapunov;
+                        self.scene.lyapunov_sequence = *sequence;
+                        self.scene.lyapunov_warmup = *warmup;
+                        self.set_julia(false);
+                        self.set_lyapunov(*sequence, *warmup);
+                    }
                 }
+                self.set_formula(self.scene.fractal, self.scene.formula);
             }
-            ObserverEvent::FractalAxisRange { x, y } => {
-                self.set_x_axis_range(x[0], x[1]);
-                self.set_y_axis_range(y[0], y[1]);
+            ObserverEvent::FractalFormula{formula, parameter} => {
+                self.scene.formula = *formula;
+                self.scene.formula_parameter = *parameter;
+                self.set_formula(self.scene.fractal, *formula);
+                self.set_formula_parameter(parameter[0], parameter[1]);
+            }
+            ObserverEvent::FractalBailout{radius, norm} => {
+                self.scene.bailout = *radius;
+                self.scene.escape_norm = *norm;
+                self.set_bailout(*radius, *norm);
+            },
+            ObserverEvent::FractalCamera(camera) => {
+                self.scene.camera = *camera;
+                self.set_view_transform(camera);
+            },
+            ObserverEvent::FractalHSVScaleChange { h, s, v } => {
+                self.scene.hsv_scale = [*h, *s, *v];
+                self.set_hsv_scale(*h, *s, *v);
+            },
+            ObserverEvent::FractalPaletteOffset(offset) => {
+                self.scene.palette_offset = *offset;
+                self.set_palette_offset(*offset);
+            },
+            ObserverEvent::FractalTerminalColorChange { r,g, b } => {
+                self.scene.terminal_color = [*r, *g, *b];
+                self.set_terminal_color(*r, *g, *b);
             },
-            ObserverEvent::FractalHSVScaleChange { h, s, v } => self.set_hsv_scale(*h, *s, *v),
-            ObserverEvent::FractalTerminalColorChange { r,g, b } => self.set_terminal_color(*r, *g, *b),
+            ObserverEvent::FractalJuliaPane(scene) => self.julia_pane = *scene,
+            ObserverEvent::Fractal3D(scene) => self.scene_3d = *scene,
+            ObserverEvent::Screenshot => self.screenshot_requested = true,
             _ => {}
         }
     }
@@ -0,0 +1,237 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use crate::renderer::{image_writer, Error, FractalRenderer, RenderTarget};
+use crate::renderer::image_export::{poll_saving, ExportJob};
+use crate::scene::Scene;
+
+/// Where the frames of an animation are written to
+#[derive(Clone, PartialEq, Debug)]
+pub enum FrameOutput {
+    /// numbered PNG files `frame_00000.png`, `frame_00001.png`, ... in a directory
+    Png(PathBuf),
+    /// a video file encoded by piping the frames to `ffmpeg`, which has to be on the `PATH`
+    Ffmpeg(PathBuf),
+    /// an animated GIF that loops forever, encoded on a background thread
+    Gif(PathBuf),
+}
+
+impl FrameOutput {
+    pub fn path(&self) -> &Path {
+        match self {
+            FrameOutput::Png(path) | FrameOutput::Ffmpeg(path) | FrameOutput::Gif(path) => path,
+        }
+    }
+}
+
+/// frames waiting for the GIF encoder at most
+const GIF_QUEUE_LENGTH: usize = 4;
+
+/// channel passing frames to the GIF encoder thread, and the thread itself
+type GifEncoder = (SyncSender<Vec<u8>>, JoinHandle<Result<(), Error>>);
+
+/// Export of a sequence of scenes as the frames of an animation
+/// every frame is rendered offscreen in one piece, one after another
+pub struct FrameExport {
+    frames: Vec<Scene>,
+    size: [i32; 2],
+    output: FrameOutput,
+
+    target: RenderTarget,
+    next_frame: usize,
+    ffmpeg: Option<Child>,
+    /// frames are passed to the GIF encoder thread through this channel
+    gif: Option<GifEncoder>,
+
+    saving: Option<JoinHandle<Result<(), Error>>>,
+}
+
+impl FrameExport {
+    /// prepare the export, the cameras are widened or narrowed to the aspect ratio of the frames
+    /// for [`FrameOutput::Ffmpeg`] and [`FrameOutput::Gif`] the encoder is started right away
+    pub fn new(frames: Vec<Scene>, width: i32, height: i32, fps: f64, output: FrameOutput) -> Result<Self, Error> {
+        let to_error = |message: String| Error::ImageWrite {
+            name: output.path().to_string_lossy().into_owned(),
+            message,
+        };
+
+        if frames.is_empty() {
+            return Err(to_error("The animation has no frames".to_string()));
+        }
+
+        let mut gif = None;
+        let ffmpeg = match &output {
+            FrameOutput::Png(directory) => {
+                std::fs::create_dir_all(directory).map_err(|e| to_error(e.to_string()))?;
+                None
+            },
+            FrameOutput::Gif(path) => {
+                gif = Some(start_gif_encoder(path, width, height, fps).map_err(to_error)?);
+                None
+            },
+            FrameOutput::Ffmpeg(path) => {
+                let child = Command::new("ffmpeg")
+                    .args(["-y", "-f", "rawvideo", "-pix_fmt", "rgba"])
+                    .args(["-s", &format!("{}x{}", width, height), "-r", &fps.to_string(), "-i", "-"])
+                    // yuv420p needs even sizes, an odd row or column is padded
+                    .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-pix_fmt", "yuv420p"])
+                    .arg(path)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .spawn()
+                    .map_err(|e| to_error(format!("Failed to start ffmpeg: {}", e)))?;
+                Some(child)
+            },
+        };
+
+        let frames = frames.into_iter().map(|mut scene| {
+            scene.camera.width = scene.camera.height * width as f64 / height as f64;
+            scene
+        }).collect();
+
+        Ok(Self {
+            frames,
+            size: [width, height],
+            output,
+            target: RenderTarget::new(width, height)?,
+            next_frame: 0,
+            ffmpeg,
+            gif,
+            saving: None,
+        })
+    }
+
+    /// render the next frame and write it to its file or to the encoder
+    fn render_frame(&mut self, renderer: &FractalRenderer) -> Result<(), Error> {
+        renderer.render_scene_to(&self.frames[self.next_frame], &self.target);
+        self.target.bind();
+        let pixels = image_writer::read_pixels(0, 0, self.size[0], self.size[1]);
+        self.target.unbind();
+
+        match (&self.output, &mut self.ffmpeg, &self.gif) {
+            (FrameOutput::Ffmpeg(path), Some(ffmpeg), _) => {
+                let stdin = ffmpeg.stdin.as_mut().expect("ffmpeg is started with a piped stdin");
+                stdin.write_all(&pixels).map_err(|e| Error::ImageWrite {
+                    name: path.to_string_lossy().into_owned(),
+                    message: format!("Failed to pass the frame to ffmpeg: {}", e),
+                })?;
+            },
+            (FrameOutput::Gif(path), _, Some((frames, _))) => {
+                // the encoder only stops listening after it failed, report its error instead
+                if frames.send(pixels).is_err() {
+                    let (_, encoder) = self.gif.take().expect("the encoder was just used");
+                    return Err(match encoder.join() {
+                        Ok(Err(err)) => err,
+                        _ => Error::ImageWrite { name: path.to_string_lossy().into_owned(), message: "The GIF encoder stopped".to_string() },
+                    });
+                }
+            },
+            (output, _, _) => {
+                let path = output.path().join(format!("frame_{:05}.png", self.next_frame));
+                image_writer::save_png(&path, self.size[0] as u32, self.size[1] as u32, &pixels, &[])?;
+            },
+        }
+
+        self.next_frame += 1;
+        Ok(())
+    }
+
+    /// close the input of the encoder and wait for it to finish on a background thread
+    fn start_saving(&mut self) {
+        if let Some((frames, encoder)) = self.gif.take() {
+            // dropping the sender ends the frame loop of the encoder thread
+            drop(frames);
+            self.saving = Some(encoder);
+            return;
+        }
+
+        let name = self.output.path().to_string_lossy().into_owned();
+        let ffmpeg = self.ffmpeg.take();
+
+        self.saving = Some(std::thread::spawn(move || {
+            let Some(mut ffmpeg) = ffmpeg else { return Ok(()) };
+
+            // closing stdin tells ffmpeg that there are no more frames
+            drop(ffmpeg.stdin.take());
+            match ffmpeg.wait() {
+                Ok(status) if status.success() => Ok(()),
+                Ok(status) => Err(Error::ImageWrite { name, message: format!("ffmpeg failed with {}", status) }),
+                Err(e) => Err(Error::ImageWrite { name, message: e.to_string() }),
+            }
+        }));
+    }
+}
+
+impl Drop for FrameExport {
+    /// stop an encoder that is left over when the export is cancelled or failed, so it does not linger
+    fn drop(&mut self) {
+        if let Some(mut ffmpeg) = self.ffmpeg.take() {
+            let _ = ffmpeg.kill();
+            let _ = ffmpeg.wait();
+        }
+    }
+}
+
+/// create the GIF file and start a thread that quantizes and writes every frame sent to it
+/// the thread returns when the sender is dropped
+fn start_gif_encoder(path: &Path, width: i32, height: i32, fps: f64) -> Result<GifEncoder, String> {
+    let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err("GIF images can be at most 65535 pixels wide and high".to_string());
+    };
+
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &[]).map_err(|e| e.to_string())?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+    // GIF delays are given in hundredths of a second
+    let delay = (100.0 / fps).round().max(1.0) as u16;
+
+    let name = path.to_string_lossy().into_owned();
+    // rendering is much faster than quantizing, a full queue holds the rendering back instead of piling up frames in memory
+    let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(GIF_QUEUE_LENGTH);
+    let encoder = std::thread::spawn(move || {
+        for mut pixels in receiver {
+            let mut frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+            frame.delay = delay;
+            encoder.write_frame(&frame).map_err(|e| Error::ImageWrite { name: name.clone(), message: e.to_string() })?;
+        }
+        Ok(())
+    });
+
+    Ok((sender, encoder))
+}
+
+impl ExportJob for FrameExport {
+    fn step(&mut self, renderer: &FractalRenderer, budget: Duration) -> Option<Result<(), Error>> {
+        if self.saving.is_some() {
+            return poll_saving(&mut self.saving, self.output.path());
+        }
+
+        let start = Instant::now();
+        while self.next_frame < self.frames.len() && start.elapsed() < budget {
+            if let Err(err) = self.render_frame(renderer) {
+                return Some(Err(err));
+            }
+        }
+
+        if self.next_frame == self.frames.len() {
+            self.start_saving();
+        }
+        None
+    }
+
+    fn progress(&self) -> f32 {
+        self.next_frame as f32 / self.frames.len() as f32
+    }
+
+    fn is_saving(&self) -> bool {
+        self.saving.is_some()
+    }
+
+    fn path(&self) -> &Path {
+        self.output.path()
+    }
+}
@@ -0,0 +1,211 @@
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use crate::location_code;
+use crate::renderer::{image_writer, Error, FractalRenderer, RenderTarget};
+use crate::scene::Scene;
+
+/// Size and quality of an exported image
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ExportSettings {
+    pub width: i32,
+    pub height: i32,
+    /// samples per pixel along each axis, the pixel color is the average of `samples * samples` samples
+    pub samples: i32,
+    /// place every sample at a random position inside its cell of the sample grid instead of its center
+    pub jitter: bool,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            width: 3840,
+            height: 2160,
+            samples: 2,
+            jitter: false,
+        }
+    }
+}
+
+/// Long running export that is advanced a little every frame
+pub trait ExportJob {
+    /// render until the time budget is used up, once everything is rendered the file is written on a background thread
+    /// returns the result of writing the file when it is done
+    fn step(&mut self, renderer: &FractalRenderer, budget: Duration) -> Option<Result<(), Error>>;
+
+    /// fraction of the rendered work, between 0 and 1
+    fn progress(&self) -> f32;
+
+    /// check if everything is rendered and the file is being written
+    fn is_saving(&self) -> bool;
+
+    fn path(&self) -> &Path;
+}
+
+/// check if the background thread writing a file is done and take its result
+pub fn poll_saving(saving: &mut Option<JoinHandle<Result<(), Error>>>, path: &Path) -> Option<Result<(), Error>> {
+    if !saving.as_ref()?.is_finished() {
+        return None;
+    }
+
+    Some(saving.take()?.join().unwrap_or_else(|_| Err(Error::ImageWrite {
+        name: path.to_string_lossy().into_owned(),
+        message: "Writing the file panicked".to_string(),
+    })))
+}
+
+/// Export of a scene into a PNG image of arbitrary size
+/// the image is rendered in tiles through an offscreen render target and stitched together on the CPU,
+/// a few samples are rendered per call to [`ExportJob::step`], so the application stays responsive
+pub struct ImageExport {
+    scene: Scene,
+    settings: ExportSettings,
+    path: PathBuf,
+
+    target: RenderTarget,
+    tiles_x: i32,
+    tiles_y: i32,
+    next_tile: i32,
+    next_sample: i32,
+    /// sum of the samples of the current tile
+    accumulator: Vec<u32>,
+    pixels: Vec<u8>,
+
+    saving: Option<JoinHandle<Result<(), Error>>>,
+}
+
+impl ImageExport {
+    /// maximum size of a tile in pixels
+    const TILE_SIZE: i32 = 1024;
+
+    /// prepare the export of the scene, the camera is widened or narrowed to the aspect ratio of the image
+    pub fn new(scene: &Scene, settings: ExportSettings, path: PathBuf) -> Result<Self, Error> {
+        if settings.width <= 0 || settings.height <= 0 || settings.samples <= 0 {
+            return Err(Error::ImageWrite {
+                name: path.to_string_lossy().into_owned(),
+                message: format!("Invalid image size {}x{} with {} samples", settings.width, settings.height, settings.samples),
+            });
+        }
+
+        let mut scene = *scene;
+        scene.camera.width = scene.camera.height * settings.width as f64 / settings.height as f64;
+
+        let tile_width = settings.width.min(Self::TILE_SIZE);
+        let tile_height = settings.height.min(Self::TILE_SIZE);
+
+        Ok(Self {
+            scene,
+            settings,
+            path,
+            target: RenderTarget::new(tile_width, tile_height)?,
+            tiles_x: (settings.width + tile_width - 1) / tile_width,
+            tiles_y: (settings.height + tile_height - 1) / tile_height,
+            next_tile: 0,
+            next_sample: 0,
+            accumulator: vec![0; (tile_width * tile_height * 4) as usize],
+            pixels: vec![0; settings.width as usize * settings.height as usize * 4],
+            saving: None,
+        })
+    }
+
+    /// render the next sample of the current tile and add it to the accumulator
+    /// the tile is copied into the image after its last sample
+    fn render_sample(&mut self, renderer: &FractalRenderer) {
+        let [tile_width, tile_height] = [self.target.width(), self.target.height()];
+        let samples = self.settings.samples;
+
+        // top left corner of the tile in image pixels
+        let x = self.next_tile % self.tiles_x * tile_width;
+        let y = self.next_tile / self.tiles_x * tile_height;
+
+        // offset of the sample inside the pixel, also in pixels
+        let (cell_x, cell_y) = (self.next_sample % samples, self.next_sample / samples);
+        let (jitter_x, jitter_y) = if self.settings.jitter { (fastrand::f64(), fastrand::f64()) } else { (0.5, 0.5) };
+        let offset_x = (cell_x as f64 + jitter_x) / samples as f64 - 0.5;
+        let offset_y = (cell_y as f64 + jitter_y) / samples as f64 - 0.5;
+
+        // tiles at the right and bottom edge are rendered at full size and cropped when reading back
+        let camera = self.scene.camera.tile([x, y], [tile_width, tile_height], [self.settings.width, self.settings.height], [offset_x, offset_y]);
+        let tile_scene = Scene { camera, ..self.scene };
+
+        renderer.render_scene_to(&tile_scene, &self.target);
+
+        let crop_width = tile_width.min(self.settings.width - x);
+        let crop_height = tile_height.min(self.settings.height - y);
+        self.target.bind();
+        let pixels = image_writer::read_pixels(0, tile_height - crop_height, crop_width, crop_height);
+        self.target.unbind();
+
+        for (sum, value) in self.accumulator.iter_mut().zip(pixels) {
+            *sum += value as u32;
+        }
+
+        self.next_sample += 1;
+        if self.next_sample == samples * samples {
+            self.copy_tile(x, y, crop_width, crop_height);
+            self.accumulator.fill(0);
+            self.next_sample = 0;
+            self.next_tile += 1;
+        }
+    }
+
+    /// average the accumulated samples and copy them into the image
+    fn copy_tile(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        let count = (self.settings.samples * self.settings.samples) as u32;
+        let row_size = width as usize * 4;
+
+        for (row, sums) in self.accumulator.chunks_exact(row_size).take(height as usize).enumerate() {
+            let start = ((y as usize + row) * self.settings.width as usize + x as usize) * 4;
+            for (pixel, sum) in self.pixels[start..start + row_size].iter_mut().zip(sums) {
+                *pixel = ((sum + count / 2) / count) as u8;
+            }
+        }
+    }
+
+    /// write the finished image on a background thread, the scene is stored in text chunks
+    fn start_saving(&mut self) {
+        let mut text = vec![
+            ("Software", "fractal_renderer".to_string()),
+            ("Location", location_code::encode(&self.scene)),
+            ("Supersampling", format!("{0}x{0}{1}", self.settings.samples, if self.settings.jitter { " jittered" } else { "" })),
+        ];
+        text.extend(self.scene.fields());
+
+        let path = self.path.clone();
+        let (width, height) = (self.settings.width as u32, self.settings.height as u32);
+        let pixels = std::mem::take(&mut self.pixels);
+        self.saving = Some(std::thread::spawn(move || image_writer::save_png(&path, width, height, &pixels, &text)));
+    }
+}
+
+impl ExportJob for ImageExport {
+    fn step(&mut self, renderer: &FractalRenderer, budget: Duration) -> Option<Result<(), Error>> {
+        if self.saving.is_some() {
+            return poll_saving(&mut self.saving, &self.path);
+        }
+
+        let start = Instant::now();
+        while self.next_tile < self.tiles_x * self.tiles_y && start.elapsed() < budget {
+            self.render_sample(renderer);
+        }
+
+        if self.next_tile == self.tiles_x * self.tiles_y {
+            self.start_saving();
+        }
+        None
+    }
+
+    fn progress(&self) -> f32 {
+        let samples_per_tile = self.settings.samples * self.settings.samples;
+        let done = self.next_tile * samples_per_tile + self.next_sample;
+        done as f32 / (self.tiles_x * self.tiles_y * samples_per_tile) as f32
+    }
+
+    fn is_saving(&self) -> bool {
+        self.saving.is_some()
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
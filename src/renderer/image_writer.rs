@@ -0,0 +1,195 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use gl::types::GLvoid;
+use crate::renderer::Error;
+
+/// read the RGBA pixels of the currently bound framebuffer
+/// rows are returned top to bottom, the way image files store them
+pub fn read_pixels(x: i32, y: i32, width: i32, height: i32) -> Vec<u8> {
+    let row_size = width as usize * 4;
+    let mut pixels = vec![0u8; row_size * height as usize];
+
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(x, y, width, height, gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut GLvoid);
+    }
+
+    // OpenGL stores the rows bottom to top
+    let mut flipped = Vec::with_capacity(pixels.len());
+    for row in pixels.chunks_exact(row_size).rev() {
+        flipped.extend_from_slice(row);
+    }
+    flipped
+}
+
+/// read the floating point RGBA values of the currently bound framebuffer
+/// rows are returned top to bottom, the way image files store them
+pub fn read_pixels_f32(x: i32, y: i32, width: i32, height: i32) -> Vec<f32> {
+    let row_size = width as usize * 4;
+    let mut values = vec![0f32; row_size * height as usize];
+
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(x, y, width, height, gl::RGBA, gl::FLOAT, values.as_mut_ptr() as *mut GLvoid);
+    }
+
+    // OpenGL stores the rows bottom to top
+    let mut flipped = Vec::with_capacity(values.len());
+    for row in values.chunks_exact(row_size).rev() {
+        flipped.extend_from_slice(row);
+    }
+    flipped
+}
+
+/// write 8-bit RGBA pixels to a PNG file
+/// the `text` pairs are stored as keyword and text of uncompressed text chunks
+pub fn save_png(path: &Path, width: u32, height: u32, pixels: &[u8], text: &[(&str, String)]) -> Result<(), Error> {
+    let to_error = |message: String| Error::ImageWrite {
+        name: path.to_string_lossy().into_owned(),
+        message,
+    };
+
+    let file = File::create(path).map_err(|e| to_error(e.to_string()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    for (keyword, value) in text {
+        encoder.add_text_chunk(keyword.to_string(), value.clone()).map_err(|e| to_error(e.to_string()))?;
+    }
+
+    let mut writer = encoder.write_header().map_err(|e| to_error(e.to_string()))?;
+    writer.write_image_data(pixels).map_err(|e| to_error(e.to_string()))
+}
+
+/// write single channel 16-bit values to a grayscale PNG file
+pub fn save_png16(path: &Path, width: u32, height: u32, values: &[u16], text: &[(&str, String)]) -> Result<(), Error> {
+    let to_error = |message: String| Error::ImageWrite {
+        name: path.to_string_lossy().into_owned(),
+        message,
+    };
+
+    let file = File::create(path).map_err(|e| to_error(e.to_string()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Sixteen);
+    for (keyword, value) in text {
+        encoder.add_text_chunk(keyword.to_string(), value.clone()).map_err(|e| to_error(e.to_string()))?;
+    }
+
+    // PNG stores 16-bit samples big endian
+    let bytes: Vec<u8> = values.iter().flat_map(|value| value.to_be_bytes()).collect();
+    let mut writer = encoder.write_header().map_err(|e| to_error(e.to_string()))?;
+    writer.write_image_data(&bytes).map_err(|e| to_error(e.to_string()))
+}
+
+/// write single channel 16-bit values to a grayscale TIFF file
+pub fn save_tiff16(path: &Path, width: u32, height: u32, values: &[u16]) -> Result<(), Error> {
+    let to_error = |message: String| Error::ImageWrite {
+        name: path.to_string_lossy().into_owned(),
+        message,
+    };
+
+    let file = File::create(path).map_err(|e| to_error(e.to_string()))?;
+    let mut encoder = tiff::encoder::TiffEncoder::new(BufWriter::new(file)).map_err(|e| to_error(e.to_string()))?;
+    encoder.write_image::<tiff::encoder::colortype::Gray16>(width, height, values).map_err(|e| to_error(e.to_string()))
+}
+
+/// write named 32-bit float channels to a single layer OpenEXR file
+/// every channel holds `width * height` values, rows top to bottom
+pub fn save_exr(path: &Path, width: usize, height: usize, channels: Vec<(&str, Vec<f32>)>, comment: &str) -> Result<(), Error> {
+    use exr::prelude::{AnyChannel, AnyChannels, Encoding, FlatSamples, Image, Layer, LayerAttributes, Text, WritableImage};
+
+    let channels = channels.into_iter()
+        .map(|(name, values)| AnyChannel::new(name, FlatSamples::F32(values)))
+        .collect();
+
+    let mut attributes = LayerAttributes::named("fractal");
+    attributes.comments = Some(Text::from(comment));
+
+    let layer = Layer::new((width, height), attributes, Encoding::FAST_LOSSLESS, AnyChannels::sort(channels));
+    Image::from_layer(layer).write().to_file(path).map_err(|e| Error::ImageWrite {
+        name: path.to_string_lossy().into_owned(),
+        message: e.to_string(),
+    })
+}
+
+/// header of a version 1.0 NumPy `.npy` file holding a little endian 32-bit float array of the given shape in C order
+/// padded with spaces so the data starts at a multiple of 64 bytes
+pub fn npy_header(shape: &[usize]) -> Vec<u8> {
+    let dimensions: Vec<String> = shape.iter().map(|length| length.to_string()).collect();
+    let trailing_comma = if shape.len() == 1 { "," } else { "" };
+    let mut dict = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({}{}), }}", dimensions.join(", "), trailing_comma);
+    let unpadded = 10 + dict.len() + 1;
+    dict.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    dict.push('\n');
+
+    let mut header = Vec::with_capacity(10 + dict.len());
+    header.extend(b"\x93NUMPY\x01\x00");
+    header.extend((dict.len() as u16).to_le_bytes());
+    header.extend(dict.as_bytes());
+    header
+}
+
+/// write planes of 32-bit floats into a NumPy `.npy` file of shape `(channels, height, width)`
+/// every plane is the channel of the given index with `width * height` values, rows top to bottom,
+/// the file is created at its full size by the first call, so the channels can be written in any order and any number of calls
+pub fn write_npy_planes(path: &Path, shape: [usize; 3], planes: &[(usize, Vec<f32>)]) -> Result<(), Error> {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let to_error = |e: std::io::Error| Error::ImageWrite {
+        name: path.to_string_lossy().into_owned(),
+        message: e.to_string(),
+    };
+
+    let header = npy_header(&shape);
+    let plane_size = (shape[1] * shape[2] * 4) as u64;
+    let file = std::fs::OpenOptions::new().write(true).create(true).truncate(false).open(path).map_err(to_error)?;
+    file.set_len(header.len() as u64 + shape[0] as u64 * plane_size).map_err(to_error)?;
+
+    let mut writer = BufWriter::new(file);
+    writer.write_all(&header).map_err(to_error)?;
+    for (channel, values) in planes {
+        writer.seek(SeekFrom::Start(header.len() as u64 + *channel as u64 * plane_size)).map_err(to_error)?;
+        for value in values {
+            writer.write_all(&value.to_le_bytes()).map_err(to_error)?;
+        }
+    }
+    writer.flush().map_err(to_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn npy_header_layout() {
+        let header = npy_header(&[5, 480, 640]);
+        assert_eq!(&header[..8], b"\x93NUMPY\x01\x00");
+        assert_eq!(u16::from_le_bytes([header[8], header[9]]) as usize, header.len() - 10);
+        assert_eq!(header.len() % 64, 0);
+        assert_eq!(header.last(), Some(&b'\n'));
+
+        let dict = std::str::from_utf8(&header[10..]).unwrap();
+        assert!(dict.starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (5, 480, 640), }"));
+        // only spaces pad the dict
+        assert!(dict.trim_end().ends_with('}'));
+        // a tuple of one element needs its comma
+        assert!(String::from_utf8_lossy(&npy_header(&[7])).contains("'shape': (7,)"));
+    }
+
+    #[test]
+    fn npy_planes_in_any_order() {
+        let path = std::env::temp_dir().join(format!("fractal_renderer_test_{}.npy", std::process::id()));
+        let shape = [3, 1, 2];
+        write_npy_planes(&path, shape, &[(2, vec![5.0, 6.0]), (0, vec![1.0, 2.0])]).unwrap();
+        write_npy_planes(&path, shape, &[(1, vec![3.0, 4.0])]).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let header = npy_header(&shape);
+        assert_eq!(&bytes[..header.len()], header.as_slice());
+        let values: Vec<f32> = bytes[header.len()..].chunks_exact(4).map(|value| f32::from_le_bytes(value.try_into().unwrap())).collect();
+        assert_eq!(values, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+}
@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use gl::types::{GLuint, GLvoid};
+
+use crate::renderer::{Camera, Program, Shader};
+use crate::resources::Resources;
+
+/// OpenGL wrapper drawing lines on the plane of the camera, used for the L-system fractals
+/// the lines are uploaded once and drawn every frame with the current camera
+pub struct LineRenderer {
+    program: Program,
+
+    vertex_array: GLuint,
+    vertex_buffer: GLuint,
+    vertex_count: i32,
+}
+
+impl LineRenderer {
+    /// Create a new line renderer
+    /// loads the line shaders from the assets folder and creates an empty vertex buffer
+    pub fn new() -> Self {
+        let shaders_resources = Resources::from_relative_exe_path(Path::new("assets\\shaders")).unwrap();
+        let vertex_shader = Shader::from_resources(&shaders_resources, "lines.vert", gl::VERTEX_SHADER).unwrap();
+        let fragment_shader = Shader::from_resources(&shaders_resources, "lines.frag", gl::FRAGMENT_SHADER).unwrap();
+        let program = Program::from_shaders(&[&vertex_shader, &fragment_shader]).unwrap();
+
+        let mut vertex_array = 0;
+        let mut vertex_buffer = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vertex_array);
+            gl::GenBuffers(1, &mut vertex_buffer);
+
+            gl::BindVertexArray(vertex_array);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
+            // every vertex is a point of the plane
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, 2 * std::mem::size_of::<f32>() as i32, std::ptr::null());
+            gl::EnableVertexAttribArray(0);
+
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+
+        Self {
+            program,
+            vertex_array,
+            vertex_buffer,
+            vertex_count: 0,
+        }
+    }
+
+    /// replace the lines, every segment goes from its first to its second point
+    pub fn set_lines(&mut self, segments: &[[[f64; 2]; 2]]) {
+        let vertices: Vec<f32> = segments.iter().flatten().flatten().map(|value| *value as f32).collect();
+        self.vertex_count = (segments.len() * 2) as i32;
+
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vertex_buffer);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (vertices.len() * std::mem::size_of::<f32>()) as isize,
+                vertices.as_ptr() as *const GLvoid,
+                gl::STATIC_DRAW
+            );
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+    }
+
+    /// draw the lines as seen by the camera into the current viewport
+    pub fn render(&self, camera: &Camera, color: [f32; 3]) {
+        // the inverse view transform maps to normalized screen coordinates from 0 to 1, clip space goes from -1 to 1
+        let m = camera.inverse_view_transform();
+        let matrix = [2.0 * m[0], 2.0 * m[1], 2.0 * m[2], 2.0 * m[3], 2.0 * m[4] - 1.0, 2.0 * m[5] - 1.0].map(|value| value as f32);
+
+        self.program.use_program();
+        if let Err(err) = self.program.set_mat3x2(c"plane_to_clip", &matrix) {
+            eprintln!("Error setting plane_to_clip: {:?}", err);
+        }
+        if let Err(err) = self.program.set_f32_3(c"line_color", color[0], color[1], color[2]) {
+            eprintln!("Error setting line_color: {:?}", err);
+        }
+
+        unsafe {
+            gl::BindVertexArray(self.vertex_array);
+            gl::DrawArrays(gl::LINES, 0, self.vertex_count);
+            gl::BindVertexArray(0);
+        }
+    }
+}
+
+impl Drop for LineRenderer {
+    /// drop the vertex array and buffer when the line renderer goes out of scope
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vertex_array);
+            gl::DeleteBuffers(1, &self.vertex_buffer);
+        }
+    }
+}
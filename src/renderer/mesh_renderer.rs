@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use gl::types::{GLuint, GLvoid};
+
+use crate::heightfield::Mesh;
+use crate::renderer::{Program, Shader};
+use crate::resources::Resources;
+use crate::scene3d::Camera3D;
+
+/// OpenGL wrapper drawing a lit triangle mesh with vertex colors, used for the height field of the escape time
+/// the mesh is uploaded once and drawn every frame with the current 3D camera
+pub struct MeshRenderer {
+    program: Program,
+
+    vertex_array: GLuint,
+    vertex_buffer: GLuint,
+    element_buffer: GLuint,
+    index_count: i32,
+}
+
+impl MeshRenderer {
+    /// Create a new mesh renderer
+    /// loads the mesh shaders from the assets folder and creates empty buffers
+    pub fn new() -> Self {
+        let shaders_resources = Resources::from_relative_exe_path(Path::new("assets\\shaders")).unwrap();
+        let vertex_shader = Shader::from_resources(&shaders_resources, "mesh.vert", gl::VERTEX_SHADER).unwrap();
+        let fragment_shader = Shader::from_resources(&shaders_resources, "mesh.frag", gl::FRAGMENT_SHADER).unwrap();
+        let program = Program::from_shaders(&[&vertex_shader, &fragment_shader]).unwrap();
+
+        let mut vertex_array = 0;
+        let mut vertex_buffer = 0;
+        let mut element_buffer = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vertex_array);
+            gl::GenBuffers(1, &mut vertex_buffer);
+            gl::GenBuffers(1, &mut element_buffer);
+
+            gl::BindVertexArray(vertex_array);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, element_buffer);
+            // every vertex is a position, a normal and a color
+            let stride = 9 * std::mem::size_of::<f32>() as i32;
+            for attribute in 0..3 {
+                let offset = (3 * attribute as usize * std::mem::size_of::<f32>()) as *const GLvoid;
+                gl::VertexAttribPointer(attribute, 3, gl::FLOAT, gl::FALSE, stride, offset);
+                gl::EnableVertexAttribArray(attribute);
+            }
+
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+
+        Self {
+            program,
+            vertex_array,
+            vertex_buffer,
+            element_buffer,
+            index_count: 0,
+        }
+    }
+
+    /// replace the mesh
+    pub fn set_mesh(&mut self, mesh: &Mesh) {
+        let vertices: Vec<f32> = mesh.positions.iter().zip(&mesh.normals).zip(&mesh.colors)
+            .flat_map(|((position, normal), color)| [*position, *normal, *color])
+            .flatten()
+            .collect();
+        let indices: Vec<u32> = mesh.triangles.iter().flatten().copied().collect();
+        self.index_count = indices.len() as i32;
+
+        unsafe {
+            gl::BindVertexArray(self.vertex_array);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vertex_buffer);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (vertices.len() * std::mem::size_of::<f32>()) as isize,
+                vertices.as_ptr() as *const GLvoid,
+                gl::STATIC_DRAW
+            );
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                (indices.len() * std::mem::size_of::<u32>()) as isize,
+                indices.as_ptr() as *const GLvoid,
+                gl::STATIC_DRAW
+            );
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+    }
+
+    /// draw the mesh as seen by the camera into the current viewport of the given size
+    /// the depth buffer is cleared and depth testing is only enabled while drawing
+    pub fn render(&self, camera: &Camera3D, width: f32, height: f32) {
+        let position = camera.position();
+
+        self.program.use_program();
+        if let Err(err) = self.program.set_mat4(c"view_projection", &camera.view_projection(width / height.max(1.0))) {
+            eprintln!("Error setting view_projection: {:?}", err);
+        }
+        if let Err(err) = self.program.set_f32_3(c"camera_position", position[0], position[1], position[2]) {
+            eprintln!("Error setting camera_position: {:?}", err);
+        }
+
+        unsafe {
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::BindVertexArray(self.vertex_array);
+            gl::DrawElements(gl::TRIANGLES, self.index_count, gl::UNSIGNED_INT, std::ptr::null());
+            gl::BindVertexArray(0);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+impl Drop for MeshRenderer {
+    /// drop the vertex array and buffers when the mesh renderer goes out of scope
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vertex_array);
+            gl::DeleteBuffers(1, &self.vertex_buffer);
+            gl::DeleteBuffers(1, &self.element_buffer);
+        }
+    }
+}
@@ -2,8 +2,20 @@ mod shader;
 mod program;
 mod renderer_error;
 mod fractal_renderer;
+mod camera;
+mod render_target;
+mod line_renderer;
+mod mesh_renderer;
+pub mod image_writer;
+pub mod image_export;
+pub mod data_export;
+pub mod frame_export;
 
 pub use self::shader::Shader;
 pub use self::program::Program;
 pub use self::renderer_error::Error;
-pub use self::fractal_renderer::FractalRenderer;
\ No newline at end of file
+pub use self::fractal_renderer::{DataLayer, FractalRenderer};
+pub use self::camera::Camera;
+pub use self::render_target::RenderTarget;
+pub use self::line_renderer::LineRenderer;
+pub use self::mesh_renderer::MeshRenderer;
\ No newline at end of file
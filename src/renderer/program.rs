@@ -116,6 +116,40 @@ impl Program {
         self.program_id
     }
 
+    /// set a `mat3x2` uniform value in the shader program
+    /// the matrix is expected in column major order
+    pub fn set_mat3x2(&self, uniform_name: &CStr, matrix: &[f32; 6]) -> Result<(), Error> {
+        // try to get the location of the uniform
+        let location = unsafe { gl::GetUniformLocation(self.program_id, uniform_name.to_bytes_with_nul().as_ptr() as *const GLchar) };
+        // if the location is -1 the uniform was not found
+        if location == -1 {
+            return Err(Error::SetUniformError {
+                name: uniform_name.to_string_lossy().into_owned(),
+                message: "Uniform not found".into(),
+            });
+        }
+        // set the uniform value
+        unsafe { gl::UniformMatrix3x2fv(location, 1, gl::FALSE, matrix.as_ptr()) };
+        Ok(())
+    }
+
+    /// set a `mat4` uniform value in the shader program
+    /// the matrix is expected in column major order
+    pub fn set_mat4(&self, uniform_name: &CStr, matrix: &[f32; 16]) -> Result<(), Error> {
+        // try to get the location of the uniform
+        let location = unsafe { gl::GetUniformLocation(self.program_id, uniform_name.to_bytes_with_nul().as_ptr() as *const GLchar) };
+        // if the location is -1 the uniform was not found
+        if location == -1 {
+            return Err(Error::SetUniformError {
+                name: uniform_name.to_string_lossy().into_owned(),
+                message: "Uniform not found".into(),
+            });
+        }
+        // set the uniform value
+        unsafe { gl::UniformMatrix4fv(location, 1, gl::FALSE, matrix.as_ptr()) };
+        Ok(())
+    }
+
     // generate the set_uniform functions
     set_shader_uniform!(set_f32, gl::Uniform1f, x: f32);
     set_shader_uniform!(set_i32, gl::Uniform1i, x: i32);
@@ -0,0 +1,104 @@
+use gl::types::{GLenum, GLuint};
+use crate::renderer::Error;
+
+/// Offscreen framebuffer with a single RGBA color texture
+/// used for rendering thumbnails and image tiles without touching the window
+/// the texture stores 8 bit colors or, for raw data, 32 bit floats
+pub struct RenderTarget {
+    framebuffer: GLuint,
+    texture: GLuint,
+    width: i32,
+    height: i32,
+}
+
+impl RenderTarget {
+    /// create a new framebuffer with an 8 bit color texture of the given size
+    /// returns an [`Error::Framebuffer`] if the framebuffer is incomplete
+    pub fn new(width: i32, height: i32) -> Result<RenderTarget, Error> {
+        Self::with_format(width, height, gl::RGBA8, gl::UNSIGNED_BYTE)
+    }
+
+    /// create a new framebuffer with a 32 bit floating point texture, the values are not clamped
+    pub fn new_float(width: i32, height: i32) -> Result<RenderTarget, Error> {
+        Self::with_format(width, height, gl::RGBA32F, gl::FLOAT)
+    }
+
+    fn with_format(width: i32, height: i32, internal_format: GLenum, data_type: GLenum) -> Result<RenderTarget, Error> {
+        let mut framebuffer = 0;
+        let mut texture = 0;
+
+        unsafe {
+            // create the color texture
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, internal_format as i32, width, height, 0, gl::RGBA, data_type, std::ptr::null());
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            // attach it to a new framebuffer
+            gl::GenFramebuffers(1, &mut framebuffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture, 0);
+        }
+
+        let status = unsafe { gl::CheckFramebufferStatus(gl::FRAMEBUFFER) };
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) };
+
+        let target = RenderTarget { framebuffer, texture, width, height };
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(Error::Framebuffer {
+                message: format!("Framebuffer of size {}x{} is incomplete, status {:#x}", width, height, status),
+            });
+        }
+
+        Ok(target)
+    }
+
+    /// bind the framebuffer and set the viewport to cover it
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+            gl::Viewport(0, 0, self.width, self.height);
+        }
+    }
+
+    /// bind the default framebuffer of the window again
+    pub fn unbind(&self) {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) };
+    }
+
+    /// replace the contents of an 8 bit color texture with RGBA pixels, the first row ends up at the bottom
+    pub fn upload(&self, pixels: &[u8]) {
+        assert_eq!(pixels.len(), (self.width * self.height * 4) as usize, "pixels do not match the size of the render target");
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+            gl::TexSubImage2D(gl::TEXTURE_2D, 0, 0, 0, self.width, self.height, gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_ptr() as *const _);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
+    /// get the color texture id, it can be passed to imgui as a texture id
+    pub fn texture(&self) -> GLuint {
+        self.texture
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+}
+
+impl Drop for RenderTarget {
+    /// delete the framebuffer and its texture when the render target goes out of scope
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.framebuffer);
+            gl::DeleteTextures(1, &self.texture);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt;
 use crate::resources;
 
 /// Error type for the renderer module
@@ -7,4 +8,19 @@ pub enum Error {
     CompileError { name: String, message: String },
     LinkError { name: String, message: String },
     SetUniformError { name: String, message: String },
+    ImageWrite { name: String, message: String },
+    Framebuffer { message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ResourceLoad { name, inner } => write!(f, "failed to load {}: {:?}", name, inner),
+            Error::CompileError { name, message } => write!(f, "failed to compile {}: {}", name, message),
+            Error::LinkError { name, message } => write!(f, "failed to link {}: {}", name, message),
+            Error::SetUniformError { name, message } => write!(f, "failed to set uniform {}: {}", name, message),
+            Error::ImageWrite { name, message } => write!(f, "failed to write {}: {}", name, message),
+            Error::Framebuffer { message } => write!(f, "{}", message),
+        }
+    }
 }
@@ -55,6 +55,18 @@ impl Resources {
 
         Ok(buffer)
     }
+
+    /// write a string to the resource file, creating missing directories on the way
+    pub fn save_string(&self, resource_name: &str, contents: &str) -> Result<(), Error> {
+        let full_path_buff = resource_name_to_path(&self.root_path, resource_name);
+
+        if let Some(parent) = full_path_buff.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&full_path_buff, contents)?;
+
+        Ok(())
+    }
 }
 
 fn resource_name_to_path(root_dir: &Path, location: &str) -> PathBuf {
@@ -0,0 +1,340 @@
+use crate::lyapunov::LyapunovSequence;
+use crate::renderer::Camera;
+
+/// Fractal formulas that can be rendered
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FractalKind {
+    Mandelbrot,
+    Julia,
+    /// Lyapunov exponents of the logistic map, the plane holds the rates a and b instead of complex numbers
+    Lyapunov,
+}
+
+impl FractalKind {
+    pub const ALL: [FractalKind; 3] = [FractalKind::Mandelbrot, FractalKind::Julia, FractalKind::Lyapunov];
+
+    /// display name of the fractal, also used when saving scenes
+    pub fn name(&self) -> &'static str {
+        match self {
+            FractalKind::Mandelbrot => "Mandelbrot",
+            FractalKind::Julia => "Julia",
+            FractalKind::Lyapunov => "Lyapunov",
+        }
+    }
+
+    /// whether the fractal is drawn on the complex plane by iterating z, so points have orbits
+    pub fn is_complex(&self) -> bool {
+        !matches!(self, FractalKind::Lyapunov)
+    }
+
+    /// camera showing the interesting part of the plane of the fractal, used when resetting the view
+    pub fn home_camera(&self) -> Camera {
+        match self {
+            FractalKind::Mandelbrot | FractalKind::Julia => Camera::default(),
+            FractalKind::Lyapunov => Camera { focus: [3.0, 3.0], ..Camera::default() },
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<FractalKind> {
+        Self::ALL.into_iter().find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Iterated function of the escape-time fractals
+/// the Mandelbrot kind shows the plane of its parameter c and the Julia kind the plane of z for a fixed c
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Formula {
+    /// z^2 + c
+    Quadratic,
+    /// z^2 + c + p z', where z' is the previous value of z
+    Phoenix,
+    /// ((z^2 + c - 1) / (2z + c - 2))^2, converges to 1 outside the escaping region
+    MagnetI,
+    /// the third degree variant of the magnet formula, also converges to 1
+    MagnetII,
+}
+
+impl Formula {
+    pub const ALL: [Formula; 4] = [Formula::Quadratic, Formula::Phoenix, Formula::MagnetI, Formula::MagnetII];
+
+    /// display name of the formula, also used when saving scenes
+    pub fn name(&self) -> &'static str {
+        match self {
+            Formula::Quadratic => "Quadratic",
+            Formula::Phoenix => "Phoenix",
+            Formula::MagnetI => "Magnet I",
+            Formula::MagnetII => "Magnet II",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Formula> {
+        Self::ALL.into_iter().find(|formula| formula.name().eq_ignore_ascii_case(name))
+    }
+
+    /// value of the `formula` uniform of the shader, 1 is taken by the Lyapunov fractal
+    pub fn shader_index(&self) -> i32 {
+        match self {
+            Formula::Quadratic => 0,
+            Formula::Phoenix => 2,
+            Formula::MagnetI => 3,
+            Formula::MagnetII => 4,
+        }
+    }
+
+    /// whether points can also stop iterating by converging to a finite attractor
+    pub fn converges(&self) -> bool {
+        matches!(self, Formula::MagnetI | Formula::MagnetII)
+    }
+
+    /// whether the extra parameter p is used
+    pub fn has_parameter(&self) -> bool {
+        *self == Formula::Phoenix
+    }
+
+    /// bailout radius the formula is usually rendered with, the magnet formulas need a large one to tell escaping from converging
+    pub fn default_bailout(&self) -> f32 {
+        match self {
+            Formula::Quadratic | Formula::Phoenix => 2.0,
+            Formula::MagnetI | Formula::MagnetII => 100.0,
+        }
+    }
+
+    /// julia constant showing a typical Julia set of the formula, used when switching to it
+    pub fn default_julia_constant(&self) -> [f32; 2] {
+        match self {
+            Formula::Quadratic => [-0.8, 0.156],
+            Formula::Phoenix => [0.5667, 0.0],
+            Formula::MagnetI => [1.5, 0.5],
+            Formula::MagnetII => [1.2, 0.6],
+        }
+    }
+}
+
+/// Measure of z compared against the bailout radius to decide whether a point escaped
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EscapeNorm {
+    /// distance from the origin
+    Modulus,
+    /// larger of the absolute real and imaginary parts, the same as requiring either of them to exceed the radius
+    MaxAbs,
+    /// sum of the absolute real and imaginary parts
+    Manhattan,
+    /// absolute real part only
+    Real,
+    /// absolute imaginary part only
+    Imaginary,
+    /// both the absolute real and imaginary parts have to exceed the radius
+    And,
+}
+
+impl EscapeNorm {
+    pub const ALL: [EscapeNorm; 6] = [
+        EscapeNorm::Modulus, EscapeNorm::MaxAbs, EscapeNorm::Manhattan, EscapeNorm::Real, EscapeNorm::Imaginary, EscapeNorm::And,
+    ];
+    /// points beyond this distance always escape, so the orbits of the one sided norms do not overflow
+    pub const OVERFLOW_RADIUS: f64 = 1e16;
+
+    /// display name of the norm, also used when saving scenes
+    pub fn name(&self) -> &'static str {
+        match self {
+            EscapeNorm::Modulus => "Modulus",
+            EscapeNorm::MaxAbs => "Max abs (or)",
+            EscapeNorm::Manhattan => "Manhattan",
+            EscapeNorm::Real => "Real",
+            EscapeNorm::Imaginary => "Imaginary",
+            EscapeNorm::And => "And",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<EscapeNorm> {
+        Self::ALL.into_iter().find(|norm| norm.name().eq_ignore_ascii_case(name))
+    }
+
+    /// value of the `escape_norm` uniform of the shader
+    pub fn index(&self) -> i32 {
+        *self as i32
+    }
+
+    /// whether z lies outside the bailout radius measured with the norm
+    pub fn escaped(&self, z: [f64; 2], radius: f64) -> bool {
+        let [x, y] = [z[0].abs(), z[1].abs()];
+        let outside = match self {
+            EscapeNorm::Modulus => x * x + y * y > radius * radius,
+            EscapeNorm::MaxAbs => x.max(y) > radius,
+            EscapeNorm::Manhattan => x + y > radius,
+            EscapeNorm::Real => x > radius,
+            EscapeNorm::Imaginary => y > radius,
+            EscapeNorm::And => x > radius && y > radius,
+        };
+        outside || x.is_nan() || y.is_nan() || x * x + y * y >= Self::OVERFLOW_RADIUS * Self::OVERFLOW_RADIUS
+    }
+}
+
+/// Snapshot of everything that defines the rendered image
+/// the camera and all fractal and coloring parameters
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Scene {
+    pub fractal: FractalKind,
+    pub julia_constant: [f32; 2],
+    /// formula iterated by the Mandelbrot and Julia kinds
+    pub formula: Formula,
+    /// extra complex parameter of the formula, p of the Phoenix fractal
+    pub formula_parameter: [f32; 2],
+    /// radius beyond which a point counts as escaped
+    pub bailout: f32,
+    pub escape_norm: EscapeNorm,
+    pub camera: Camera,
+    pub max_iterations: i32,
+    pub hsv_scale: [f32; 3],
+    /// shift of the hue, cycling it moves the colors through the bands
+    pub palette_offset: f32,
+    pub terminal_color: [f32; 3],
+    pub lyapunov_sequence: LyapunovSequence,
+    /// steps of the logistic map skipped before the exponent is measured
+    pub lyapunov_warmup: i32,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self {
+            fractal: FractalKind::Mandelbrot,
+            julia_constant: [-0.8, 0.156],
+            formula: Formula::Quadratic,
+            formula_parameter: [-0.5, 0.0],
+            bailout: 2.0,
+            escape_norm: EscapeNorm::Modulus,
+            camera: Camera::default(),
+            max_iterations: 500,
+            hsv_scale: [1.0, 1.0, 1.0],
+            palette_offset: 0.0,
+            terminal_color: [0.0, 0.0, 0.0],
+            lyapunov_sequence: LyapunovSequence::default(),
+            lyapunov_warmup: 50,
+        }
+    }
+}
+
+impl Scene {
+    /// camera showing the interesting part of the plane of the fractal and its formula, used when resetting the view
+    pub fn home_camera(&self) -> Camera {
+        match (self.fractal, self.formula) {
+            (FractalKind::Mandelbrot | FractalKind::Julia, Formula::MagnetI | Formula::MagnetII) => {
+                Camera { focus: [1.0, 0.0], width: 5.0, height: 5.0, ..Camera::default() }
+            },
+            _ => self.fractal.home_camera(),
+        }
+    }
+
+    /// serialize the scene as `key = value` pairs
+    /// floating point values are written with enough digits to be read back exactly
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let camera = &self.camera;
+        vec![
+            ("fractal", self.fractal.name().to_string()),
+            ("julia_constant", format!("{}, {}", self.julia_constant[0], self.julia_constant[1])),
+            ("formula", self.formula.name().to_string()),
+            ("formula_parameter", format!("{}, {}", self.formula_parameter[0], self.formula_parameter[1])),
+            ("bailout", self.bailout.to_string()),
+            ("escape_norm", self.escape_norm.name().to_string()),
+            ("focus", format!("{}, {}", camera.focus[0], camera.focus[1])),
+            ("zoom", camera.zoom.to_string()),
+            ("rotation", camera.rotation.to_string()),
+            ("camera_size", format!("{}, {}", camera.width, camera.height)),
+            ("max_iterations", self.max_iterations.to_string()),
+            ("hsv_scale", format!("{}, {}, {}", self.hsv_scale[0], self.hsv_scale[1], self.hsv_scale[2])),
+            ("palette_offset", self.palette_offset.to_string()),
+            ("terminal_color", format!("{}, {}, {}", self.terminal_color[0], self.terminal_color[1], self.terminal_color[2])),
+            ("lyapunov_sequence", self.lyapunov_sequence.to_string()),
+            ("lyapunov_warmup", self.lyapunov_warmup.to_string()),
+        ]
+    }
+
+    /// set a single field from its serialized `key = value` form
+    /// returns an error message if the key is unknown or the value cannot be parsed
+    pub fn set_field(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let camera = &mut self.camera;
+        match key {
+            "fractal" => self.fractal = FractalKind::from_name(value).ok_or(format!("unknown fractal `{}`", value))?,
+            "julia_constant" => self.julia_constant = parse_values(value)?,
+            "formula" => self.formula = Formula::from_name(value).ok_or(format!("unknown formula `{}`", value))?,
+            "formula_parameter" => self.formula_parameter = parse_values(value)?,
+            "bailout" => self.bailout = parse_value(value)?,
+            "escape_norm" => self.escape_norm = EscapeNorm::from_name(value).ok_or(format!("unknown escape norm `{}`", value))?,
+            "focus" => camera.focus = parse_values(value)?,
+            "zoom" => camera.zoom = parse_value(value)?,
+            "rotation" => camera.rotation = parse_value(value)?,
+            "camera_size" => [camera.width, camera.height] = parse_values(value)?,
+            "max_iterations" => self.max_iterations = parse_value(value)?,
+            "hsv_scale" => self.hsv_scale = parse_values(value)?,
+            "palette_offset" => self.palette_offset = parse_value(value)?,
+            "terminal_color" => self.terminal_color = parse_values(value)?,
+            "lyapunov_sequence" => self.lyapunov_sequence = LyapunovSequence::parse(value)?,
+            "lyapunov_warmup" => self.lyapunov_warmup = parse_value(value)?,
+            _ => return Err(format!("unknown key `{}`", key)),
+        }
+        Ok(())
+    }
+
+    /// short description of what differs between two scenes, used to label history entries
+    pub fn describe_change(&self, previous: &Scene) -> String {
+        let mut changes = Vec::new();
+
+        if self.fractal != previous.fractal {
+            changes.push(format!("Fractal: {}", self.fractal.name()));
+        }
+        if self.julia_constant != previous.julia_constant {
+            changes.push("Julia constant".to_string());
+        }
+        if self.formula != previous.formula {
+            changes.push(format!("Formula: {}", self.formula.name()));
+        }
+        if self.formula_parameter != previous.formula_parameter {
+            changes.push("Parameter p".to_string());
+        }
+        if self.bailout != previous.bailout || self.escape_norm != previous.escape_norm {
+            changes.push(format!("Bailout: {} {}", self.escape_norm.name(), self.bailout));
+        }
+        if self.camera.focus != previous.camera.focus {
+            changes.push("Pan".to_string());
+        }
+        if self.camera.zoom != previous.camera.zoom {
+            changes.push(format!("Zoom x{:.3}", self.camera.zoom / previous.camera.zoom));
+        }
+        if self.camera.rotation != previous.camera.rotation {
+            changes.push(format!("Rotate {:.1} deg", self.camera.rotation.to_degrees()));
+        }
+        if self.camera.width != previous.camera.width || self.camera.height != previous.camera.height {
+            changes.push("Camera size".to_string());
+        }
+        if self.max_iterations != previous.max_iterations {
+            changes.push(format!("Iterations: {}", self.max_iterations));
+        }
+        if self.hsv_scale != previous.hsv_scale || self.palette_offset != previous.palette_offset || self.terminal_color != previous.terminal_color {
+            changes.push("Colors".to_string());
+        }
+        if self.lyapunov_sequence != previous.lyapunov_sequence || self.lyapunov_warmup != previous.lyapunov_warmup {
+            changes.push(format!("Sequence: {}", self.lyapunov_sequence));
+        }
+
+        changes.join(", ")
+    }
+}
+
+/// parse a single value of a serialized field
+fn parse_value<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("invalid value `{}`", value.trim()))
+}
+
+/// parse a comma separated list of exactly `N` values of a serialized field
+fn parse_values<T: std::str::FromStr + Copy + Default, const N: usize>(value: &str) -> Result<[T; N], String> {
+    let parts: Vec<&str> = value.split(',').collect();
+    if parts.len() != N {
+        return Err(format!("expected {} values, found `{}`", N, value.trim()));
+    }
+
+    let mut values = [T::default(); N];
+    for (target, part) in values.iter_mut().zip(parts) {
+        *target = parse_value(part)?;
+    }
+    Ok(values)
+}
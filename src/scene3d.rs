@@ -0,0 +1,283 @@
+/// Distance-estimated 3D fractals the ray marcher can render
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Shape {
+    Mandelbulb,
+    Mandelbox,
+    MengerSponge,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 3] = [Shape::Mandelbulb, Shape::Mandelbox, Shape::MengerSponge];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Shape::Mandelbulb => "Mandelbulb",
+            Shape::Mandelbox => "Mandelbox",
+            Shape::MengerSponge => "Menger sponge",
+        }
+    }
+
+    /// value of the `shape` uniform of the ray marching shader
+    pub fn index(&self) -> i32 {
+        *self as i32
+    }
+
+    /// distance of the camera from the origin that shows the whole shape
+    pub fn home_distance(&self) -> f32 {
+        match self {
+            Shape::Mandelbulb => 3.0,
+            Shape::Mandelbox => 12.0,
+            Shape::MengerSponge => 3.5,
+        }
+    }
+}
+
+/// How the 3D camera is moved
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CameraMode {
+    /// dragging circles around the target, moving forward and backward changes the distance to it
+    Orbit,
+    /// dragging looks around, the movement keys move the camera freely
+    Fly,
+}
+
+/// Camera looking at the point `target` from `distance` units away
+/// the direction is given by `yaw` around the vertical y axis and `pitch` above the horizon, both in radians
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Camera3D {
+    pub target: [f32; 3],
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// vertical field of view in degrees
+    pub fov: f32,
+}
+
+impl Default for Camera3D {
+    fn default() -> Self {
+        Self {
+            target: [0.0, 0.0, 0.0],
+            distance: Shape::Mandelbulb.home_distance(),
+            yaw: 0.6,
+            pitch: 0.4,
+            fov: 60.0,
+        }
+    }
+}
+
+impl Camera3D {
+    /// steepest pitch, looking straight up or down would make the right vector undefined
+    const MAX_PITCH: f32 = 1.55;
+    /// closest the camera gets to its target
+    const MIN_DISTANCE: f32 = 1e-4;
+
+    /// unit vector the camera looks along
+    pub fn forward(&self) -> [f32; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        [-cos_pitch * sin_yaw, -sin_pitch, -cos_pitch * cos_yaw]
+    }
+
+    /// unit vector pointing to the right of the view, always horizontal
+    pub fn right(&self) -> [f32; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        [cos_yaw, 0.0, -sin_yaw]
+    }
+
+    /// unit vector pointing up in the view
+    pub fn up(&self) -> [f32; 3] {
+        let [fx, fy, fz] = self.forward();
+        let [rx, ry, rz] = self.right();
+        [ry * fz - rz * fy, rz * fx - rx * fz, rx * fy - ry * fx]
+    }
+
+    pub fn position(&self) -> [f32; 3] {
+        let forward = self.forward();
+        [0, 1, 2].map(|i| self.target[i] - forward[i] * self.distance)
+    }
+
+    /// circle around the target
+    pub fn orbit(&mut self, yaw: f32, pitch: f32) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+    }
+
+    /// move towards the target by multiplying the distance with the factor
+    pub fn zoom(&mut self, factor: f32) {
+        self.distance = (self.distance * factor).max(Self::MIN_DISTANCE);
+    }
+
+    /// turn the camera around its own position
+    pub fn look(&mut self, yaw: f32, pitch: f32) {
+        let position = self.position();
+        self.orbit(yaw, pitch);
+        let forward = self.forward();
+        self.target = [0, 1, 2].map(|i| position[i] + forward[i] * self.distance);
+    }
+
+    /// move the camera and its target by the given amounts along the view directions
+    pub fn translate(&mut self, forward: f32, right: f32, up: f32) {
+        let [f, r, u] = [self.forward(), self.right(), self.up()];
+        self.target = [0, 1, 2].map(|i| self.target[i] + f[i] * forward + r[i] * right + u[i] * up);
+    }
+
+    /// perspective projection of the view for a screen of the given width to height ratio
+    /// the 4x4 matrix is stored column major, the same layout as a GLSL `mat4`
+    pub fn view_projection(&self, aspect: f32) -> [f32; 16] {
+        let [right, up, forward, position] = [self.right(), self.up(), self.forward(), self.position()];
+        let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+        let scale = 1.0 / (self.fov.to_radians() / 2.0).tan();
+        // the clipping planes follow the distance, so close-ups keep their depth precision
+        let [near, far] = [self.distance * 0.01, self.distance * 100.0];
+
+        // rows of the view matrix, the camera looks along the negative z axis
+        let view = [
+            [right[0], right[1], right[2], -dot(right, position)],
+            [up[0], up[1], up[2], -dot(up, position)],
+            [-forward[0], -forward[1], -forward[2], dot(forward, position)],
+        ];
+        let depth = [(far + near) / (near - far), 2.0 * far * near / (near - far)];
+        let rows = [
+            view[0].map(|value| value * scale / aspect),
+            view[1].map(|value| value * scale),
+            [0, 1, 2, 3].map(|i| depth[0] * view[2][i] + if i == 3 { depth[1] } else { 0.0 }),
+            view[2].map(|value| -value),
+        ];
+        std::array::from_fn(|i| rows[i % 4][i / 4])
+    }
+}
+
+/// Moves a [`Camera3D`] with the 3D navigation of the input handler, shared by the views that show a 3D camera
+/// held keys move it every frame, mouse drags and wheel steps move it right away,
+/// it only moves while its view is shown and the mouse only while the cursor is not over a panel
+pub struct CameraController3D {
+    pub mode: CameraMode,
+    /// speed of the held keys, radians per second while orbiting and distances to the target per second while flying
+    pub speed: f32,
+
+    /// held movement directions, forward, right and up
+    movement: [f32; 3],
+    /// whether the view was shown in the last frame, the camera is only moved then
+    active: bool,
+    window_hovered: bool,
+}
+
+impl Default for CameraController3D {
+    fn default() -> Self {
+        Self {
+            mode: CameraMode::Orbit,
+            speed: 1.0,
+
+            movement: [0.0, 0.0, 0.0],
+            active: false,
+            window_hovered: false,
+        }
+    }
+}
+
+impl CameraController3D {
+    /// radians the camera turns per pixel the mouse is dragged
+    const LOOK_SENSITIVITY: f32 = 0.005;
+    /// fraction of the distance to the target a wheel step moves
+    const DOLLY_STEP: f32 = 0.1;
+
+    /// whether the view was shown in the last frame
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// the held directions are always taken, so leaving the 3D navigation stops the movement
+    pub fn set_movement(&mut self, forward: i32, right: i32, up: i32) {
+        self.movement = [forward as f32, right as f32, up as f32];
+    }
+
+    /// whether the cursor is over a panel, called once per frame while the panel of the view is drawn
+    pub fn set_window_hovered(&mut self, hovered: bool) {
+        self.window_hovered = hovered;
+    }
+
+    /// move the camera along the held directions by `dt` seconds, `shown` is false while the view is not in the main view
+    pub fn update(&mut self, camera: &mut Camera3D, dt: f64, shown: bool) {
+        self.active = shown;
+        if !shown || self.movement == [0.0, 0.0, 0.0] {
+            return;
+        }
+
+        let step = self.speed * dt as f32;
+        let [forward, right, up] = self.movement;
+        match self.mode {
+            CameraMode::Orbit => {
+                camera.zoom((-forward * step).exp());
+                camera.orbit(right * step, up * step);
+            },
+            CameraMode::Fly => {
+                let step = step * camera.distance;
+                camera.translate(forward * step, right * step, up * step);
+            },
+        }
+    }
+
+    /// turn the camera by a mouse drag of the given pixels
+    pub fn look(&self, camera: &mut Camera3D, xrel: i32, yrel: i32) {
+        if !self.active || self.window_hovered {
+            return;
+        }
+        let [yaw, pitch] = [-xrel as f32 * Self::LOOK_SENSITIVITY, yrel as f32 * Self::LOOK_SENSITIVITY];
+        match self.mode {
+            CameraMode::Orbit => camera.orbit(yaw, pitch),
+            CameraMode::Fly => camera.look(yaw, pitch),
+        }
+    }
+
+    /// move the camera by wheel steps, positive steps move towards the target
+    pub fn dolly(&self, camera: &mut Camera3D, steps: f32) {
+        if !self.active || self.window_hovered {
+            return;
+        }
+        match self.mode {
+            CameraMode::Orbit => camera.zoom((1.0 - Self::DOLLY_STEP).powf(steps)),
+            CameraMode::Fly => camera.translate(steps * Self::DOLLY_STEP * camera.distance, 0.0, 0.0),
+        }
+    }
+}
+
+/// Everything that defines an image of the ray marched fractals
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Scene3D {
+    pub shape: Shape,
+    /// exponent of the Mandelbulb formula
+    pub power: f32,
+    /// scale of the Mandelbox fold, negative values give the other family of boxes
+    pub box_scale: f32,
+    /// iterations of the distance estimator
+    pub iterations: i32,
+    /// marching steps per ray before giving up
+    pub max_steps: i32,
+    /// sharpness of the shadow edges, 0 turns shadows off
+    pub shadow_softness: f32,
+    /// strength of the ambient occlusion darkening creases
+    pub ambient_occlusion: f32,
+    pub fog_density: f32,
+    pub fog_color: [f32; 3],
+    /// shift of the hue the surface is colored with
+    pub palette_offset: f32,
+    pub camera: Camera3D,
+}
+
+impl Default for Scene3D {
+    fn default() -> Self {
+        Self {
+            shape: Shape::Mandelbulb,
+            power: 8.0,
+            box_scale: -1.5,
+            iterations: 12,
+            max_steps: 200,
+            shadow_softness: 16.0,
+            ambient_occlusion: 1.0,
+            fog_density: 0.05,
+            fog_color: [0.55, 0.6, 0.7],
+            palette_offset: 0.0,
+            camera: Camera3D::default(),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use imgui::{Drag, TableColumnSetup, TableFlags, Ui};
+use crate::animation::{Animation, Easing, Keyframe};
+use crate::renderer::FractalRenderer;
+use crate::renderer::frame_export::{FrameExport, FrameOutput};
+use crate::renderer::image_export::ExportJob;
+use crate::scene::Scene;
+use crate::ui::event_observer::{Observable, Observer, ObserverEvent};
+use crate::ui::export_runner::ExportRunner;
+
+/// Panel for building a keyframe animation, previewing it in the main view and rendering its frames
+pub struct AnimationWindow {
+    animation: Animation,
+    playing: bool,
+    time: f64,
+
+    width: i32,
+    height: i32,
+    fps: i32,
+    use_ffmpeg: bool,
+    frames_directory: String,
+    video_path: String,
+    runner: ExportRunner,
+
+    observers: Vec<Rc<RefCell<dyn Observer>>>,
+}
+
+impl Default for AnimationWindow {
+    fn default() -> Self {
+        Self {
+            animation: Animation::default(),
+            playing: false,
+            time: 0.0,
+
+            width: 1920,
+            height: 1080,
+            fps: 30,
+            use_ffmpeg: false,
+            frames_directory: String::from("frames"),
+            video_path: String::from("animation.mp4"),
+            runner: ExportRunner::default(),
+
+            observers: Vec::new(),
+        }
+    }
+}
+
+impl AnimationWindow {
+    /// advance the preview by `dt` seconds while it is playing
+    pub fn update(&mut self, dt: f64) {
+        if !self.playing {
+            return;
+        }
+
+        self.time += dt;
+        if self.time >= self.animation.duration() {
+            self.time = self.animation.duration();
+            self.playing = false;
+        }
+        self.show_time();
+    }
+
+    /// continue rendering frames, called once per frame before drawing into the window
+    pub fn update_export(&mut self, renderer: &FractalRenderer) {
+        self.runner.update(renderer);
+    }
+
+    /// append a keyframe recorded elsewhere, such as by the explorer
+    pub fn add_keyframe(&mut self, keyframe: Keyframe) {
+        self.animation.keyframes.push(keyframe);
+    }
+
+    /// draw the panel, `scene` is the current view that gets captured as a keyframe
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool, scene: &Scene) {
+        let mut restore = None;
+
+        ui.window("Animation")
+            .size([460.0, 480.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                if ui.button("Add keyframe") {
+                    self.animation.keyframes.push(Keyframe::new(*scene));
+                }
+                ui.same_line();
+                ui.text_disabled("captures the current view");
+
+                restore = self.draw_keyframes(ui, scene);
+                ui.separator();
+                self.draw_playback(ui);
+                ui.separator();
+                self.draw_export(ui);
+            });
+
+        if let Some(scene) = restore {
+            self.notify_observers(ObserverEvent::RestoreScene(scene));
+        }
+    }
+
+    /// draw the keyframe table, returns the scene of a keyframe to jump to
+    fn draw_keyframes(&mut self, ui: &Ui, scene: &Scene) -> Option<Scene> {
+        let mut restore = None;
+        let mut removed = None;
+
+        let flags = TableFlags::BORDERS | TableFlags::ROW_BG | TableFlags::SIZING_STRETCH_PROP;
+        if let Some(_table) = ui.begin_table_header_with_flags("##keyframes", [
+            TableColumnSetup::new("Keyframe"),
+            TableColumnSetup::new("Duration"),
+            TableColumnSetup::new("Easing"),
+            TableColumnSetup::new(""),
+        ], flags) {
+            for (index, keyframe) in self.animation.keyframes.iter_mut().enumerate() {
+                let _id = ui.push_id_usize(index);
+
+                ui.table_next_row();
+                ui.table_next_column();
+                ui.text(format!("{}. {} x{:.3e}", index + 1, keyframe.scene.fractal.name(), keyframe.scene.camera.zoom));
+
+                ui.table_next_column();
+                ui.set_next_item_width(-1.0);
+                Drag::new("##duration").range(0.1, 600.0).speed(0.1).display_format("%.1f s").build(ui, &mut keyframe.duration);
+
+                ui.table_next_column();
+                ui.set_next_item_width(-1.0);
+                if let Some(_combo) = ui.begin_combo("##easing", keyframe.easing.name()) {
+                    for easing in Easing::ALL {
+                        if ui.selectable_config(easing.name()).selected(keyframe.easing == easing).build() {
+                            keyframe.easing = easing;
+                        }
+                    }
+                }
+
+                ui.table_next_column();
+                if ui.small_button("Go") {
+                    restore = Some(keyframe.scene);
+                }
+                ui.same_line();
+                if ui.small_button("Set") {
+                    keyframe.scene = *scene;
+                }
+                ui.same_line();
+                if ui.small_button("Delete") {
+                    removed = Some(index);
+                }
+            }
+        }
+
+        if let Some(index) = removed {
+            self.animation.keyframes.remove(index);
+        }
+        if restore.is_some() {
+            self.playing = false;
+        }
+        restore
+    }
+
+    /// draw the preview controls, moving the time slider shows that moment in the main view
+    fn draw_playback(&mut self, ui: &Ui) {
+        let duration = self.animation.duration();
+
+        if ui.button(if self.playing { "Pause" } else { "Play" }) {
+            self.playing = !self.playing && duration > 0.0;
+            if self.playing && self.time >= duration {
+                self.time = 0.0;
+            }
+        }
+        ui.same_line();
+        ui.set_next_item_width(-1.0);
+        if ui.slider_config("##time", 0.0, duration).display_format("%.2f s").build(&mut self.time) {
+            self.playing = false;
+            self.show_time();
+        }
+    }
+
+    fn draw_export(&mut self, ui: &Ui) {
+        if self.runner.is_running() {
+            self.runner.draw_progress(ui);
+            return;
+        }
+
+        let _item_width_stack_token = ui.push_item_width(-120.0);
+        Drag::new("Width").range(2, 8192).build(ui, &mut self.width);
+        Drag::new("Height").range(2, 8192).build(ui, &mut self.height);
+        ui.slider("Frames per second", 1, 120, &mut self.fps);
+        ui.checkbox("Encode video with ffmpeg", &mut self.use_ffmpeg);
+        if self.use_ffmpeg {
+            ui.input_text("Video file", &mut self.video_path).build();
+        } else {
+            ui.input_text("Frame directory", &mut self.frames_directory).build();
+        }
+
+        if ui.button(format!("Render {} frames", self.animation.frame_count(self.fps as f64))) {
+            let output = if self.use_ffmpeg {
+                FrameOutput::Ffmpeg(PathBuf::from(&self.video_path))
+            } else {
+                FrameOutput::Png(PathBuf::from(&self.frames_directory))
+            };
+            let frames = self.animation.frames(self.fps as f64);
+            let export = FrameExport::new(frames, self.width, self.height, self.fps as f64, output);
+            self.runner.start(export.map(|export| Box::new(export) as Box<dyn ExportJob>));
+        }
+        self.runner.draw_status(ui);
+    }
+
+    /// show the animation at the current time in the main view
+    fn show_time(&mut self) {
+        if let Some(scene) = self.animation.sample(self.time) {
+            self.notify_observers(ObserverEvent::RestoreScene(scene));
+        }
+    }
+}
+
+impl Observable<'_> for AnimationWindow {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer>>) {
+        self.observers.push(observer)
+    }
+
+    fn notify_observers(&mut self, event: ObserverEvent) {
+        for observer in self.observers.iter() {
+            observer.borrow_mut().notify(&event)
+        }
+    }
+}
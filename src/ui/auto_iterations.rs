@@ -0,0 +1,206 @@
+use std::time::{Duration, Instant};
+use crate::renderer::{image_writer, DataLayer, FractalRenderer, RenderTarget};
+use crate::scene::Scene;
+
+/// Outcome of one probe of the view
+#[derive(PartialEq, Debug)]
+struct Probe {
+    /// fraction of the pixels that escaped only after the current limit
+    beyond_limit: f32,
+    iterations: i32,
+    /// the limit was raised but the probe could not tell whether enough, the new limit has to be probed as well
+    probe_again: bool,
+}
+
+/// Automatic choice of the maximum iterations
+/// once the view has settled, a small probe of it is rendered with twice the current limit, the limit is raised until
+/// only a tiny fraction of the pixels would still escape beyond it and lowered again when far fewer iterations are needed,
+/// the zoom depth gives a lower bound that is applied right away, so fast zooms do not wait for the statistics
+pub struct AutoIterations {
+    pub enabled: bool,
+    /// keep the last chosen value while the view changes
+    pub locked: bool,
+    /// fraction of the pixels of the last probe that escaped only after the current limit
+    pub beyond_limit: f32,
+
+    target: Option<RenderTarget>,
+    /// view the statistics belong to, the scene without its maximum iterations
+    view: Option<Scene>,
+    /// when the view last changed
+    changed_at: Instant,
+    /// number of probes of the settled view
+    probes: u32,
+    /// whether the iterations of the settled view are chosen, the view is probed again until then
+    done: bool,
+}
+
+impl Default for AutoIterations {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            locked: false,
+            beyond_limit: 0.0,
+
+            target: None,
+            view: None,
+            changed_at: Instant::now(),
+            probes: 0,
+            done: false,
+        }
+    }
+}
+
+impl AutoIterations {
+    /// size of the probe in pixels
+    const PROBE_SIZE: [i32; 2] = [160, 120];
+    /// at most this fraction of the pixels may escape only after the limit
+    const TARGET_FRACTION: f32 = 0.001;
+    /// headroom above the iterations that are actually needed
+    const HEADROOM: f64 = 1.25;
+    const MIN_ITERATIONS: i32 = 100;
+    const MAX_ITERATIONS: i32 = 1_000_000;
+    /// time the view has to stay the same before it is probed
+    const SETTLE_DELAY: Duration = Duration::from_millis(300);
+    /// most probes of one view, each can at most double the limit
+    const MAX_PROBES: u32 = 8;
+
+    /// lower bound for the iterations at the zoom of the camera, grows with the number of decades zoomed in
+    fn zoom_iterations(scene: &Scene) -> i32 {
+        let decades = scene.camera.zoom.log10().max(0.0);
+        (Self::MIN_ITERATIONS as f64 * (1.0 + decades)) as i32
+    }
+
+    /// return the new maximum iterations if they should change, called once per frame
+    /// the probe is only rendered once the view has settled, while it moves only the zoom depth is followed
+    /// only the escape-time fractals are adjusted, the Lyapunov fractal averages over its iterations instead
+    pub fn probe(&mut self, renderer: &FractalRenderer, scene: &Scene) -> Option<i32> {
+        if !self.enabled || self.locked || !scene.fractal.is_complex() {
+            // probe again once turned back on
+            self.view = None;
+            return None;
+        }
+
+        // changing the iterations is not a new view, so applying the chosen value does not probe again
+        let view = Scene { max_iterations: 0, ..*scene };
+        if self.view != Some(view) {
+            self.view = Some(view);
+            self.changed_at = Instant::now();
+            self.probes = 0;
+            self.done = false;
+        }
+        if self.done || self.changed_at.elapsed() < Self::SETTLE_DELAY {
+            let floor = Self::zoom_iterations(scene).clamp(Self::MIN_ITERATIONS, Self::MAX_ITERATIONS);
+            return (scene.max_iterations < floor).then_some(floor);
+        }
+
+        if self.target.is_none() {
+            match RenderTarget::new_float(Self::PROBE_SIZE[0], Self::PROBE_SIZE[1]) {
+                Ok(target) => self.target = Some(target),
+                Err(err) => {
+                    eprintln!("Error creating the iteration probe: {:?}", err);
+                    self.enabled = false;
+                    return None;
+                },
+            }
+        }
+        let target = self.target.as_ref()?;
+
+        let limit = scene.max_iterations.max(1);
+        let probe_scene = Scene { max_iterations: limit.saturating_mul(2).min(Self::MAX_ITERATIONS), ..*scene };
+        renderer.render_data_to(&probe_scene, DataLayer::Escape, target);
+        target.bind();
+        let values = image_writer::read_pixels_f32(0, 0, target.width(), target.height());
+        target.unbind();
+
+        // iteration counts of the pixels that escaped or converged, the others belong to the set at any limit
+        let escaped: Vec<f32> = values.chunks_exact(4).filter(|pixel| pixel[2] != 0.0).map(|pixel| pixel[3]).collect();
+        self.probes += 1;
+        let probe = Self::evaluate(escaped, values.len() / 4, limit, Self::zoom_iterations(scene), self.probes);
+        self.beyond_limit = probe.beyond_limit;
+        self.done = !probe.probe_again;
+        (probe.iterations != scene.max_iterations).then_some(probe.iterations)
+    }
+
+    /// choose the iterations from a probe rendered with twice the current `limit`
+    /// `escaped` holds the iteration counts of the pixels that escaped or converged out of all `pixels` of the probe,
+    /// `floor` is the lower bound of the zoom depth and `probes` the number of probes of the view including this one
+    fn evaluate(mut escaped: Vec<f32>, pixels: usize, limit: i32, floor: i32, probes: u32) -> Probe {
+        let pixels = pixels.max(1);
+        let beyond_limit = escaped.iter().filter(|iterations| **iterations >= limit as f32).count() as f32 / pixels as f32;
+
+        // iterations after which fewer than the target fraction of the pixels still escape
+        let allowed = (Self::TARGET_FRACTION * pixels as f32) as usize;
+        escaped.sort_unstable_by(|a, b| b.total_cmp(a));
+        let needed = escaped.get(allowed).copied().unwrap_or(0.0) as f64 * Self::HEADROOM;
+
+        let chosen = if beyond_limit > Self::TARGET_FRACTION {
+            needed.max(limit as f64 * Self::HEADROOM)
+        } else if needed < limit as f64 * 0.5 {
+            // lower by at most a quarter per view, the small probe can miss thin details
+            needed.max(limit as f64 * 0.75)
+        } else {
+            limit as f64
+        };
+        let iterations = (chosen as i32).max(floor).clamp(Self::MIN_ITERATIONS, Self::MAX_ITERATIONS);
+
+        // pixels escaping near twice the limit may need even more, which only a probe with the raised limit shows
+        let probe_again = beyond_limit > Self::TARGET_FRACTION && iterations > limit && probes < Self::MAX_PROBES;
+        Probe { beyond_limit, iterations, probe_again }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// iteration counts of `pixels` escaped pixels spread evenly up to `max`
+    fn histogram(pixels: usize, max: f32) -> Vec<f32> {
+        (0..pixels).map(|pixel| max * (pixel + 1) as f32 / pixels as f32).collect()
+    }
+
+    #[test]
+    fn raises_and_probes_again() {
+        // 1% of the pixels escape between the limit and twice the limit
+        let mut escaped = histogram(9900, 900.0);
+        escaped.extend(histogram(100, 1000.0).iter().map(|iterations| 1000.0 + iterations));
+        let probe = AutoIterations::evaluate(escaped, 10_000, 1000, 100, 1);
+        assert!((probe.beyond_limit - 0.01).abs() < 1e-6);
+        // the 11th highest count is 1900 and gets the headroom
+        assert_eq!(probe.iterations, 2375);
+        assert!(probe.probe_again);
+    }
+
+    #[test]
+    fn stops_probing_at_the_cap() {
+        let escaped = histogram(10_000, 2000.0);
+        let probe = AutoIterations::evaluate(escaped, 10_000, 1000, 100, AutoIterations::MAX_PROBES);
+        assert!(probe.iterations > 1000);
+        assert!(!probe.probe_again);
+
+        // the highest limit cannot be raised, so probing again would not change anything
+        let limit = AutoIterations::MAX_ITERATIONS;
+        let probe = AutoIterations::evaluate(histogram(10_000, 2.0 * limit as f32), 10_000, limit, 100, 1);
+        assert_eq!(probe.iterations, AutoIterations::MAX_ITERATIONS);
+        assert!(!probe.probe_again);
+    }
+
+    #[test]
+    fn keeps_a_sufficient_limit() {
+        // a few pixels beyond the limit are tolerated, the set itself does not count
+        let mut escaped = histogram(5000, 800.0);
+        escaped.extend([1200.0, 1500.0]);
+        let probe = AutoIterations::evaluate(escaped, 10_000, 1000, 100, 1);
+        assert_eq!(probe, Probe { beyond_limit: 0.0002, iterations: 1000, probe_again: false });
+    }
+
+    #[test]
+    fn lowers_by_a_quarter_at_most() {
+        let probe = AutoIterations::evaluate(histogram(10_000, 100.0), 10_000, 1000, 100, 1);
+        assert_eq!(probe.iterations, 750);
+        assert!(!probe.probe_again);
+
+        // but not below the bound of the zoom depth
+        let probe = AutoIterations::evaluate(histogram(10_000, 100.0), 10_000, 1000, 900, 1);
+        assert_eq!(probe.iterations, 900);
+    }
+}
@@ -0,0 +1,171 @@
+use std::path::Path;
+use crate::renderer::{FractalRenderer, RenderTarget};
+use crate::resources::{self, Resources};
+use crate::scene::Scene;
+
+/// Saved location with a name and tags used for searching
+pub struct Bookmark {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub scene: Scene,
+    /// built-in locations are shipped with the application and cannot be deleted
+    pub built_in: bool,
+    pub thumbnail: Option<RenderTarget>,
+}
+
+impl Bookmark {
+    pub fn new(name: &str, tags: Vec<String>, scene: Scene) -> Self {
+        Self {
+            name: name.to_string(),
+            tags,
+            scene,
+            built_in: false,
+            thumbnail: None,
+        }
+    }
+
+    /// check if the name or one of the tags contains the filter, ignoring case
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.trim().to_lowercase();
+        self.name.to_lowercase().contains(&filter)
+            || self.tags.iter().any(|tag| tag.to_lowercase().contains(&filter))
+    }
+}
+
+/// Library of bookmarked locations
+/// holds the built-in famous locations followed by the locations saved by the user
+#[derive(Default)]
+pub struct Bookmarks {
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    /// name of the file storing the user bookmarks next to the executable
+    pub const LIBRARY_FILE: &'static str = "bookmarks.txt";
+    /// built-in locations inside the assets folder
+    const BUILT_IN_FILE: &'static str = "bookmarks/famous_locations.txt";
+    /// width of the thumbnails in pixels, the height follows the aspect ratio of the camera
+    const THUMBNAIL_WIDTH: i32 = 64;
+
+    /// load the built-in locations and the user library
+    /// missing or unreadable files are reported and leave the library without their bookmarks
+    pub fn load() -> Self {
+        let mut library = Self::default();
+
+        match Resources::from_relative_exe_path(Path::new("assets")).and_then(|resources| resources.load_string(Self::BUILT_IN_FILE)) {
+            Ok(contents) => library.bookmarks.extend(Self::parse(&contents, Self::BUILT_IN_FILE).into_iter().map(|bookmark| Bookmark { built_in: true, ..bookmark })),
+            Err(err) => eprintln!("Error loading built-in bookmarks: {:?}", err),
+        }
+
+        // the user library does not exist until the first bookmark is saved
+        if let Ok(contents) = Resources::from_relative_exe_path(Path::new("")).and_then(|resources| resources.load_string(Self::LIBRARY_FILE)) {
+            library.bookmarks.extend(Self::parse(&contents, Self::LIBRARY_FILE));
+        }
+
+        library
+    }
+
+    /// save the user bookmarks to the library file next to the executable
+    pub fn save(&self) -> Result<(), resources::Error> {
+        Resources::from_relative_exe_path(Path::new(""))?
+            .save_string(Self::LIBRARY_FILE, &self.to_text())
+    }
+
+    /// add the bookmarks of another library file, returns the number of imported bookmarks
+    pub fn import(&mut self, path: &Path) -> Result<usize, std::io::Error> {
+        let contents = std::fs::read_to_string(path)?;
+        let imported = Self::parse(&contents, &path.display().to_string());
+        let count = imported.len();
+        self.bookmarks.extend(imported);
+        Ok(count)
+    }
+
+    /// write the user bookmarks to a library file that can be imported elsewhere
+    pub fn export(&self, path: &Path) -> Result<(), std::io::Error> {
+        std::fs::write(path, self.to_text())
+    }
+
+    pub fn bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    pub fn add(&mut self, bookmark: Bookmark) {
+        self.bookmarks.push(bookmark);
+    }
+
+    /// remove a bookmark, built-in bookmarks are kept
+    pub fn remove(&mut self, index: usize) {
+        if self.bookmarks.get(index).is_some_and(|bookmark| !bookmark.built_in) {
+            self.bookmarks.remove(index);
+        }
+    }
+
+    /// render the thumbnails of bookmarks that do not have one yet
+    pub fn update_thumbnails(&mut self, renderer: &FractalRenderer) {
+        for bookmark in self.bookmarks.iter_mut().filter(|bookmark| bookmark.thumbnail.is_none()) {
+            match renderer.render_thumbnail(&bookmark.scene, Self::THUMBNAIL_WIDTH) {
+                Ok(target) => bookmark.thumbnail = Some(target),
+                Err(err) => {
+                    eprintln!("Error creating bookmark thumbnail: {:?}", err);
+                    return;
+                },
+            }
+        }
+    }
+
+    /// parse bookmarks from the library file format
+    /// every bookmark starts with its name in square brackets followed by `key = value` lines,
+    /// fields that are left out keep the value of the default scene
+    fn parse(contents: &str, file_name: &str) -> Vec<Bookmark> {
+        let mut bookmarks: Vec<Bookmark> = Vec::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                bookmarks.push(Bookmark::new(name.trim(), Vec::new(), Scene::default()));
+                continue;
+            }
+
+            let Some(bookmark) = bookmarks.last_mut() else {
+                eprintln!("Line {} of {} does not belong to a bookmark: {}", number + 1, file_name, line);
+                continue;
+            };
+
+            let result = match line.split_once('=') {
+                Some((key, tags)) if key.trim() == "tags" => {
+                    bookmark.tags = tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(String::from).collect();
+                    Ok(())
+                },
+                Some((key, value)) => bookmark.scene.set_field(key.trim(), value),
+                None => Err("expected `key = value`".to_string()),
+            };
+
+            if let Err(message) = result {
+                eprintln!("Invalid bookmark field on line {} of {}: {}", number + 1, file_name, message);
+            }
+        }
+
+        bookmarks
+    }
+
+    /// write the user bookmarks in the library file format
+    fn to_text(&self) -> String {
+        let mut text = String::from("# Fractal bookmarks\n");
+
+        for bookmark in self.bookmarks.iter().filter(|bookmark| !bookmark.built_in) {
+            text.push_str(&format!("\n[{}]\n", bookmark.name));
+            if !bookmark.tags.is_empty() {
+                text.push_str(&format!("tags = {}\n", bookmark.tags.join(", ")));
+            }
+            for (key, value) in bookmark.scene.fields() {
+                text.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+
+        text
+    }
+}
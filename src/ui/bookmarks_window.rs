@@ -0,0 +1,150 @@
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+use imgui::{Image, TextureId, Ui};
+use crate::scene::Scene;
+use crate::ui::bookmarks::{Bookmark, Bookmarks};
+use crate::ui::event_observer::{Observable, Observer, ObserverEvent};
+
+/// Panel for saving the current view as a bookmark and browsing the library
+/// clicking a bookmark navigates to it
+pub struct BookmarksWindow {
+    bookmarks: Rc<RefCell<Bookmarks>>,
+    name: String,
+    tags: String,
+    filter: String,
+    file_path: String,
+    status: String,
+    observers: Vec<Rc<RefCell<dyn Observer>>>,
+}
+
+impl BookmarksWindow {
+    pub fn new(bookmarks: Rc<RefCell<Bookmarks>>) -> Self {
+        Self {
+            bookmarks,
+            name: String::new(),
+            tags: String::new(),
+            filter: String::new(),
+            file_path: String::from("exported_bookmarks.txt"),
+            status: String::new(),
+            observers: Vec::new(),
+        }
+    }
+
+    /// draw the panel, `scene` is the current view that gets saved when adding a bookmark
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool, scene: &Scene) {
+        let mut clicked = None;
+
+        ui.window("Bookmarks")
+            .size([340.0, 480.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                ui.text("Save current view");
+                ui.input_text("Name", &mut self.name).build();
+                ui.input_text("Tags", &mut self.tags).hint("comma separated").build();
+                if ui.button("Add bookmark") {
+                    self.add_bookmark(scene);
+                }
+                ui.separator();
+
+                ui.input_text("Search", &mut self.filter).build();
+                clicked = self.draw_list(ui);
+                ui.separator();
+
+                ui.input_text("File", &mut self.file_path).build();
+                if ui.button("Import") {
+                    self.status = match self.bookmarks.borrow_mut().import(Path::new(&self.file_path)) {
+                        Ok(count) => format!("Imported {} bookmarks", count),
+                        Err(err) => format!("Failed to import: {}", err),
+                    };
+                    self.save();
+                }
+                ui.same_line();
+                if ui.button("Export") {
+                    self.status = match self.bookmarks.borrow().export(Path::new(&self.file_path)) {
+                        Ok(()) => format!("Exported to {}", self.file_path),
+                        Err(err) => format!("Failed to export: {}", err),
+                    };
+                }
+                ui.text(&self.status);
+            });
+
+        if let Some(scene) = clicked {
+            self.notify_observers(ObserverEvent::RestoreScene(scene));
+        }
+    }
+
+    /// draw the bookmarks matching the search filter, returns the scene of the clicked bookmark
+    fn draw_list(&mut self, ui: &Ui) -> Option<Scene> {
+        let mut clicked = None;
+        let mut removed = None;
+
+        let height = -ui.frame_height_with_spacing() * 3.0;
+        ui.child_window("##bookmark_list").size([0.0, height]).border(true).build(|| {
+            let bookmarks = self.bookmarks.borrow();
+            for (index, bookmark) in bookmarks.bookmarks().iter().enumerate().filter(|(_, bookmark)| bookmark.matches(&self.filter)) {
+                let _id = ui.push_id_usize(index);
+
+                if let Some(thumbnail) = &bookmark.thumbnail {
+                    Image::new(TextureId::new(thumbnail.texture() as usize), [thumbnail.width() as f32, thumbnail.height() as f32])
+                        // the texture is stored bottom to top
+                        .uv0([0.0, 1.0])
+                        .uv1([1.0, 0.0])
+                        .build(ui);
+                    ui.same_line();
+                }
+
+                ui.group(|| {
+                    if ui.selectable(&bookmark.name) {
+                        clicked = Some(bookmark.scene);
+                    }
+                    if !bookmark.tags.is_empty() {
+                        ui.text_disabled(bookmark.tags.join(", "));
+                    }
+                    if !bookmark.built_in && ui.small_button("Delete") {
+                        removed = Some(index);
+                    }
+                });
+            }
+        });
+
+        if let Some(index) = removed {
+            self.bookmarks.borrow_mut().remove(index);
+            self.save();
+        }
+
+        clicked
+    }
+
+    fn add_bookmark(&mut self, scene: &Scene) {
+        let name = match self.name.trim() {
+            "" => format!("Bookmark {}", self.bookmarks.borrow().bookmarks().len() + 1),
+            name => name.to_string(),
+        };
+        let tags = self.tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(String::from).collect();
+
+        self.bookmarks.borrow_mut().add(Bookmark::new(&name, tags, *scene));
+        self.name.clear();
+        self.tags.clear();
+        self.save();
+    }
+
+    /// store the user bookmarks after every change
+    fn save(&mut self) {
+        if let Err(err) = self.bookmarks.borrow().save() {
+            self.status = format!("Failed to save {}: {:?}", Bookmarks::LIBRARY_FILE, err);
+        }
+    }
+}
+
+impl Observable<'_> for BookmarksWindow {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer>>) {
+        self.observers.push(observer)
+    }
+
+    fn notify_observers(&mut self, event: ObserverEvent) {
+        for observer in self.observers.iter() {
+            observer.borrow_mut().notify(&event)
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::path::Path;
+use std::time::Duration;
+use imgui::{Drag, Image, TextureId, Ui};
+use crate::buddhabrot::{Buddhabrot, DensitySettings, Sampling, ToneCurve, ToneMapping};
+use crate::renderer::{image_writer, Camera, RenderTarget};
+use crate::scene::Scene;
+
+/// Panel rendering the Buddhabrot, Nebulabrot or Anti-Buddhabrot of a view
+/// the density is accumulated a little every frame and shown as it improves
+pub struct BuddhabrotWindow {
+    settings: DensitySettings,
+    tone: ToneMapping,
+    width: i32,
+    height: i32,
+    path: String,
+
+    buddhabrot: Option<Buddhabrot>,
+    target: Option<RenderTarget>,
+    running: bool,
+    /// the image has to be tone mapped and uploaded again
+    changed: bool,
+    status: String,
+}
+
+impl Default for BuddhabrotWindow {
+    fn default() -> Self {
+        Self {
+            settings: DensitySettings::default(),
+            tone: ToneMapping::default(),
+            width: 600,
+            height: 600,
+            path: String::from("buddhabrot.png"),
+
+            buddhabrot: None,
+            target: None,
+            running: false,
+            changed: false,
+            status: String::new(),
+        }
+    }
+}
+
+impl BuddhabrotWindow {
+    /// time spent accumulating per frame
+    const FRAME_BUDGET: Duration = Duration::from_millis(20);
+
+    /// the classic upright view of the whole Buddhabrot
+    fn whole_set_camera() -> Camera {
+        Camera { focus: [-0.4, 0.0], width: 3.2, height: 3.2, rotation: std::f64::consts::FRAC_PI_2, ..Camera::default() }
+    }
+
+    /// accumulate more samples and show them, called once per frame before drawing into the window
+    pub fn update(&mut self) {
+        let (Some(buddhabrot), Some(target)) = (&mut self.buddhabrot, &self.target) else { return };
+
+        if self.running {
+            buddhabrot.accumulate(Self::FRAME_BUDGET);
+            self.changed = true;
+        }
+        if self.changed {
+            target.upload(&buddhabrot.to_rgba(&self.tone));
+            self.changed = false;
+        }
+    }
+
+    /// draw the panel, `scene` is the view that is rendered when starting with the current view
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool, scene: &Scene) {
+        ui.window("Buddhabrot")
+            .size([420.0, 760.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                self.draw_settings(ui, scene);
+                ui.separator();
+                self.draw_tone_mapping(ui);
+                ui.separator();
+                self.draw_image(ui);
+            });
+
+        if !*opened {
+            self.running = false;
+        }
+    }
+
+    fn draw_settings(&mut self, ui: &Ui, scene: &Scene) {
+        if ui.button("Buddhabrot") {
+            self.settings = DensitySettings { limits: [1000; 3], anti: false, ..self.settings };
+        }
+        ui.same_line();
+        if ui.button("Nebulabrot") {
+            self.settings = DensitySettings { limits: [5000, 500, 50], anti: false, ..self.settings };
+        }
+        ui.same_line();
+        if ui.button("Anti-Buddhabrot") {
+            self.settings = DensitySettings { limits: [500, 200, 50], anti: true, ..self.settings };
+        }
+
+        let _item_width_stack_token = ui.push_item_width(-140.0);
+        Drag::new("Iteration limits (RGB)").range(1, 1_000_000).build_array(ui, &mut self.settings.limits);
+        ui.checkbox("Anti-Buddhabrot (bounded orbits)", &mut self.settings.anti);
+        if let Some(_combo) = ui.begin_combo("Sampling", self.settings.sampling.name()) {
+            for sampling in Sampling::ALL {
+                if ui.selectable_config(sampling.name()).selected(self.settings.sampling == sampling).build() {
+                    self.settings.sampling = sampling;
+                }
+            }
+        }
+        Drag::new("Width").range(16, 4096).build(ui, &mut self.width);
+        Drag::new("Height").range(16, 4096).build(ui, &mut self.height);
+
+        if ui.button("Render whole set") {
+            self.start(Self::whole_set_camera());
+        }
+        ui.same_line();
+        if ui.button("Render current view") {
+            self.start(scene.camera);
+        }
+
+        if let Some(buddhabrot) = &self.buddhabrot {
+            if ui.button(if self.running { "Pause" } else { "Resume" }) {
+                self.running = !self.running;
+            }
+            ui.same_line();
+            ui.text(format!("{} samples", buddhabrot.samples()));
+            if *buddhabrot.settings() != self.settings {
+                ui.text_disabled("settings changed, render again to apply them");
+            }
+        }
+    }
+
+    fn draw_tone_mapping(&mut self, ui: &Ui) {
+        let tone = self.tone;
+        let _item_width_stack_token = ui.push_item_width(-140.0);
+        if let Some(_combo) = ui.begin_combo("Tone curve", self.tone.curve.name()) {
+            for curve in ToneCurve::ALL {
+                if ui.selectable_config(curve.name()).selected(self.tone.curve == curve).build() {
+                    self.tone.curve = curve;
+                }
+            }
+        }
+        ui.slider_config("Exposure", 0.1, 20.0).display_format("%.2f").flags(imgui::SliderFlags::LOGARITHMIC).build(&mut self.tone.exposure);
+        ui.slider_config("Gamma", 0.2, 5.0).display_format("%.2f").build(&mut self.tone.gamma);
+        ui.checkbox("Normalize channels separately", &mut self.tone.normalize_channels);
+        self.changed |= tone != self.tone;
+    }
+
+    fn draw_image(&mut self, ui: &Ui) {
+        let (Some(buddhabrot), Some(target)) = (&self.buddhabrot, &self.target) else { return };
+
+        // the pixels are uploaded top to bottom, so the texture does not need to be flipped
+        let width = ui.content_region_avail()[0];
+        let height = width * target.height() as f32 / target.width() as f32;
+        Image::new(TextureId::new(target.texture() as usize), [width, height]).build(ui);
+
+        ui.input_text("File", &mut self.path).build();
+        if ui.button("Save PNG") {
+            let pixels = buddhabrot.to_rgba(&self.tone);
+            let text = [
+                ("Software", "fractal_renderer".to_string()),
+                ("Samples", buddhabrot.samples().to_string()),
+                ("Iteration limits", format!("{:?}", buddhabrot.settings().limits)),
+            ];
+            let path = Path::new(&self.path);
+            self.status = match image_writer::save_png(path, buddhabrot.width() as u32, buddhabrot.height() as u32, &pixels, &text) {
+                Ok(()) => format!("Saved {}", path.display()),
+                Err(err) => format!("Saving failed: {}", err),
+            };
+        }
+        ui.text_wrapped(&self.status);
+    }
+
+    /// throw away the accumulated density and start over with the current settings
+    fn start(&mut self, camera: Camera) {
+        match RenderTarget::new(self.width, self.height) {
+            Ok(target) => {
+                self.buddhabrot = Some(Buddhabrot::new(self.settings, camera, self.width, self.height));
+                self.target = Some(target);
+                self.running = true;
+                self.status.clear();
+            },
+            Err(err) => self.status = format!("Failed to create the image: {}", err),
+        }
+    }
+}
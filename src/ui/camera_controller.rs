@@ -0,0 +1,135 @@
+use crate::renderer::Camera;
+
+/// Smooth camera navigation
+/// keyboard input sets a target velocity which the camera accelerates towards and coasts out of when released,
+/// mouse wheel zooms are accumulated and applied gradually around the cursor
+/// all movement is scaled by the frame time, so it does not depend on the framerate or the key repeat rate
+pub struct CameraController {
+    /// held navigation keys, each component in range -1..=1
+    pan_input: [f64; 2],
+    zoom_input: f64,
+    rotation_input: f64,
+
+    /// pan velocity in window sizes per second
+    pan_velocity: [f64; 2],
+    /// zoom velocity in natural logarithm of the zoom per second
+    zoom_velocity: f64,
+    /// rotation velocity in radians per second
+    rotation_velocity: f64,
+
+    /// natural logarithm of the wheel zoom that is still to be applied
+    pending_zoom: f64,
+    /// window pixel that stays fixed while the pending zoom is applied
+    zoom_anchor: [f64; 2],
+    /// window pixel that stays fixed while a zoom key is held, the mouse position
+    key_zoom_anchor: [f64; 2],
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            pan_input: [0.0, 0.0],
+            zoom_input: 0.0,
+            rotation_input: 0.0,
+
+            pan_velocity: [0.0, 0.0],
+            zoom_velocity: 0.0,
+            rotation_velocity: 0.0,
+
+            pending_zoom: 0.0,
+            zoom_anchor: [0.0, 0.0],
+            key_zoom_anchor: [0.0, 0.0],
+        }
+    }
+}
+
+impl CameraController {
+    /// top panning speed in window sizes per second
+    const PAN_SPEED: f64 = 0.75;
+    /// top zooming speed, the zoom doubles every second
+    const ZOOM_SPEED: f64 = std::f64::consts::LN_2;
+    /// top rotation speed in radians per second
+    const ROTATION_SPEED: f64 = std::f64::consts::FRAC_PI_2;
+
+    /// how fast the velocity approaches the target while a key is held
+    const ACCELERATION: f64 = 10.0;
+    /// how fast the velocity decays after all keys are released
+    const FRICTION: f64 = 5.0;
+    /// how fast the pending wheel zoom is applied
+    const ZOOM_SMOOTHING: f64 = 12.0;
+
+    /// set the currently held navigation directions
+    pub fn set_input(&mut self, x: i32, y: i32, zoom: i32, rotation: i32) {
+        self.pan_input = [x.signum() as f64, y.signum() as f64];
+        self.zoom_input = zoom.signum() as f64;
+        self.rotation_input = rotation.signum() as f64;
+    }
+
+    /// set the window pixel the zoom keys zoom around
+    pub fn set_key_zoom_anchor(&mut self, x: f64, y: f64) {
+        self.key_zoom_anchor = [x, y];
+    }
+
+    /// queue a zoom by the given factor around the given window pixel
+    pub fn add_zoom(&mut self, factor: f64, x: i32, y: i32) {
+        self.pending_zoom += factor.ln();
+        self.zoom_anchor = [x as f64, y as f64];
+    }
+
+    /// drop all remaining motion, used when the camera is moved by other means
+    pub fn stop(&mut self) {
+        self.pan_velocity = [0.0, 0.0];
+        self.zoom_velocity = 0.0;
+        self.rotation_velocity = 0.0;
+        self.pending_zoom = 0.0;
+    }
+
+    /// advance the camera by `dt` seconds
+    pub fn update(&mut self, dt: f64, camera: &mut Camera, screen_width: f64, screen_height: f64) {
+        for axis in 0..2 {
+            self.pan_velocity[axis] = Self::approach(self.pan_velocity[axis], self.pan_input[axis] * Self::PAN_SPEED, dt);
+        }
+        self.zoom_velocity = Self::approach(self.zoom_velocity, self.zoom_input * Self::ZOOM_SPEED, dt);
+        self.rotation_velocity = Self::approach(self.rotation_velocity, self.rotation_input * Self::ROTATION_SPEED, dt);
+
+        if self.pan_velocity != [0.0, 0.0] {
+            camera.translate_pixels(
+                self.pan_velocity[0] * screen_width * dt,
+                self.pan_velocity[1] * screen_height * dt,
+                screen_width,
+                screen_height);
+        }
+        if self.zoom_velocity != 0.0 {
+            let anchor = camera.screen_to_complex(self.key_zoom_anchor[0], self.key_zoom_anchor[1], screen_width, screen_height);
+            camera.zoom_at((self.zoom_velocity * dt).exp(), anchor);
+        }
+        if self.rotation_velocity != 0.0 {
+            camera.rotate(self.rotation_velocity * dt);
+        }
+
+        if self.pending_zoom != 0.0 {
+            // exponential interpolation, apply a constant fraction of the remaining zoom each second
+            let mut step = self.pending_zoom * (1.0 - (-Self::ZOOM_SMOOTHING * dt).exp());
+            if (self.pending_zoom - step).abs() < 1e-4 {
+                step = self.pending_zoom;
+            }
+            self.pending_zoom -= step;
+
+            let anchor = camera.screen_to_complex(self.zoom_anchor[0], self.zoom_anchor[1], screen_width, screen_height);
+            camera.zoom_at(step.exp(), anchor);
+        }
+    }
+
+    /// move the velocity towards the target, accelerating while a key is held and coasting otherwise
+    fn approach(velocity: f64, target: f64, dt: f64) -> f64 {
+        let rate = if target != 0.0 { Self::ACCELERATION } else { Self::FRICTION };
+        let velocity = velocity + (target - velocity) * (1.0 - (-rate * dt).exp());
+
+        // snap to rest once the movement becomes invisible
+        if target == 0.0 && velocity.abs() < 1e-4 {
+            0.0
+        } else {
+            velocity
+        }
+    }
+}
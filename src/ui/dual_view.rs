@@ -0,0 +1,62 @@
+use crate::renderer::Camera;
+use crate::scene::{FractalKind, Formula, Scene};
+use crate::ui::camera_controller::CameraController;
+
+/// Julia pane shown to the right of the Mandelbrot set while the window is split
+/// the pane shows the Julia set of the point under the cursor, or of the pinned julia constant,
+/// and has its own camera, so the Julia set can be explored independently of the parameter plane
+pub struct DualView {
+    pub enabled: bool,
+    /// follow the cursor over the Mandelbrot pane instead of showing the julia constant of the scene
+    pub follow_cursor: bool,
+    /// point of the parameter plane under the cursor
+    pub hovered: Option<[f32; 2]>,
+    pub camera: Camera,
+    pub camera_controller: CameraController,
+}
+
+impl Default for DualView {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            follow_cursor: true,
+            hovered: None,
+            camera: Camera { width: 3.0, height: 3.0, ..Camera::default() },
+            camera_controller: CameraController::default(),
+        }
+    }
+}
+
+impl DualView {
+    /// width of the Mandelbrot pane in a window of the given width, the Julia pane takes the rest
+    /// the renderer splits the window the same way
+    pub fn split(width: i32) -> i32 {
+        width / 2
+    }
+
+    /// camera of the pane showing the whole Julia sets of the formula of the scene
+    pub fn home_camera(scene: &Scene) -> Camera {
+        match scene.formula {
+            Formula::MagnetI | Formula::MagnetII => Scene { fractal: FractalKind::Julia, ..*scene }.home_camera(),
+            Formula::Quadratic | Formula::Phoenix => Self::default().camera,
+        }
+    }
+
+    /// constant of the Julia set shown in the pane
+    pub fn julia_constant(&self, scene: &Scene) -> [f32; 2] {
+        match self.hovered {
+            Some(point) if self.follow_cursor => point,
+            _ => scene.julia_constant,
+        }
+    }
+
+    /// the scene rendered in the Julia pane, colored like the main scene
+    pub fn scene(&self, scene: &Scene) -> Scene {
+        Scene {
+            fractal: FractalKind::Julia,
+            julia_constant: self.julia_constant(scene),
+            camera: self.camera,
+            ..*scene
+        }
+    }
+}
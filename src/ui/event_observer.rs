@@ -1,5 +1,9 @@
 use std::cell::RefCell;
 use std::rc::Rc;
+use crate::lyapunov::LyapunovSequence;
+use crate::renderer::Camera;
+use crate::scene3d::Scene3D;
+use crate::scene::{EscapeNorm, Formula, Scene};
 
 /**
     File containing the Observer pattern traits and definitions
@@ -7,20 +11,47 @@ use std::rc::Rc;
 
 pub enum FractalType {
     Julia([f32; 2]),
-    Mandelbrot
+    Mandelbrot,
+    Lyapunov{sequence: LyapunovSequence, warmup: i32},
 }
 
 /// All possible events that can be observed in the application
 pub enum ObserverEvent {
-    Zoom(f32),
-    UnZoom(f32),
+    Zoom{factor: f32, x: i32, y: i32},
+    UnZoom{factor: f32, x: i32, y: i32},
     Translate{xrel: i32, yrel: i32},
+    Rotate(f32),
+    NavigationInput{x: i32, y: i32, zoom: i32, rotation: i32},
+    ResetView,
+    Screenshot,
+    ToggleUi,
+    NextFractal,
+    Undo,
+    Redo,
+    CopyLocation,
+    PasteLocation,
+    HistoryJump(usize),
+    RestoreScene(Scene),
+    SelectionChanged{from: [i32; 2], to: [i32; 2]},
+    ZoomToSelection{from: [i32; 2], to: [i32; 2]},
+    Click{x: i32, y: i32},
     WindowSizeChanged{width: i32, height: i32},
     FractalIterations(i32),
     FractalChoice(FractalType),
-    FractalAxisRange{x: [f32; 2], y: [f32; 2]},
+    FractalFormula{formula: Formula, parameter: [f32; 2]},
+    FractalBailout{radius: f32, norm: EscapeNorm},
+    FractalCamera(Camera),
     FractalHSVScaleChange{h: f32, s: f32, v: f32},
+    FractalPaletteOffset(f32),
     FractalTerminalColorChange{r: f32, g: f32, b: f32},
+    FractalJuliaPane(Option<Scene>),
+    Fractal3D(Option<Scene3D>),
+    /// look around or orbit in the 3D mode, in pixels the mouse moved
+    Look{xrel: i32, yrel: i32},
+    /// move towards the target in the 3D mode, positive values move closer
+    Dolly(f32),
+    /// held 3D movement directions
+    NavigationInput3D{forward: i32, right: i32, up: i32},
 }
 
 /// Observer trait for observer pattern
@@ -0,0 +1,252 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use imgui::{Drag, Ui};
+use crate::animation::{self, Easing, Keyframe};
+use crate::explorer::{DetailGrid, DetailMeasure};
+use crate::renderer::{image_writer, Camera, DataLayer, FractalRenderer, RenderTarget};
+use crate::scene::Scene;
+use crate::ui::event_observer::{Observable, Observer, ObserverEvent};
+
+/// Zoom from one camera to another
+struct Flight {
+    from: Camera,
+    to: Camera,
+    /// seconds the flight takes
+    duration: f64,
+    time: f64,
+    /// keyframe of the start, recorded once the leg is flown
+    keyframe: Option<Keyframe>,
+}
+
+/// Panel for exploring the fractal on its own, it keeps zooming into the most detailed parts of the view
+/// every leg probes the view, picks one of its most detailed regions and flies there, deep enough it flies back home
+/// and starts a new dive, the legs can be recorded as keyframes of the animation
+pub struct ExplorerWindow {
+    running: bool,
+    measure: DetailMeasure,
+    /// zoom factor of every leg, the view is split into this many regions across
+    zoom_step: f64,
+    /// speed in zoom doublings per second
+    speed: f64,
+    /// zoom after which the explorer flies back home, the precision of the shader ends not far beyond
+    max_zoom: f64,
+    easing: Easing,
+    record_keyframes: bool,
+
+    flight: Option<Flight>,
+    /// camera the explorer last showed, any other camera means the view was moved by hand
+    shown_camera: Option<Camera>,
+    target: Option<RenderTarget>,
+    rng: fastrand::Rng,
+    keyframes: Vec<Keyframe>,
+    legs: usize,
+    status: String,
+
+    observers: Vec<Rc<RefCell<dyn Observer>>>,
+}
+
+impl Default for ExplorerWindow {
+    fn default() -> Self {
+        Self {
+            running: false,
+            measure: DetailMeasure::EdgeDensity,
+            zoom_step: 8.0,
+            speed: 1.0,
+            max_zoom: 1e5,
+            easing: Easing::Linear,
+            record_keyframes: false,
+
+            flight: None,
+            shown_camera: None,
+            target: None,
+            rng: fastrand::Rng::new(),
+            keyframes: Vec::new(),
+            legs: 0,
+            status: String::new(),
+
+            observers: Vec::new(),
+        }
+    }
+}
+
+impl ExplorerWindow {
+    /// width of the probe in pixels, the height follows the aspect ratio of the view
+    const PROBE_WIDTH: i32 = 128;
+
+    /// advance the flight by `dt` seconds and show it in the main view, `scene` is the current view
+    pub fn update(&mut self, dt: f64, scene: &Scene) {
+        if !self.running {
+            return;
+        }
+        if let Some(shown) = self.shown_camera {
+            let camera = &scene.camera;
+            if camera.focus != shown.focus || camera.zoom != shown.zoom || camera.rotation != shown.rotation {
+                self.stop(scene, "Stopped, the view was moved");
+                return;
+            }
+        }
+        let Some(flight) = &mut self.flight else { return };
+
+        flight.time = (flight.time + dt).min(flight.duration);
+        let t = self.easing.apply(flight.time / flight.duration);
+        let from = Scene { camera: flight.from, ..*scene };
+        let to = Scene { camera: flight.to, ..*scene };
+        let camera = animation::interpolate(&from, &to, t).camera;
+        if flight.time >= flight.duration {
+            self.keyframes.extend(flight.keyframe.take());
+            self.flight = None;
+        }
+
+        self.shown_camera = Some(camera);
+        self.notify_observers(ObserverEvent::RestoreScene(Scene { camera, ..*scene }));
+    }
+
+    /// pick the next region once the last flight arrived, called once per frame before drawing into the window
+    pub fn update_target(&mut self, renderer: &FractalRenderer, scene: &Scene) {
+        if !self.running || self.flight.is_some() {
+            return;
+        }
+        if !scene.fractal.is_complex() {
+            self.stop(scene, "The explorer needs an escape-time fractal");
+            return;
+        }
+
+        let from = scene.camera;
+        let to = if from.zoom >= self.max_zoom {
+            self.status = String::from("Deep enough, flying home");
+            Self::home_camera(scene)
+        } else {
+            match self.pick_region(renderer, scene) {
+                Ok(Some([u, v])) => {
+                    self.status = format!("Leg {}, zooming into {:.3}, {:.3}", self.legs + 1, u, v);
+                    Camera { focus: from.transform_point(u, v), zoom: from.zoom * self.zoom_step, ..from }
+                },
+                Ok(None) => {
+                    self.status = String::from("No detail in view, flying home");
+                    Self::home_camera(scene)
+                },
+                Err(message) => {
+                    self.stop(scene, &message);
+                    return;
+                },
+            }
+        };
+
+        // every zoom doubling takes the same time, so the legs join at a steady pace
+        let doublings = (to.zoom / from.zoom).log2().abs().max(1.0);
+        let duration = doublings / self.speed.max(0.01);
+        let keyframe = self.record_keyframes.then_some(Keyframe { scene: *scene, duration, easing: self.easing });
+        self.flight = Some(Flight { from, to, duration, time: 0.0, keyframe });
+        self.shown_camera = Some(from);
+        self.legs += 1;
+    }
+
+    /// keyframes recorded since the last call, added to the animation by the application
+    pub fn take_keyframes(&mut self) -> Vec<Keyframe> {
+        std::mem::take(&mut self.keyframes)
+    }
+
+    /// home camera of the scene with the aspect ratio of the current view
+    fn home_camera(scene: &Scene) -> Camera {
+        let home = scene.home_camera();
+        Camera { width: home.height * scene.camera.width / scene.camera.height, ..home }
+    }
+
+    /// render the escape data of the view and pick one of its most detailed regions in normalized screen coordinates
+    fn pick_region(&mut self, renderer: &FractalRenderer, scene: &Scene) -> Result<Option<[f64; 2]>, String> {
+        let camera = &scene.camera;
+        let width = Self::PROBE_WIDTH;
+        let height = ((width as f64 * camera.height / camera.width).round() as i32).clamp(2, 4 * width);
+        if self.target.as_ref().map(|target| [target.width(), target.height()]) != Some([width, height]) {
+            self.target = Some(RenderTarget::new_float(width, height).map_err(|err| format!("{:?}", err))?);
+        }
+        let Some(target) = &self.target else { return Ok(None) };
+
+        renderer.render_data_to(scene, DataLayer::Escape, target);
+        target.bind();
+        let data = image_writer::read_pixels_f32(0, 0, width, height);
+        target.unbind();
+
+        // the regions have the aspect ratio of the view, so a leg zooms into exactly one of them
+        let across = self.zoom_step.round().max(2.0) as usize;
+        let grid = DetailGrid::from_data([width as usize, height as usize], &data, [across, across], self.measure, scene.max_iterations);
+        Ok(grid.pick(&mut self.rng))
+    }
+
+    /// stop when the window is closed, so the camera is not left flying without a way to stop it
+    pub fn close(&mut self, scene: &Scene) {
+        if self.running {
+            self.stop(scene, "Stopped");
+        }
+    }
+
+    fn start(&mut self) {
+        self.running = true;
+        self.flight = None;
+        self.shown_camera = None;
+        self.legs = 0;
+        self.status = String::from("Exploring");
+    }
+
+    /// stop exploring, the current view ends the recorded keyframes
+    fn stop(&mut self, scene: &Scene, status: &str) {
+        // the interrupted leg only lasts as long as it was flown
+        if let Some(Flight { keyframe: Some(keyframe), time, .. }) = self.flight.take() {
+            if time > 0.0 {
+                self.keyframes.push(Keyframe { duration: time, ..keyframe });
+            }
+        }
+        if self.running && self.record_keyframes && self.legs > 0 {
+            self.keyframes.push(Keyframe { scene: *scene, easing: self.easing, ..Keyframe::new(*scene) });
+        }
+        self.running = false;
+        self.flight = None;
+        self.shown_camera = None;
+        self.status = String::from(status);
+    }
+
+    /// draw the panel, `scene` is the current view
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool, scene: &Scene) {
+        ui.window("Auto explore")
+            .size([340.0, 280.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                if ui.button(if self.running { "Stop" } else { "Explore" }) {
+                    if self.running {
+                        self.stop(scene, "Stopped");
+                    } else {
+                        self.start();
+                    }
+                }
+
+                let _item_width_stack_token = ui.push_item_width(-130.0);
+                let mut measure_index = DetailMeasure::ALL.iter().position(|measure| *measure == self.measure).unwrap_or(0);
+                if ui.combo("Detail measure", &mut measure_index, &DetailMeasure::ALL, |measure| measure.name().into()) {
+                    self.measure = DetailMeasure::ALL[measure_index];
+                }
+                Drag::new("Zoom per leg").range(2.0, 64.0).speed(0.1).display_format("x%.1f").build(ui, &mut self.zoom_step);
+                Drag::new("Speed").range(0.05, 10.0).speed(0.01).display_format("%.2f doublings/s").build(ui, &mut self.speed);
+                Drag::new("Max zoom").range(10.0, 1e7).speed(100.0).display_format("%.3g").build(ui, &mut self.max_zoom);
+                let mut easing_index = Easing::ALL.iter().position(|easing| *easing == self.easing).unwrap_or(0);
+                if ui.combo("Easing", &mut easing_index, &Easing::ALL, |easing| easing.name().into()) {
+                    self.easing = Easing::ALL[easing_index];
+                }
+                ui.checkbox("Record keyframes into the animation", &mut self.record_keyframes);
+
+                ui.text_wrapped(&self.status);
+                ui.text_disabled("moving the view by hand stops the explorer");
+            });
+    }
+}
+
+impl Observable<'_> for ExplorerWindow {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer>>) {
+        self.observers.push(observer)
+    }
+
+    fn notify_observers(&mut self, event: ObserverEvent) {
+        for observer in self.observers.iter() {
+            observer.borrow_mut().notify(&event)
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+use imgui::{ProgressBar, Ui};
+use crate::renderer::{Error, FractalRenderer};
+use crate::renderer::image_export::ExportJob;
+
+/// Runs one export at a time a little every frame and reports its progress
+/// shared by the panels that start exports
+#[derive(Default)]
+pub struct ExportRunner {
+    export: Option<Box<dyn ExportJob>>,
+    status: String,
+}
+
+impl ExportRunner {
+    /// time spent rendering the export per frame
+    const FRAME_BUDGET: Duration = Duration::from_millis(30);
+
+    pub fn is_running(&self) -> bool {
+        self.export.is_some()
+    }
+
+    /// start a newly created export, or show why it could not be created
+    pub fn start(&mut self, export: Result<Box<dyn ExportJob>, Error>) {
+        match export {
+            Ok(export) => {
+                self.export = Some(export);
+                self.status.clear();
+            },
+            Err(err) => self.status = format!("Export failed: {}", err),
+        }
+    }
+
+    /// continue the running export, called once per frame before drawing into the window
+    pub fn update(&mut self, renderer: &FractalRenderer) {
+        let Some(export) = self.export.as_mut() else { return };
+
+        if let Some(result) = export.step(renderer, Self::FRAME_BUDGET) {
+            self.status = match result {
+                Ok(()) => format!("Saved {}", export.path().display()),
+                Err(err) => format!("Export failed: {}", err),
+            };
+            self.export = None;
+        }
+    }
+
+    /// draw the progress bar with a cancel button while an export is running
+    pub fn draw_progress(&mut self, ui: &Ui) {
+        if let Some(export) = &self.export {
+            let label = if export.is_saving() { "Writing file...".to_string() } else { format!("{:.1}%", export.progress() * 100.0) };
+            ProgressBar::new(export.progress()).overlay_text(label).build(ui);
+            if !export.is_saving() && ui.button("Cancel") {
+                self.export = None;
+                self.status = "Export cancelled".to_string();
+            }
+        }
+    }
+
+    /// draw the result of the last export
+    pub fn draw_status(&self, ui: &Ui) {
+        ui.text_wrapped(&self.status);
+    }
+}
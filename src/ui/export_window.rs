@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+use imgui::{Drag, Ui};
+use crate::renderer::FractalRenderer;
+use crate::renderer::data_export::{DataExport, DataFormat};
+use crate::renderer::image_export::{ExportJob, ExportSettings, ImageExport};
+use crate::scene::Scene;
+use crate::ui::export_runner::ExportRunner;
+
+/// Dialog for exporting the current view as a high resolution image or as raw data
+/// shows the progress of a running export and allows cancelling it
+pub struct ExportWindow {
+    settings: ExportSettings,
+    path: String,
+    data_format: DataFormat,
+    data_path: String,
+    runner: ExportRunner,
+}
+
+impl Default for ExportWindow {
+    fn default() -> Self {
+        Self {
+            settings: ExportSettings::default(),
+            path: String::from("export.png"),
+            data_format: DataFormat::Exr,
+            data_path: String::from("export_data.exr"),
+            runner: ExportRunner::default(),
+        }
+    }
+}
+
+impl ExportWindow {
+    /// largest supported image size along each axis
+    const MAX_SIZE: i32 = 16384;
+
+    /// continue a running export, called once per frame before drawing into the window
+    pub fn update(&mut self, renderer: &FractalRenderer) {
+        self.runner.update(renderer);
+    }
+
+    /// draw the dialog, `scene` is the view that gets exported
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool, scene: &Scene) {
+        ui.window("Export")
+            .size([340.0, 320.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                if self.runner.is_running() {
+                    self.runner.draw_progress(ui);
+                    return;
+                }
+
+                let _item_width_stack_token = ui.push_item_width(-80.0);
+                Drag::new("Width").range(1, Self::MAX_SIZE).build(ui, &mut self.settings.width);
+                Drag::new("Height").range(1, Self::MAX_SIZE).build(ui, &mut self.settings.height);
+
+                ui.separator();
+                ui.text("Image");
+                ui.slider_config("Samples", 1, 8).display_format("%d per axis").build(&mut self.settings.samples);
+                ui.checkbox("Jittered samples", &mut self.settings.jitter);
+                ui.input_text("File", &mut self.path).build();
+                if ui.button("Export image") {
+                    let export = ImageExport::new(scene, self.settings, PathBuf::from(&self.path));
+                    self.runner.start(export.map(|export| Box::new(export) as Box<dyn ExportJob>));
+                }
+
+                ui.separator();
+                ui.text("Raw data");
+                self.draw_data_format_combo(ui);
+                ui.input_text("Data file", &mut self.data_path).build();
+                if ui.button("Export data") {
+                    let export = DataExport::new(scene, self.settings.width, self.settings.height, self.data_format, PathBuf::from(&self.data_path));
+                    self.runner.start(export.map(|export| Box::new(export) as Box<dyn ExportJob>));
+                }
+
+                self.runner.draw_status(ui);
+            });
+    }
+
+    /// choose the format of the data export, the extension of the file name follows the format
+    fn draw_data_format_combo(&mut self, ui: &Ui) {
+        if let Some(_combo) = ui.begin_combo("Format", self.data_format.name()) {
+            for format in DataFormat::ALL {
+                if ui.selectable_config(format.name()).selected(self.data_format == format).build() {
+                    self.data_format = format;
+                    self.data_path = Path::new(&self.data_path).with_extension(format.extension()).to_string_lossy().into_owned();
+                }
+            }
+        }
+    }
+}
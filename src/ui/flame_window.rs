@@ -0,0 +1,267 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use imgui::{Drag, Image, TableColumnSetup, TableFlags, TextureId, Ui};
+use crate::flame::{Flame, FlameRenderer, Transform, Variation};
+use crate::renderer::{image_writer, RenderTarget};
+
+/// Panel editing and rendering iterated function systems and fractal flames
+/// the flame is rendered with the chaos game a little every frame and starts over whenever the transforms change
+pub struct FlameWindow {
+    flame: Flame,
+    path: String,
+    image_path: String,
+
+    renderer: Option<FlameRenderer>,
+    target: Option<RenderTarget>,
+    running: bool,
+    /// the image has to be tone mapped and uploaded again
+    changed: bool,
+    status: String,
+}
+
+impl Default for FlameWindow {
+    fn default() -> Self {
+        Self {
+            flame: Flame::default(),
+            path: String::from("flame.flame"),
+            image_path: String::from("flame.png"),
+
+            renderer: None,
+            target: None,
+            running: false,
+            changed: false,
+            status: String::new(),
+        }
+    }
+}
+
+impl FlameWindow {
+    /// time spent accumulating per frame
+    const FRAME_BUDGET: Duration = Duration::from_millis(20);
+
+    /// accumulate more points and show them, called once per frame before drawing into the window
+    pub fn update(&mut self) {
+        if self.renderer.as_ref().is_some_and(|renderer| !renderer.flame().same_density(&self.flame)) {
+            self.start();
+        }
+        let (Some(renderer), Some(target)) = (&mut self.renderer, &self.target) else { return };
+
+        if self.running {
+            renderer.accumulate(Self::FRAME_BUDGET);
+            self.changed = true;
+        }
+        if self.changed {
+            target.upload(&renderer.to_rgba(self.flame.brightness, self.flame.gamma));
+            self.changed = false;
+        }
+    }
+
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool) {
+        ui.window("Flames")
+            .size([640.0, 820.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                for preset in Flame::presets() {
+                    if ui.button(&preset.name) {
+                        self.flame = preset;
+                    }
+                    ui.same_line();
+                }
+                ui.new_line();
+                ui.input_text("Name", &mut self.flame.name).build();
+
+                self.draw_transforms(ui);
+                ui.separator();
+                self.draw_view(ui);
+                ui.separator();
+                self.draw_files(ui);
+                self.draw_image(ui);
+            });
+
+        if !*opened {
+            self.running = false;
+        }
+    }
+
+    /// table with one row per transform
+    fn draw_transforms(&mut self, ui: &Ui) {
+        let flags = TableFlags::BORDERS | TableFlags::ROW_BG | TableFlags::SIZING_STRETCH_PROP | TableFlags::RESIZABLE;
+        let mut removed = None;
+
+        if let Some(_table) = ui.begin_table_header_with_flags("##transforms", [
+            TableColumnSetup::new("Weight"),
+            TableColumnSetup::new("Color"),
+            TableColumnSetup::new("Affine (x axis, y axis, offset)"),
+            TableColumnSetup::new("Variations"),
+            TableColumnSetup::new(""),
+        ], flags) {
+            for (index, transform) in self.flame.transforms.iter_mut().enumerate() {
+                let _id = ui.push_id_usize(index);
+                ui.table_next_row();
+
+                ui.table_next_column();
+                ui.set_next_item_width(-f32::MIN_POSITIVE);
+                Drag::new("##weight").range(0.0, 100.0).speed(0.01).build(ui, &mut transform.weight);
+
+                ui.table_next_column();
+                ui.set_next_item_width(-f32::MIN_POSITIVE);
+                Drag::new("##color").range(0.0, 1.0).speed(0.005).build(ui, &mut transform.color);
+
+                ui.table_next_column();
+                Self::draw_affine(ui, transform);
+
+                ui.table_next_column();
+                Self::draw_variations(ui, transform);
+
+                ui.table_next_column();
+                if ui.small_button("Remove") {
+                    removed = Some(index);
+                }
+            }
+        }
+
+        if let Some(index) = removed {
+            self.flame.transforms.remove(index);
+        }
+        if ui.button("Add transform") {
+            self.flame.transforms.push(Transform::default());
+        }
+    }
+
+    /// the affine coefficients as the images of the x and y axis followed by the offset, like in flame editors
+    fn draw_affine(ui: &Ui, transform: &mut Transform) {
+        let _item_width_stack_token = ui.push_item_width(-f32::MIN_POSITIVE);
+        for (label, pair) in ["##x", "##y", "##o"].into_iter().zip(transform.coefs.chunks_exact_mut(2)) {
+            let mut values = [pair[0], pair[1]];
+            if Drag::new(label).speed(0.005).build_array(ui, &mut values) {
+                pair.copy_from_slice(&values);
+            }
+        }
+    }
+
+    fn draw_variations(ui: &Ui, transform: &mut Transform) {
+        let mut removed = None;
+        for (index, (variation, weight)) in transform.variations.iter_mut().enumerate() {
+            let _id = ui.push_id_usize(index);
+            ui.set_next_item_width(110.0);
+            if let Some(_combo) = ui.begin_combo("##variation", variation.name()) {
+                for option in Variation::ALL {
+                    if ui.selectable_config(option.name()).selected(*variation == option).build() {
+                        *variation = option;
+                    }
+                }
+            }
+            ui.same_line();
+            ui.set_next_item_width(60.0);
+            Drag::new("##variation_weight").speed(0.005).build(ui, weight);
+            ui.same_line();
+            if ui.small_button("x") {
+                removed = Some(index);
+            }
+        }
+
+        if let Some(index) = removed {
+            transform.variations.remove(index);
+        }
+        if ui.small_button("+") {
+            let unused = Variation::ALL.into_iter().find(|option| transform.variations.iter().all(|(variation, _)| variation != option));
+            transform.variations.push((unused.unwrap_or(Variation::Linear), 1.0));
+        }
+    }
+
+    fn draw_view(&mut self, ui: &Ui) {
+        let _item_width_stack_token = ui.push_item_width(-140.0);
+        Drag::new("Center").speed(0.01).build_array(ui, &mut self.flame.center);
+        Drag::new("Scale (pixels per unit)").range(1.0, 100_000.0).speed(1.0).build(ui, &mut self.flame.scale);
+        Drag::new("Size").range(Flame::MIN_SIZE, Flame::MAX_SIZE).build_array(ui, &mut self.flame.size);
+
+        let tone = (self.flame.brightness, self.flame.gamma);
+        ui.slider_config("Brightness", 0.1, 50.0).display_format("%.2f").flags(imgui::SliderFlags::LOGARITHMIC).build(&mut self.flame.brightness);
+        ui.slider_config("Gamma", 0.5, 8.0).display_format("%.2f").build(&mut self.flame.gamma);
+        self.changed |= tone != (self.flame.brightness, self.flame.gamma);
+
+        match &self.renderer {
+            Some(renderer) => {
+                if ui.button(if self.running { "Pause" } else { "Resume" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                ui.text(format!("{} points", renderer.samples()));
+            },
+            None => if ui.button("Render") {
+                self.start();
+            },
+        }
+    }
+
+    fn draw_files(&mut self, ui: &Ui) {
+        ui.input_text("Flame file", &mut self.path).build();
+        if ui.button("Save flame") {
+            self.status = match fs::write(&self.path, self.flame.to_xml()) {
+                Ok(()) => format!("Saved {}", self.path),
+                Err(err) => format!("Saving failed: {}", err),
+            };
+        }
+        ui.same_line();
+        if ui.button("Load flame") {
+            let loaded = fs::read_to_string(&self.path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| Flame::from_xml(&text));
+            self.status = match loaded {
+                Ok(flame) => {
+                    self.flame = flame;
+                    format!("Loaded {}", self.path)
+                },
+                Err(err) => format!("Loading failed: {}", err),
+            };
+        }
+        ui.text_wrapped(&self.status);
+    }
+
+    fn draw_image(&mut self, ui: &Ui) {
+        let (Some(renderer), Some(target)) = (&self.renderer, &self.target) else { return };
+
+        // the pixels are uploaded top to bottom, so the texture does not need to be flipped
+        let width = ui.content_region_avail()[0];
+        let height = width * target.height() as f32 / target.width() as f32;
+        Image::new(TextureId::new(target.texture() as usize), [width, height]).build(ui);
+
+        ui.input_text("Image file", &mut self.image_path).build();
+        if ui.button("Save PNG") {
+            let [width, height] = renderer.flame().size;
+            let pixels = renderer.to_rgba(self.flame.brightness, self.flame.gamma);
+            let text = [
+                ("Software", "fractal_renderer".to_string()),
+                ("Flame", self.flame.name.clone()),
+                ("Points", renderer.samples().to_string()),
+            ];
+            let path = Path::new(&self.image_path);
+            self.status = match image_writer::save_png(path, width as u32, height as u32, &pixels, &text) {
+                Ok(()) => format!("Saved {}", path.display()),
+                Err(err) => format!("Saving failed: {}", err),
+            };
+        }
+    }
+
+    /// throw away the accumulated points and start over with the current flame
+    fn start(&mut self) {
+        let renderer = FlameRenderer::new(&self.flame);
+        let [width, height] = renderer.flame().size;
+        let reuse = self.target.as_ref().is_some_and(|target| target.width() == width && target.height() == height);
+        if !reuse {
+            match RenderTarget::new(width, height) {
+                Ok(target) => self.target = Some(target),
+                Err(err) => {
+                    self.status = format!("Failed to create the image: {:?}", err);
+                    self.renderer = None;
+                    self.running = false;
+                    return;
+                },
+            }
+        }
+
+        self.renderer = Some(renderer);
+        self.running = true;
+    }
+}
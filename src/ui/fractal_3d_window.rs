@@ -0,0 +1,122 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use imgui::{Drag, Ui};
+use crate::scene3d::{Camera3D, CameraController3D, CameraMode, Scene3D, Shape};
+use crate::ui::event_observer::{Observable, Observer, ObserverEvent};
+
+/// Panel for the ray marched 3D fractals, which replace the 2D fractal in the main view while it is open
+/// moves the 3D camera with the mouse and the navigation keys passed on by the input handler
+pub struct Fractal3DWindow {
+    scene: Scene3D,
+    /// whether the main view shows the 3D fractal while the window is open
+    enabled: bool,
+    camera_controller: CameraController3D,
+
+    observers: Vec<Rc<RefCell<dyn Observer>>>,
+}
+
+impl Default for Fractal3DWindow {
+    fn default() -> Self {
+        Self {
+            scene: Scene3D::default(),
+            enabled: true,
+            camera_controller: CameraController3D::default(),
+
+            observers: Vec::new(),
+        }
+    }
+}
+
+impl Fractal3DWindow {
+    /// whether the main view shows the 3D fractal while the window is open
+    pub fn shows_3d(&self) -> bool {
+        self.enabled
+    }
+
+    /// move the camera by `dt` seconds and pass the scene on to the renderer, `shown` is false while the window is closed
+    pub fn update(&mut self, dt: f64, shown: bool) {
+        self.camera_controller.update(&mut self.scene.camera, dt, shown);
+        self.notify_observers(ObserverEvent::Fractal3D(shown.then_some(self.scene)));
+    }
+
+    /// put the camera back to where it shows the whole shape
+    fn reset_camera(&mut self) {
+        self.scene.camera = Camera3D { distance: self.scene.shape.home_distance(), fov: self.scene.camera.fov, ..Camera3D::default() };
+    }
+
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool) {
+        self.camera_controller.set_window_hovered(ui.io().want_capture_mouse);
+        ui.window("3D fractals")
+            .size([360.0, 480.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                ui.checkbox("Render in main view", &mut self.enabled);
+
+                let _item_width_stack_token = ui.push_item_width(-130.0);
+                let mut shape_index = self.scene.shape as usize;
+                if ui.combo("Shape", &mut shape_index, &Shape::ALL, |shape| shape.name().into()) {
+                    self.scene.shape = Shape::ALL[shape_index];
+                    self.reset_camera();
+                }
+                match self.scene.shape {
+                    Shape::Mandelbulb => {
+                        ui.slider("Power", 2.0, 16.0, &mut self.scene.power);
+                    },
+                    Shape::Mandelbox => {
+                        ui.slider("Box scale", -3.0, 3.0, &mut self.scene.box_scale);
+                    },
+                    Shape::MengerSponge => {},
+                }
+                ui.slider("Iterations", 1, 30, &mut self.scene.iterations);
+                ui.slider("Max steps", 16, 1000, &mut self.scene.max_steps);
+                ui.separator();
+
+                ui.slider("Shadow softness", 0.0, 64.0, &mut self.scene.shadow_softness);
+                ui.slider("Ambient occlusion", 0.0, 4.0, &mut self.scene.ambient_occlusion);
+                ui.slider("Fog density", 0.0, 1.0, &mut self.scene.fog_density);
+                ui.color_edit3("Fog color", &mut self.scene.fog_color);
+                ui.slider("Palette offset", 0.0, 1.0, &mut self.scene.palette_offset);
+                ui.separator();
+
+                ui.radio_button("Orbit", &mut self.camera_controller.mode, CameraMode::Orbit);
+                ui.same_line();
+                ui.radio_button("Fly", &mut self.camera_controller.mode, CameraMode::Fly);
+                ui.slider("Field of view", 10.0, 120.0, &mut self.scene.camera.fov);
+                Drag::new("Speed").range(0.01, 10.0).speed(0.01).build(ui, &mut self.camera_controller.speed);
+                if ui.button("Reset camera") {
+                    self.reset_camera();
+                }
+
+                let [x, y, z] = self.scene.camera.position();
+                ui.text(format!("Camera at {:.4}, {:.4}, {:.4}", x, y, z));
+                ui.text_disabled(match self.camera_controller.mode {
+                    CameraMode::Orbit => "drag to orbit, wheel or W/S to zoom, A/D and Q/E to circle",
+                    CameraMode::Fly => "drag to look, W/S/A/D to move, Q/E down and up, wheel to step",
+                });
+            });
+    }
+}
+
+impl Observer for Fractal3DWindow {
+    fn notify(&mut self, event: &ObserverEvent) {
+        match event {
+            ObserverEvent::NavigationInput3D{forward, right, up} => self.camera_controller.set_movement(*forward, *right, *up),
+            ObserverEvent::Look{xrel, yrel} => self.camera_controller.look(&mut self.scene.camera, *xrel, *yrel),
+            ObserverEvent::Dolly(steps) => self.camera_controller.dolly(&mut self.scene.camera, *steps),
+            ObserverEvent::ResetView if self.camera_controller.is_active() => self.reset_camera(),
+            _ => {}
+        }
+    }
+}
+
+impl Observable<'_> for Fractal3DWindow {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer>>) {
+        self.observers.push(observer)
+    }
+
+    fn notify_observers(&mut self, event: ObserverEvent) {
+        for observer in self.observers.iter() {
+            observer.borrow_mut().notify(&event)
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::fs;
+use imgui::Ui;
+use crate::heightfield::{HeightField, HeightSource, Mesh};
+use crate::renderer::{image_writer, DataLayer, FractalRenderer, MeshRenderer, RenderTarget};
+use crate::scene::Scene;
+use crate::scene3d::{Camera3D, CameraController3D};
+use crate::ui::event_observer::{Observer, ObserverEvent};
+
+/// Panel turning the escape time of the current view into a 3D landscape, which replaces the fractal in the main view
+/// the terrain follows the 2D view and is looked at with an orbit camera moved by the 3D navigation of the input handler
+pub struct HeightFieldWindow {
+    source: HeightSource,
+    /// samples across the view
+    resolution: i32,
+    height_scale: f32,
+    /// thickness of the solid below the lowest point
+    base: f32,
+    /// whether the main view shows the landscape while the window is open
+    enabled: bool,
+    camera: Camera3D,
+    camera_controller: CameraController3D,
+    obj_path: String,
+    stl_path: String,
+
+    /// scene and settings the mesh was last generated from
+    generated: Option<(Scene, HeightSource, i32, f32, f32)>,
+    mesh: Mesh,
+    error: Option<String>,
+    status: String,
+}
+
+impl Default for HeightFieldWindow {
+    fn default() -> Self {
+        Self {
+            source: HeightSource::SmoothIterations,
+            resolution: 256,
+            height_scale: 0.5,
+            base: 0.05,
+            enabled: true,
+            camera: Self::home_camera(),
+            camera_controller: CameraController3D::default(),
+            obj_path: String::from("heightfield.obj"),
+            stl_path: String::from("heightfield.stl"),
+
+            generated: None,
+            mesh: Mesh::default(),
+            error: None,
+            status: String::new(),
+        }
+    }
+}
+
+impl HeightFieldWindow {
+    fn home_camera() -> Camera3D {
+        Camera3D { distance: 3.0, yaw: 0.0, pitch: 0.8, ..Camera3D::default() }
+    }
+
+    /// whether the main view shows the landscape while the window is open
+    pub fn shows_landscape(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn camera(&self) -> &Camera3D {
+        &self.camera
+    }
+
+    /// move the camera by `dt` seconds, `shown` is false while the landscape is not in the main view
+    pub fn update(&mut self, dt: f64, shown: bool) {
+        self.camera_controller.update(&mut self.camera, dt, shown);
+    }
+
+    /// build the mesh again if the scene or the settings changed, called once per frame while the window is open
+    pub fn update_mesh(&mut self, fractal_renderer: &FractalRenderer, scene: &Scene, mesh_renderer: &mut MeshRenderer) {
+        let settings = (*scene, self.source, self.resolution, self.height_scale, self.base);
+        if self.generated == Some(settings) {
+            return;
+        }
+        self.generated = Some(settings);
+
+        if !scene.fractal.is_complex() {
+            self.mesh = Mesh::default();
+            self.error = Some(String::from("The height field needs an escape-time fractal"));
+        } else {
+            match Self::sample(fractal_renderer, scene, self.source, self.resolution) {
+                Ok(field) => {
+                    self.mesh = field.mesh(self.height_scale, self.base);
+                    self.error = None;
+                },
+                Err(message) => {
+                    self.mesh = Mesh::default();
+                    self.error = Some(message);
+                },
+            }
+        }
+        mesh_renderer.set_mesh(&self.mesh);
+    }
+
+    /// render the escape data and the colors of the scene on a grid of `resolution` samples across
+    fn sample(fractal_renderer: &FractalRenderer, scene: &Scene, source: HeightSource, resolution: i32) -> Result<HeightField, String> {
+        let camera = &scene.camera;
+        let width = resolution;
+        let height = ((resolution as f64 * camera.height / camera.width).round() as i32).clamp(2, 4 * resolution);
+
+        let data_target = RenderTarget::new_float(width, height).map_err(|err| format!("{:?}", err))?;
+        fractal_renderer.render_data_to(scene, DataLayer::Escape, &data_target);
+        data_target.bind();
+        let data = image_writer::read_pixels_f32(0, 0, width, height);
+        data_target.unbind();
+
+        let color_target = RenderTarget::new(width, height).map_err(|err| format!("{:?}", err))?;
+        fractal_renderer.render_scene_to(scene, &color_target);
+        color_target.bind();
+        let colors = image_writer::read_pixels(0, 0, width, height);
+        color_target.unbind();
+
+        let pixel_size = camera.width / camera.zoom / width as f64;
+        Ok(HeightField::from_data([width as usize, height as usize], &data, &colors, source, scene.max_iterations, pixel_size))
+    }
+
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool) {
+        self.camera_controller.set_window_hovered(ui.io().want_capture_mouse);
+        ui.window("Height field")
+            .size([340.0, 360.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                ui.checkbox("Show in main view", &mut self.enabled);
+
+                let _item_width_stack_token = ui.push_item_width(-110.0);
+                let mut source_index = HeightSource::ALL.iter().position(|source| *source == self.source).unwrap_or(0);
+                if ui.combo("Height from", &mut source_index, &HeightSource::ALL, |source| source.name().into()) {
+                    self.source = HeightSource::ALL[source_index];
+                }
+                ui.slider("Resolution", 16, 1024, &mut self.resolution);
+                ui.slider("Height", 0.0, 2.0, &mut self.height_scale);
+                ui.slider("Base", 0.0, 0.5, &mut self.base);
+                ui.slider("Field of view", 10.0, 120.0, &mut self.camera.fov);
+                if ui.button("Reset camera") {
+                    self.camera = Camera3D { fov: self.camera.fov, ..Self::home_camera() };
+                }
+
+                match &self.error {
+                    Some(message) => ui.text_colored([1.0, 0.4, 0.4, 1.0], message),
+                    None => ui.text(format!("{} triangles", self.mesh.triangles.len())),
+                }
+                ui.text_disabled("drag to orbit, wheel or W/S to zoom, A/D and Q/E to circle");
+                ui.separator();
+
+                ui.input_text("OBJ file", &mut self.obj_path).build();
+                if ui.button("Export OBJ") {
+                    self.status = match fs::write(&self.obj_path, self.mesh.to_obj()) {
+                        Ok(()) => format!("Saved {}", self.obj_path),
+                        Err(err) => format!("Saving failed: {}", err),
+                    };
+                }
+                ui.input_text("STL file", &mut self.stl_path).build();
+                if ui.button("Export STL") {
+                    self.status = match fs::write(&self.stl_path, self.mesh.to_stl()) {
+                        Ok(()) => format!("Saved {}", self.stl_path),
+                        Err(err) => format!("Saving failed: {}", err),
+                    };
+                }
+                ui.text_wrapped(&self.status);
+            });
+    }
+}
+
+impl Observer for HeightFieldWindow {
+    fn notify(&mut self, event: &ObserverEvent) {
+        match event {
+            ObserverEvent::NavigationInput3D{forward, right, up} => self.camera_controller.set_movement(*forward, *right, *up),
+            ObserverEvent::Look{xrel, yrel} => self.camera_controller.look(&mut self.camera, *xrel, *yrel),
+            ObserverEvent::Dolly(steps) => self.camera_controller.dolly(&mut self.camera, *steps),
+            ObserverEvent::ResetView if self.camera_controller.is_active() => {
+                self.camera = Camera3D { fov: self.camera.fov, ..Self::home_camera() };
+            },
+            _ => {}
+        }
+    }
+}
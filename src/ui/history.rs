@@ -0,0 +1,210 @@
+use crate::renderer::{FractalRenderer, RenderTarget};
+use crate::scene::Scene;
+
+/// Single step in the history
+pub struct HistoryEntry {
+    pub scene: Scene,
+    pub label: String,
+    pub thumbnail: Option<RenderTarget>,
+}
+
+/// Undo/redo history of scenes
+/// a change is recorded once the scene stays the same for a moment,
+/// so continuous drags and animated zooms end up as a single entry
+#[derive(Default)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    current: usize,
+
+    /// scene that changed but did not settle yet and for how long it has been unchanged
+    pending: Option<Scene>,
+    pending_time: f64,
+    /// the current entry was just restored and for how long the scene has been unchanged since
+    /// restoring adapts the scene to the view, its width to the aspect ratio and the automatic iterations to the detail,
+    /// these changes update the entry until the scene settles instead of adding a new one and discarding the redo steps
+    restoring: bool,
+    restoring_time: f64,
+}
+
+impl History {
+    /// how long a scene has to stay unchanged before it is recorded, in seconds
+    const SETTLE_TIME: f64 = 0.4;
+    /// maximum number of entries, the oldest entries are dropped first
+    const MAX_ENTRIES: usize = 100;
+    /// width of the thumbnails in pixels, the height follows the aspect ratio of the camera
+    const THUMBNAIL_WIDTH: i32 = 96;
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// track the current scene, called every frame with the frame time
+    /// the first recorded scene becomes the starting point of the history
+    pub fn record(&mut self, scene: &Scene, dt: f64) {
+        if self.entries.is_empty() {
+            self.push(*scene);
+            return;
+        }
+
+        if self.restoring {
+            if *scene == self.entries[self.current].scene {
+                self.restoring_time += dt;
+                self.restoring = self.restoring_time < Self::SETTLE_TIME;
+            } else {
+                self.entries[self.current].scene = *scene;
+                self.restoring_time = 0.0;
+            }
+            return;
+        }
+
+        if *scene == self.entries[self.current].scene {
+            self.pending = None;
+            return;
+        }
+
+        if self.pending.as_ref() != Some(scene) {
+            self.pending = Some(*scene);
+            self.pending_time = 0.0;
+            return;
+        }
+
+        self.pending_time += dt;
+        if self.pending_time >= Self::SETTLE_TIME {
+            self.push(*scene);
+        }
+    }
+
+    /// step back, returns the scene to restore
+    /// a change that did not settle yet is recorded first, so it can be redone
+    pub fn undo(&mut self, scene: &Scene) -> Option<Scene> {
+        if self.restoring {
+            self.entries[self.current].scene = *scene;
+        } else if self.entries.get(self.current).map(|entry| entry.scene) != Some(*scene) {
+            self.push(*scene);
+        }
+
+        if self.current == 0 {
+            return None;
+        }
+        self.restore(self.current - 1)
+    }
+
+    /// step forward, returns the scene to restore
+    pub fn redo(&mut self) -> Option<Scene> {
+        if self.current + 1 >= self.entries.len() {
+            return None;
+        }
+        self.restore(self.current + 1)
+    }
+
+    /// jump directly to an entry, returns the scene to restore
+    pub fn jump_to(&mut self, index: usize) -> Option<Scene> {
+        if index >= self.entries.len() {
+            return None;
+        }
+        self.restore(index)
+    }
+
+    /// make an entry the current one, returns its scene
+    fn restore(&mut self, index: usize) -> Option<Scene> {
+        self.pending = None;
+        self.restoring = true;
+        self.restoring_time = 0.0;
+        self.current = index;
+        Some(self.entries[index].scene)
+    }
+
+    /// render the thumbnails of entries that do not have one yet
+    pub fn update_thumbnails(&mut self, renderer: &FractalRenderer) {
+        for entry in self.entries.iter_mut().filter(|entry| entry.thumbnail.is_none()) {
+            match renderer.render_thumbnail(&entry.scene, Self::THUMBNAIL_WIDTH) {
+                Ok(target) => entry.thumbnail = Some(target),
+                Err(err) => {
+                    eprintln!("Error creating history thumbnail: {:?}", err);
+                    return;
+                },
+            }
+        }
+    }
+
+    /// add a new entry after the current one, discarding everything that could be redone
+    fn push(&mut self, scene: Scene) {
+        let label = match self.entries.get(self.current) {
+            Some(previous) => scene.describe_change(&previous.scene),
+            None => "Start".to_string(),
+        };
+
+        self.entries.truncate(self.current + 1);
+        self.entries.push(HistoryEntry { scene, label, thumbnail: None });
+
+        if self.entries.len() > Self::MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.current = self.entries.len() - 1;
+        self.pending = None;
+        self.restoring = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// record the scene for long enough to settle
+    fn settle(history: &mut History, scene: &Scene) {
+        for _ in 0..3 {
+            history.record(scene, History::SETTLE_TIME);
+        }
+    }
+
+    fn zoomed(zoom: f64) -> Scene {
+        let mut scene = Scene::default();
+        scene.camera.zoom = zoom;
+        scene
+    }
+
+    #[test]
+    fn records_settled_changes() {
+        let mut history = History::default();
+        settle(&mut history, &zoomed(1.0));
+        // a change that does not settle is not recorded
+        history.record(&zoomed(2.0), 0.1);
+        settle(&mut history, &zoomed(4.0));
+        assert_eq!(history.entries().len(), 2);
+        assert_eq!(history.entries()[1].scene, zoomed(4.0));
+    }
+
+    #[test]
+    fn restoring_keeps_the_redo_steps() {
+        let mut history = History::default();
+        for zoom in [1.0, 2.0, 4.0] {
+            settle(&mut history, &zoomed(zoom));
+        }
+
+        let restored = history.undo(&zoomed(4.0)).unwrap();
+        assert_eq!(restored, zoomed(2.0));
+        // the restored scene is adapted to the view, e.g. to its aspect ratio and the automatic iterations
+        let adapted = Scene { max_iterations: restored.max_iterations * 2, ..restored };
+        history.record(&restored, 0.1);
+        settle(&mut history, &adapted);
+        assert_eq!(history.entries().len(), 3);
+        assert_eq!(history.current(), 1);
+        assert_eq!(history.entries()[1].scene, adapted);
+
+        // undoing again right away does not add the adapted scene either
+        history.redo().unwrap();
+        assert_eq!(history.undo(&zoomed(4.0)), Some(adapted));
+        assert_eq!(history.entries().len(), 3);
+
+        // once settled, a change is a new entry again and discards the redo steps
+        settle(&mut history, &adapted);
+        settle(&mut history, &zoomed(8.0));
+        assert_eq!(history.entries().len(), 3);
+        assert_eq!(history.current(), 2);
+        assert_eq!(history.entries()[2].scene, zoomed(8.0));
+    }
+}
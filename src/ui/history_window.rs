@@ -0,0 +1,74 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use imgui::{Image, TextureId, Ui};
+use crate::ui::event_observer::{Observable, Observer, ObserverEvent};
+use crate::ui::history::History;
+
+/// Panel listing the history entries with their thumbnails
+/// clicking an entry jumps back to it
+pub struct HistoryWindow {
+    history: Rc<RefCell<History>>,
+    observers: Vec<Rc<RefCell<dyn Observer>>>,
+}
+
+impl HistoryWindow {
+    pub fn new(history: Rc<RefCell<History>>) -> Self {
+        Self {
+            history,
+            observers: Vec::new(),
+        }
+    }
+
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool) {
+        let mut clicked = None;
+
+        ui.window("History")
+            .size([300.0, 400.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                if ui.button("Undo") {
+                    self.notify_observers(ObserverEvent::Undo);
+                }
+                ui.same_line();
+                if ui.button("Redo") {
+                    self.notify_observers(ObserverEvent::Redo);
+                }
+                ui.separator();
+
+                let history = self.history.borrow();
+                for (index, entry) in history.entries().iter().enumerate().rev() {
+                    let _id = ui.push_id_usize(index);
+
+                    if let Some(thumbnail) = &entry.thumbnail {
+                        Image::new(TextureId::new(thumbnail.texture() as usize), [thumbnail.width() as f32, thumbnail.height() as f32])
+                            // the texture is stored bottom to top
+                            .uv0([0.0, 1.0])
+                            .uv1([1.0, 0.0])
+                            .build(ui);
+                        ui.same_line();
+                    }
+
+                    let label = format!("{}. {}", index + 1, entry.label);
+                    if ui.selectable_config(label).selected(index == history.current()).build() {
+                        clicked = Some(index);
+                    }
+                }
+            });
+
+        if let Some(index) = clicked {
+            self.notify_observers(ObserverEvent::HistoryJump(index));
+        }
+    }
+}
+
+impl Observable<'_> for HistoryWindow {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer>>) {
+        self.observers.push(observer)
+    }
+
+    fn notify_observers(&mut self, event: ObserverEvent) {
+        for observer in self.observers.iter() {
+            observer.borrow_mut().notify(&event)
+        }
+    }
+}
@@ -1,89 +1,109 @@
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::rc::Rc;
 use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Keycode;
 use crate::ui::event_observer::{Observable, Observer, ObserverEvent};
+use crate::ui::key_bindings::{Action, Binding, KeyBindings, Modifiers, Trigger};
 
 
 pub struct InputHandler {
-    left_btn_down: bool,
-    translation_x: i32,
-    translation_y: i32,
+    bindings: Rc<RefCell<KeyBindings>>,
+    held_keys: HashSet<Keycode>,
+    active_actions: HashSet<Action>,
+    mouse_position: [i32; 2],
+    selection_start: [i32; 2],
+    /// where the drag started while it is still short enough to count as a click
+    click_start: Option<[i32; 2]>,
+    /// whether the 3D fractals are shown, the mouse and the navigation keys then move the 3D camera
+    navigation_3d: bool,
     observers: Vec<Rc<RefCell<dyn Observer>>>,
 }
 
-impl Default for InputHandler {
-    fn default() -> Self {
+impl InputHandler {
+    /// rotation applied per pixel of horizontal mouse movement while rotate-dragging
+    const ROTATION_DRAG_SPEED: f32 = 0.005;
+    /// pixels the mouse may move between pressing and releasing the pan button for a click
+    const CLICK_TOLERANCE: i32 = 3;
+
+    /// create a new input handler translating events according to the given bindings
+    pub fn new(bindings: Rc<RefCell<KeyBindings>>) -> Self {
         Self {
-            left_btn_down: false,
-            translation_x: 0,
-            translation_y: 0,
+            bindings,
+            held_keys: HashSet::new(),
+            active_actions: HashSet::new(),
+            mouse_position: [0, 0],
+            selection_start: [0, 0],
+            click_start: None,
+            navigation_3d: false,
             observers: Vec::new()
         }
     }
-}
 
-impl InputHandler {
+    /// switch between navigating the 2D view and the 3D camera
+    pub fn set_navigation_3d(&mut self, enabled: bool) {
+        if self.navigation_3d != enabled {
+            self.navigation_3d = enabled;
+            // stop the movement of the mode that is left
+            self.notify_navigation_input();
+        }
+    }
+
+    /// handle a single SDL event, returns false if the application should quit
     pub fn handle_input(&mut self, event: &Event) -> bool {
         match event {
-            Event::Quit { .. }
-            | Event::KeyDown {
-                keycode: Some(Keycode::Escape),
-                ..
-            } => return false,
+            Event::Quit { .. } => return false,
+            Event::MouseWheel {y, ..} if self.navigation_3d => {
+                self.notify_observers(ObserverEvent::Dolly(*y as f32));
+            },
             Event::MouseWheel {y, ..} => {
+                // zoom around the last known cursor position
+                let [x, y_pos] = self.mouse_position;
                 if y > &0 {
-                    self.notify_observers(ObserverEvent::Zoom(1.1))
+                    self.notify_observers(ObserverEvent::Zoom{ factor: 1.1, x, y: y_pos })
                 } else {
-                    self.notify_observers(ObserverEvent::UnZoom(1.1))
+                    self.notify_observers(ObserverEvent::UnZoom{ factor: 1.1, x, y: y_pos })
                 }
             },
-            Event::KeyDown {keycode, ..} => {
-                match keycode {
-                    Some(Keycode::W) => {
-                        self.translation_y = 10;
-                        self.notify_observers(ObserverEvent::Translate{ xrel: self.translation_x, yrel: self.translation_y})
-                    },
-                    Some(Keycode::S) => {
-                        self.translation_y = -10;
-                        self.notify_observers(ObserverEvent::Translate{ xrel: self.translation_x, yrel: self.translation_y})
-                    },
-                    Some(Keycode::A) => {
-                        self.translation_x = -10;
-                        self.notify_observers(ObserverEvent::Translate{ xrel: self.translation_x, yrel: self.translation_y})
-                    }
-                    Some(Keycode::D) => {
-                        self.translation_x = 10;
-                        self.notify_observers(ObserverEvent::Translate{ xrel: self.translation_x, yrel: self.translation_y})
-                    },
-                    Some(Keycode::Equals) => self.notify_observers(ObserverEvent::Zoom(1.1)),
-                    Some(Keycode::Minus) => self.notify_observers(ObserverEvent::UnZoom(1.1)),
-                    _ => {}
-                }
+            Event::KeyDown {keycode: Some(keycode), repeat: false, ..} => {
+                self.held_keys.insert(*keycode);
+                return self.press(Trigger::Key(*keycode));
             },
-            Event::KeyUp {keycode, ..} => {
-                match keycode {
-                    Some(Keycode::W) => self.translation_y = 0,
-                    Some(Keycode::S) => self.translation_y = 0,
-                    Some(Keycode::A) => self.translation_x = 0,
-                    Some(Keycode::D) => self.translation_x = 0,
-                    _ => {}
-                }
+            Event::KeyUp {keycode: Some(keycode), ..} => {
+                self.held_keys.remove(keycode);
+                self.release(Trigger::Key(*keycode));
             },
-            Event::MouseButtonDown {mouse_btn, ..} => {
-                if *mouse_btn == sdl2::mouse::MouseButton::Left {
-                    self.left_btn_down = true;
-                }
+            Event::MouseButtonDown {mouse_btn, x, y, ..} => {
+                self.mouse_position = [*x, *y];
+                return self.press(Trigger::Mouse(*mouse_btn));
             },
-            Event::MouseButtonUp {mouse_btn, ..} => {
-                if *mouse_btn == sdl2::mouse::MouseButton::Left {
-                    self.left_btn_down = false;
-                }
+            Event::MouseButtonUp {mouse_btn, x, y, ..} => {
+                self.mouse_position = [*x, *y];
+                self.release(Trigger::Mouse(*mouse_btn));
             },
-            Event::MouseMotion {xrel, yrel, ..} => {
-                if self.left_btn_down {
+            Event::MouseMotion {x, y, xrel, yrel, ..} => {
+                self.mouse_position = [*x, *y];
+                if let Some(start) = self.click_start {
+                    if (x - start[0]).abs() > Self::CLICK_TOLERANCE || (y - start[1]).abs() > Self::CLICK_TOLERANCE {
+                        self.click_start = None;
+                    }
+                }
+
+                if self.navigation_3d {
+                    if self.active_actions.contains(&Action::DragPan) {
+                        self.notify_observers(ObserverEvent::Look{ xrel: *xrel, yrel: *yrel });
+                    }
+                    return true;
+                }
+
+                if self.active_actions.contains(&Action::DragRotate) {
+                    self.notify_observers(ObserverEvent::Rotate(*xrel as f32 * Self::ROTATION_DRAG_SPEED));
+                } else if self.active_actions.contains(&Action::DragPan) {
                     self.notify_observers(ObserverEvent::Translate{ xrel: -*xrel, yrel: *yrel});
                 }
+                if self.active_actions.contains(&Action::SelectionZoom) {
+                    self.notify_observers(ObserverEvent::SelectionChanged{ from: self.selection_start, to: [*x, *y] });
+                }
             },
             Event::Window {
                 win_event, ..
@@ -100,6 +120,105 @@ impl InputHandler {
 
         true
     }
+
+    /// start all actions bound to the trigger with the currently held modifiers
+    /// if an action is waiting to be rebound the trigger is bound to it instead
+    fn press(&mut self, trigger: Trigger) -> bool {
+        if trigger.is_modifier() {
+            return true;
+        }
+
+        let binding = Binding { trigger, modifiers: self.modifiers() };
+
+        let capturing = self.bindings.borrow_mut().capturing.take();
+        if let Some(action) = capturing {
+            // a plain escape cancels the rebinding
+            if binding != (Binding { trigger: Trigger::Key(Keycode::Escape), modifiers: Modifiers::default() }) {
+                self.bindings.borrow_mut().add_binding(action, binding);
+            }
+            return true;
+        }
+
+        let actions: Vec<Action> = self.bindings.borrow().actions_for(&binding).collect();
+        for action in actions {
+            self.active_actions.insert(action);
+
+            match action {
+                Action::Quit => return false,
+                Action::SelectionZoom => self.selection_start = self.mouse_position,
+                Action::DragPan => self.click_start = Some(self.mouse_position),
+                Action::ResetView => self.notify_observers(ObserverEvent::ResetView),
+                Action::Screenshot => self.notify_observers(ObserverEvent::Screenshot),
+                Action::ToggleUi => self.notify_observers(ObserverEvent::ToggleUi),
+                Action::NextFractal => self.notify_observers(ObserverEvent::NextFractal),
+                Action::Undo => self.notify_observers(ObserverEvent::Undo),
+                Action::Redo => self.notify_observers(ObserverEvent::Redo),
+                Action::CopyLocation => self.notify_observers(ObserverEvent::CopyLocation),
+                Action::PasteLocation => self.notify_observers(ObserverEvent::PasteLocation),
+                _ => {}
+            }
+        }
+
+        self.notify_navigation_input();
+        true
+    }
+
+    /// stop all actions started by the trigger
+    fn release(&mut self, trigger: Trigger) {
+        let actions: Vec<Action> = self.bindings.borrow().actions_for_trigger(trigger).collect();
+        for action in actions {
+            if !self.active_actions.remove(&action) {
+                continue;
+            }
+
+            match action {
+                // the 3D camera has no selection or clicks
+                Action::SelectionZoom | Action::DragPan if self.navigation_3d => self.click_start = None,
+                // finish the rubber-band selection and zoom into it
+                Action::SelectionZoom => self.notify_observers(ObserverEvent::ZoomToSelection{ from: self.selection_start, to: self.mouse_position }),
+                // a drag that barely moved is a click
+                Action::DragPan if self.click_start.take().is_some() => {
+                    let [x, y] = self.mouse_position;
+                    self.notify_observers(ObserverEvent::Click{ x, y });
+                },
+                _ => {}
+            }
+        }
+
+        self.notify_navigation_input();
+    }
+
+    /// notify observers about the held navigation directions
+    /// in the 3D mode panning moves forward and sideways and rotating moves down and up, the 2D view stands still
+    fn notify_navigation_input(&mut self) {
+        let x = self.action_axis(Action::PanRight, Action::PanLeft);
+        let y = self.action_axis(Action::PanUp, Action::PanDown);
+        let zoom = self.action_axis(Action::ZoomIn, Action::ZoomOut);
+        let rotation = self.action_axis(Action::RotateLeft, Action::RotateRight);
+
+        if self.navigation_3d {
+            self.notify_observers(ObserverEvent::NavigationInput{x: 0, y: 0, zoom: 0, rotation: 0});
+            self.notify_observers(ObserverEvent::NavigationInput3D{forward: y, right: x, up: -rotation});
+        } else {
+            self.notify_observers(ObserverEvent::NavigationInput{x, y, zoom, rotation});
+            self.notify_observers(ObserverEvent::NavigationInput3D{forward: 0, right: 0, up: 0});
+        }
+    }
+
+    /// direction along an axis controlled by a pair of opposing actions
+    fn action_axis(&self, positive: Action, negative: Action) -> i32 {
+        self.active_actions.contains(&positive) as i32 - self.active_actions.contains(&negative) as i32
+    }
+
+    /// modifier keys that are currently held
+    fn modifiers(&self) -> Modifiers {
+        let held = |left, right| self.held_keys.contains(&left) || self.held_keys.contains(&right);
+        Modifiers {
+            ctrl: held(Keycode::LCtrl, Keycode::RCtrl),
+            shift: held(Keycode::LShift, Keycode::RShift),
+            alt: held(Keycode::LAlt, Keycode::RAlt),
+        }
+    }
 }
 
 impl Observable<'_> for InputHandler {
@@ -112,4 +231,4 @@ impl Observable<'_> for InputHandler {
             observer.borrow_mut().notify(&event)
         }
     }
-}
\ No newline at end of file
+}
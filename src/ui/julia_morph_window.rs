@@ -0,0 +1,292 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use imgui::{Drag, Ui};
+use crate::julia_path::JuliaPath;
+use crate::renderer::FractalRenderer;
+use crate::renderer::frame_export::{FrameExport, FrameOutput};
+use crate::renderer::image_export::ExportJob;
+use crate::scene::{FractalKind, Scene};
+use crate::ui::event_observer::{Observable, Observer, ObserverEvent};
+use crate::ui::export_runner::ExportRunner;
+
+/// Panel for morphing Julia sets by moving the julia constant along a closed path
+/// the path is drawn over the Mandelbrot set, playing it shows the Julia sets in the main view
+pub struct JuliaMorphWindow {
+    path: JuliaPath,
+    /// position on the path, from 0 to 1
+    phase: f32,
+    /// seconds for one round along the path
+    duration: f64,
+    playing: bool,
+    recording: bool,
+    /// the view the Julia sets are shown with, updated while drawing the panel
+    base_scene: Scene,
+
+    width: i32,
+    height: i32,
+    fps: i32,
+    output: usize,
+    output_paths: [String; 3],
+    runner: ExportRunner,
+
+    observers: Vec<Rc<RefCell<dyn Observer>>>,
+}
+
+impl Default for JuliaMorphWindow {
+    fn default() -> Self {
+        Self {
+            path: JuliaPath::MainCardioid { scale: 0.98 },
+            phase: 0.0,
+            duration: 10.0,
+            playing: false,
+            recording: false,
+            base_scene: Scene::default(),
+
+            width: 640,
+            height: 640,
+            fps: 25,
+            output: 2,
+            output_paths: [String::from("julia_frames"), String::from("julia_morph.mp4"), String::from("julia_morph.gif")],
+            runner: ExportRunner::default(),
+
+            observers: Vec::new(),
+        }
+    }
+}
+
+impl JuliaMorphWindow {
+    const OUTPUTS: [&'static str; 3] = ["PNG frames", "Video (ffmpeg)", "Animated GIF"];
+    /// recorded trail points are at least this many window pixels apart
+    const TRAIL_SPACING: f64 = 4.0;
+
+    /// advance the preview by `dt` seconds while it is playing, the preview loops until paused
+    pub fn update(&mut self, dt: f64) {
+        if !self.playing {
+            return;
+        }
+
+        self.phase = (self.phase + (dt / self.duration) as f32).rem_euclid(1.0);
+        self.show_phase();
+    }
+
+    /// continue rendering frames, called once per frame before drawing into the window
+    pub fn update_export(&mut self, renderer: &FractalRenderer) {
+        self.runner.update(renderer);
+    }
+
+    /// draw the panel and the path over the Mandelbrot set, `scene` is the current view
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool, scene: &Scene) {
+        self.base_scene = *scene;
+        if scene.fractal == FractalKind::Mandelbrot {
+            self.record_trail(ui, scene);
+            self.draw_path(ui, scene);
+        }
+
+        ui.window("Julia morph")
+            .size([380.0, 460.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                self.draw_path_combo(ui);
+                self.draw_path_parameters(ui, scene);
+                ui.separator();
+                self.draw_playback(ui);
+                ui.separator();
+                self.draw_export(ui);
+            });
+
+        if !*opened {
+            self.playing = false;
+            self.recording = false;
+        }
+    }
+
+    fn draw_path_combo(&mut self, ui: &Ui) {
+        ui.set_next_item_width(-1.0);
+        if let Some(_combo) = ui.begin_combo("##path", self.path.name()) {
+            for path in JuliaPath::defaults() {
+                if ui.selectable_config(path.name()).selected(self.path.name() == path.name()).build() && self.path.name() != path.name() {
+                    self.path = path;
+                    self.recording = false;
+                }
+            }
+        }
+    }
+
+    /// draw the editors for the parameters of the current kind of path
+    fn draw_path_parameters(&mut self, ui: &Ui, scene: &Scene) {
+        let _item_width_stack_token = ui.push_item_width(-80.0);
+        match &mut self.path {
+            JuliaPath::Circle { center, radius } => {
+                Drag::new("Center").speed(0.001).build_array(ui, center);
+                Drag::new("Radius").range(0.0, 4.0).speed(0.001).build(ui, radius);
+            },
+            JuliaPath::Line { from, to } => {
+                Drag::new("From").speed(0.001).build_array(ui, from);
+                Drag::new("To").speed(0.001).build_array(ui, to);
+            },
+            JuliaPath::MainCardioid { scale } | JuliaPath::PeriodTwoBulb { scale } => {
+                ui.slider_config("Scale", 0.5, 1.2).display_format("%.3f").build(scale);
+                ui.text_disabled("1.0 follows the boundary of the Mandelbrot set");
+            },
+            JuliaPath::Spline { points } => {
+                let mut removed = None;
+                for (index, point) in points.iter_mut().enumerate() {
+                    let _id = ui.push_id_usize(index);
+                    Drag::new(format!("Point {}", index + 1)).speed(0.001).build_array(ui, point);
+                    ui.same_line();
+                    if ui.small_button("Delete") {
+                        removed = Some(index);
+                    }
+                }
+                if let Some(index) = removed {
+                    points.remove(index);
+                }
+                if ui.button("Add julia constant") {
+                    points.push(scene.julia_constant);
+                }
+                ui.same_line();
+                ui.text_disabled("of the current view");
+            },
+            JuliaPath::Trail { points } => {
+                if ui.button(if self.recording { "Stop recording" } else { "Record" }) {
+                    self.recording = !self.recording;
+                }
+                ui.same_line();
+                if ui.button("Clear") {
+                    points.clear();
+                }
+                ui.same_line();
+                ui.text(format!("{} points", points.len()));
+                ui.text_disabled("move the mouse over the Mandelbrot set while recording");
+            },
+        }
+    }
+
+    fn draw_playback(&mut self, ui: &Ui) {
+        if ui.button(if self.playing { "Pause" } else { "Play" }) {
+            self.playing = !self.playing && self.path.point(0.0).is_some();
+        }
+        ui.same_line();
+        ui.set_next_item_width(-1.0);
+        if ui.slider_config("##phase", 0.0, 1.0).display_format("%.3f").build(&mut self.phase) {
+            self.playing = false;
+            self.show_phase();
+        }
+
+        let _item_width_stack_token = ui.push_item_width(-120.0);
+        Drag::new("Loop duration").range(0.5, 600.0).speed(0.1).display_format("%.1f s").build(ui, &mut self.duration);
+    }
+
+    fn draw_export(&mut self, ui: &Ui) {
+        if self.runner.is_running() {
+            self.runner.draw_progress(ui);
+            return;
+        }
+
+        let _item_width_stack_token = ui.push_item_width(-120.0);
+        Drag::new("Width").range(2, 8192).build(ui, &mut self.width);
+        Drag::new("Height").range(2, 8192).build(ui, &mut self.height);
+        ui.slider("Frames per second", 1, 60, &mut self.fps);
+        if let Some(_combo) = ui.begin_combo("Output", Self::OUTPUTS[self.output]) {
+            for (index, name) in Self::OUTPUTS.iter().enumerate() {
+                if ui.selectable_config(name).selected(self.output == index).build() {
+                    self.output = index;
+                }
+            }
+        }
+        ui.input_text(if self.output == 0 { "Frame directory" } else { "File" }, &mut self.output_paths[self.output]).build();
+
+        let frames = self.frames();
+        if ui.button(format!("Render {} frames", frames.len())) {
+            let path = PathBuf::from(&self.output_paths[self.output]);
+            let output = match self.output {
+                0 => FrameOutput::Png(path),
+                1 => FrameOutput::Ffmpeg(path),
+                _ => FrameOutput::Gif(path),
+            };
+            let export = FrameExport::new(frames, self.width, self.height, self.fps as f64, output);
+            self.runner.start(export.map(|export| Box::new(export) as Box<dyn ExportJob>));
+        }
+        self.runner.draw_status(ui);
+    }
+
+    /// draw the path over the Mandelbrot set with a marker at the current position
+    fn draw_path(&self, ui: &Ui, scene: &Scene) {
+        let [width, height] = ui.io().display_size;
+        let to_screen = |point: [f32; 2]| {
+            let [x, y] = scene.camera.complex_to_screen([point[0] as f64, point[1] as f64], width as f64, height as f64);
+            [x as f32, y as f32]
+        };
+
+        let draw_list = ui.get_background_draw_list();
+        let outline: Vec<[f32; 2]> = self.path.outline(512).into_iter().map(to_screen).collect();
+        draw_list.add_polyline(outline, [1.0, 1.0, 1.0, 0.8]).thickness(1.5).build();
+
+        if let JuliaPath::Spline { points } = &self.path {
+            for point in points {
+                draw_list.add_circle(to_screen(*point), 4.0, [1.0, 1.0, 0.0, 1.0]).build();
+            }
+        }
+        if let Some(point) = self.path.point(self.phase) {
+            draw_list.add_circle(to_screen(point), 5.0, [1.0, 0.3, 0.2, 1.0]).filled(true).build();
+        }
+    }
+
+    /// add the point under the mouse to the recorded trail
+    fn record_trail(&mut self, ui: &Ui, scene: &Scene) {
+        let JuliaPath::Trail { points } = &mut self.path else { return };
+        if !self.recording || ui.io().want_capture_mouse {
+            return;
+        }
+
+        let [width, height] = ui.io().display_size.map(|size| size as f64);
+        let [x, y] = ui.io().mouse_pos.map(|position| position as f64);
+        if !(0.0..width).contains(&x) || !(0.0..height).contains(&y) {
+            return;
+        }
+
+        let point = scene.camera.screen_to_complex(x, y, width, height);
+        let spacing = Self::TRAIL_SPACING * scene.camera.width / scene.camera.zoom / width;
+        let far_enough = points.last().is_none_or(|last| {
+            (point[0] - last[0] as f64).hypot(point[1] - last[1] as f64) >= spacing
+        });
+        if far_enough {
+            points.push([point[0] as f32, point[1] as f32]);
+        }
+    }
+
+    /// scenes of all frames of one round along the path, the last frame leads back into the first
+    fn frames(&self) -> Vec<Scene> {
+        let count = (self.duration * self.fps as f64).round().max(1.0) as usize;
+        (0..count).filter_map(|frame| self.scene_at(frame as f32 / count as f32)).collect()
+    }
+
+    /// the current view showing the Julia set of the point at `phase` on the path
+    fn scene_at(&self, phase: f32) -> Option<Scene> {
+        self.path.point(phase).map(|point| Scene {
+            fractal: FractalKind::Julia,
+            julia_constant: point,
+            ..self.base_scene
+        })
+    }
+
+    /// show the Julia set at the current position in the main view
+    fn show_phase(&mut self) {
+        if let Some(scene) = self.scene_at(self.phase) {
+            self.notify_observers(ObserverEvent::RestoreScene(scene));
+        }
+    }
+}
+
+impl Observable<'_> for JuliaMorphWindow {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer>>) {
+        self.observers.push(observer)
+    }
+
+    fn notify_observers(&mut self, event: ObserverEvent) {
+        for observer in self.observers.iter() {
+            observer.borrow_mut().notify(&event)
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use std::fmt;
+use std::path::Path;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use crate::resources::{self, Resources};
+
+/// Every action that can be bound to a key or a mouse button
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    RotateLeft,
+    RotateRight,
+    DragPan,
+    DragRotate,
+    SelectionZoom,
+    ResetView,
+    Screenshot,
+    ToggleUi,
+    NextFractal,
+    Undo,
+    Redo,
+    CopyLocation,
+    PasteLocation,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 20] = [
+        Action::PanUp, Action::PanDown, Action::PanLeft, Action::PanRight,
+        Action::ZoomIn, Action::ZoomOut, Action::RotateLeft, Action::RotateRight,
+        Action::DragPan, Action::DragRotate, Action::SelectionZoom,
+        Action::ResetView, Action::Screenshot, Action::ToggleUi, Action::NextFractal,
+        Action::Undo, Action::Redo, Action::CopyLocation, Action::PasteLocation, Action::Quit,
+    ];
+
+    /// name of the action used in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::PanUp => "pan_up",
+            Action::PanDown => "pan_down",
+            Action::PanLeft => "pan_left",
+            Action::PanRight => "pan_right",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::RotateLeft => "rotate_left",
+            Action::RotateRight => "rotate_right",
+            Action::DragPan => "drag_pan",
+            Action::DragRotate => "drag_rotate",
+            Action::SelectionZoom => "selection_zoom",
+            Action::ResetView => "reset_view",
+            Action::Screenshot => "screenshot",
+            Action::ToggleUi => "toggle_ui",
+            Action::NextFractal => "next_fractal",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::CopyLocation => "copy_location",
+            Action::PasteLocation => "paste_location",
+            Action::Quit => "quit",
+        }
+    }
+
+    /// human readable name of the action used in the rebinding panel
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::PanUp => "Pan up",
+            Action::PanDown => "Pan down",
+            Action::PanLeft => "Pan left",
+            Action::PanRight => "Pan right",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::RotateLeft => "Rotate left",
+            Action::RotateRight => "Rotate right",
+            Action::DragPan => "Drag to pan",
+            Action::DragRotate => "Drag to rotate",
+            Action::SelectionZoom => "Drag to zoom into region",
+            Action::ResetView => "Reset view",
+            Action::Screenshot => "Screenshot",
+            Action::ToggleUi => "Toggle UI",
+            Action::NextFractal => "Next fractal",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::CopyLocation => "Copy location code",
+            Action::PasteLocation => "Paste location code",
+            Action::Quit => "Quit",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// Key or mouse button that activates a binding
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Trigger {
+    Key(Keycode),
+    Mouse(MouseButton),
+}
+
+impl Trigger {
+    const MOUSE_BUTTONS: [(MouseButton, &'static str); 5] = [
+        (MouseButton::Left, "Mouse Left"),
+        (MouseButton::Right, "Mouse Right"),
+        (MouseButton::Middle, "Mouse Middle"),
+        (MouseButton::X1, "Mouse X1"),
+        (MouseButton::X2, "Mouse X2"),
+    ];
+
+    /// modifier keys are never triggers on their own, they are part of [`Modifiers`]
+    pub fn is_modifier(&self) -> bool {
+        matches!(self, Trigger::Key(
+            Keycode::LCtrl | Keycode::RCtrl | Keycode::LShift | Keycode::RShift | Keycode::LAlt | Keycode::RAlt
+        ))
+    }
+
+    fn parse(name: &str) -> Option<Trigger> {
+        if let Some((button, _)) = Self::MOUSE_BUTTONS.iter().find(|(_, button_name)| button_name.eq_ignore_ascii_case(name)) {
+            return Some(Trigger::Mouse(*button));
+        }
+        Keycode::from_name(name).map(Trigger::Key)
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trigger::Key(keycode) => write!(f, "{}", keycode.name()),
+            Trigger::Mouse(button) => {
+                let name = Self::MOUSE_BUTTONS.iter()
+                    .find(|(other, _)| other == button)
+                    .map_or("Mouse Unknown", |(_, name)| name);
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+/// Modifier keys that have to be held together with the trigger
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+/// A single key or mouse binding of an action
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Binding {
+    pub trigger: Trigger,
+    pub modifiers: Modifiers,
+}
+
+impl Binding {
+    fn new(trigger: Trigger) -> Self {
+        Self { trigger, modifiers: Modifiers::default() }
+    }
+
+    fn with_shift(trigger: Trigger) -> Self {
+        Self { trigger, modifiers: Modifiers { shift: true, ..Modifiers::default() } }
+    }
+
+    fn with_ctrl(trigger: Trigger) -> Self {
+        Self { trigger, modifiers: Modifiers { ctrl: true, ..Modifiers::default() } }
+    }
+
+    /// parse a binding in the form of `Ctrl+Shift+S` or `Alt+Mouse Left`
+    fn parse(text: &str) -> Option<Binding> {
+        let mut modifiers = Modifiers::default();
+        let mut rest = text.trim();
+
+        // strip the modifier prefixes, whatever remains is the name of the trigger
+        // so key names containing a `+` such as `Keypad +` still work
+        loop {
+            let lowercase = rest.to_ascii_lowercase();
+            if lowercase.starts_with("ctrl+") {
+                modifiers.ctrl = true;
+                rest = rest["ctrl+".len()..].trim_start();
+            } else if lowercase.starts_with("shift+") {
+                modifiers.shift = true;
+                rest = rest["shift+".len()..].trim_start();
+            } else if lowercase.starts_with("alt+") {
+                modifiers.alt = true;
+                rest = rest["alt+".len()..].trim_start();
+            } else {
+                break;
+            }
+        }
+
+        let trigger = Trigger::parse(rest)?;
+        if trigger.is_modifier() {
+            return None;
+        }
+
+        Some(Binding { trigger, modifiers })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl { write!(f, "Ctrl+")?; }
+        if self.modifiers.shift { write!(f, "Shift+")?; }
+        if self.modifiers.alt { write!(f, "Alt+")?; }
+        write!(f, "{}", self.trigger)
+    }
+}
+
+/// Map from actions to their key and mouse bindings
+/// loaded from and saved to a plain text config file with one `action = binding` line per binding
+pub struct KeyBindings {
+    bindings: Vec<(Action, Binding)>,
+    /// action waiting for the next key or mouse press to be bound to it
+    pub capturing: Option<Action>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        use Keycode as K;
+        use Trigger::{Key, Mouse};
+
+        let bindings = vec![
+            (Action::PanUp, Binding::new(Key(K::W))),
+            (Action::PanUp, Binding::new(Key(K::Up))),
+            (Action::PanDown, Binding::new(Key(K::S))),
+            (Action::PanDown, Binding::new(Key(K::Down))),
+            (Action::PanLeft, Binding::new(Key(K::A))),
+            (Action::PanLeft, Binding::new(Key(K::Left))),
+            (Action::PanRight, Binding::new(Key(K::D))),
+            (Action::PanRight, Binding::new(Key(K::Right))),
+            (Action::ZoomIn, Binding::new(Key(K::Equals))),
+            (Action::ZoomIn, Binding::new(Key(K::KpPlus))),
+            (Action::ZoomOut, Binding::new(Key(K::Minus))),
+            (Action::ZoomOut, Binding::new(Key(K::KpMinus))),
+            (Action::RotateLeft, Binding::new(Key(K::Q))),
+            (Action::RotateRight, Binding::new(Key(K::E))),
+            (Action::DragPan, Binding::new(Mouse(MouseButton::Left))),
+            (Action::DragRotate, Binding::with_shift(Mouse(MouseButton::Left))),
+            (Action::SelectionZoom, Binding::new(Mouse(MouseButton::Right))),
+            (Action::ResetView, Binding::new(Key(K::Home))),
+            (Action::Screenshot, Binding::new(Key(K::F12))),
+            (Action::ToggleUi, Binding::new(Key(K::Tab))),
+            (Action::NextFractal, Binding::new(Key(K::N))),
+            (Action::Undo, Binding::with_ctrl(Key(K::Z))),
+            (Action::Redo, Binding::with_ctrl(Key(K::Y))),
+            (Action::Redo, Binding { trigger: Key(K::Z), modifiers: Modifiers { ctrl: true, shift: true, alt: false } }),
+            (Action::CopyLocation, Binding::with_ctrl(Key(K::C))),
+            (Action::PasteLocation, Binding::with_ctrl(Key(K::V))),
+            (Action::Quit, Binding::new(Key(K::Escape))),
+        ];
+
+        Self { bindings, capturing: None }
+    }
+}
+
+impl KeyBindings {
+    /// name of the config file stored next to the executable
+    pub const CONFIG_FILE: &'static str = "bindings.cfg";
+
+    /// load the bindings from the config file next to the executable
+    /// falls back to the default bindings if the file does not exist or cannot be read
+    pub fn load() -> Self {
+        let config = Resources::from_relative_exe_path(Path::new(""))
+            .and_then(|resources| resources.load_string(Self::CONFIG_FILE));
+
+        match config {
+            Ok(config) => Self::from_config(&config),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// save the bindings to the config file next to the executable
+    pub fn save(&self) -> Result<(), resources::Error> {
+        Resources::from_relative_exe_path(Path::new(""))?
+            .save_string(Self::CONFIG_FILE, &self.to_config())
+    }
+
+    /// parse bindings from the config file format, invalid lines are reported and skipped
+    /// actions missing from the config keep their default bindings
+    pub fn from_config(config: &str) -> Self {
+        let mut bindings = Vec::new();
+
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parsed = line.split_once('=').and_then(|(action, binding)| {
+                Some((Action::from_name(action.trim())?, Binding::parse(binding.trim())?))
+            });
+
+            match parsed {
+                // a binding listed twice would trigger its action twice
+                Some(entry) if bindings.contains(&entry) => {},
+                Some(entry) => bindings.push(entry),
+                None => eprintln!("Invalid binding on line {} of {}: {}", number + 1, Self::CONFIG_FILE, line),
+            }
+        }
+
+        let defaults = Self::default();
+        for action in Action::ALL {
+            if !bindings.iter().any(|(other, _)| *other == action) {
+                bindings.extend(defaults.bindings.iter().filter(|(other, _)| *other == action));
+            }
+        }
+
+        Self { bindings, capturing: None }
+    }
+
+    /// serialize the bindings to the config file format
+    pub fn to_config(&self) -> String {
+        let mut config = String::from("# action = [Ctrl+][Shift+][Alt+]key or mouse button\n");
+        for action in Action::ALL {
+            for binding in self.bindings_for(action) {
+                config.push_str(&format!("{} = {}\n", action.name(), binding));
+            }
+        }
+        config
+    }
+
+    /// all bindings of the given action
+    pub fn bindings_for(&self, action: Action) -> impl Iterator<Item = &Binding> {
+        self.bindings.iter().filter(move |(other, _)| *other == action).map(|(_, binding)| binding)
+    }
+
+    /// actions bound to exactly this trigger and modifier combination
+    pub fn actions_for(&self, binding: &Binding) -> impl Iterator<Item = Action> + '_ {
+        let binding = *binding;
+        self.bindings.iter().filter(move |(_, other)| *other == binding).map(|(action, _)| *action)
+    }
+
+    /// actions using the given trigger with any modifiers
+    pub fn actions_for_trigger(&self, trigger: Trigger) -> impl Iterator<Item = Action> + '_ {
+        self.bindings.iter().filter(move |(_, other)| other.trigger == trigger).map(|(action, _)| *action)
+    }
+
+    /// add a binding to an action, the same combination is removed from any other action
+    pub fn add_binding(&mut self, action: Action, binding: Binding) {
+        self.bindings.retain(|(_, other)| *other != binding);
+        self.bindings.push((action, binding));
+    }
+
+    /// remove all bindings of an action
+    pub fn clear_bindings(&mut self, action: Action) {
+        self.bindings.retain(|(other, _)| *other != action);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifier_combinations() {
+        let binding = Binding::parse("Ctrl+Shift+S").unwrap();
+        assert_eq!(binding.trigger, Trigger::Key(Keycode::S));
+        assert_eq!(binding.modifiers, Modifiers { ctrl: true, shift: true, alt: false });
+
+        let binding = Binding::parse(" alt+ mouse left ").unwrap();
+        assert_eq!(binding.trigger, Trigger::Mouse(MouseButton::Left));
+        assert_eq!(binding.modifiers, Modifiers { ctrl: false, shift: false, alt: true });
+
+        // the `+` of the key name is not taken for a separator
+        let binding = Binding::parse("Ctrl+Keypad +").unwrap();
+        assert_eq!(binding.trigger, Trigger::Key(Keycode::KpPlus));
+        assert_eq!(binding.modifiers, Modifiers { ctrl: true, ..Modifiers::default() });
+
+        let binding = Binding::parse("Escape").unwrap();
+        assert_eq!(binding, Binding::new(Trigger::Key(Keycode::Escape)));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert_eq!(Binding::parse("Ctrl+Nonsense"), None);
+        assert_eq!(Binding::parse("Mouse Nowhere"), None);
+        assert_eq!(Binding::parse("Ctrl+"), None);
+        assert_eq!(Binding::parse(""), None);
+        // modifiers are only valid as prefixes
+        assert_eq!(Binding::parse("Shift+Left Ctrl"), None);
+    }
+
+    #[test]
+    fn skips_malformed_lines() {
+        let bindings = KeyBindings::from_config("\
+            # comment\n\
+            pan_up W\n\
+            no_such_action = Q\n\
+            zoom_in = No Such Key\n\
+            pan_down = Shift+Down\n");
+
+        // actions without a valid line keep their defaults
+        let defaults = KeyBindings::default();
+        assert!(bindings.bindings_for(Action::PanUp).eq(defaults.bindings_for(Action::PanUp)));
+        assert!(bindings.bindings_for(Action::ZoomIn).eq(defaults.bindings_for(Action::ZoomIn)));
+        let pan_down: Vec<&Binding> = bindings.bindings_for(Action::PanDown).collect();
+        assert_eq!(pan_down, [&Binding::with_shift(Trigger::Key(Keycode::Down))]);
+    }
+
+    #[test]
+    fn keeps_duplicate_bindings_once() {
+        let bindings = KeyBindings::from_config("undo = Ctrl+Z\nundo = ctrl+z\nredo = Ctrl+Z\n");
+        assert_eq!(bindings.bindings_for(Action::Undo).count(), 1);
+        let undo = Binding::with_ctrl(Trigger::Key(Keycode::Z));
+        assert_eq!(bindings.actions_for(&undo).collect::<Vec<_>>(), [Action::Undo, Action::Redo]);
+    }
+
+    #[test]
+    fn config_round_trip() {
+        let mut bindings = KeyBindings::default();
+        bindings.add_binding(Action::Screenshot, Binding { trigger: Trigger::Mouse(MouseButton::X1), modifiers: Modifiers { ctrl: true, shift: false, alt: true } });
+        let parsed = KeyBindings::from_config(&bindings.to_config());
+        for action in Action::ALL {
+            assert!(parsed.bindings_for(action).eq(bindings.bindings_for(action)), "{:?}", action);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use imgui::{TableColumnSetup, TableFlags, Ui};
+use crate::ui::key_bindings::{Action, KeyBindings};
+
+/// Panel listing all actions with their bindings
+/// a binding is added by pressing "Add" and then the key or mouse button (with modifiers) to bind
+pub struct KeyBindingsWindow {
+    bindings: Rc<RefCell<KeyBindings>>,
+    status: String,
+}
+
+impl KeyBindingsWindow {
+    pub fn new(bindings: Rc<RefCell<KeyBindings>>) -> Self {
+        Self {
+            bindings,
+            status: String::new(),
+        }
+    }
+
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool) {
+        ui.window("Key bindings")
+            .size([420.0, 480.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                ui.text_wrapped("Press \"Add\" and then the key or mouse button to bind, Escape cancels.");
+
+                let flags = TableFlags::BORDERS | TableFlags::ROW_BG | TableFlags::SIZING_STRETCH_PROP;
+                if let Some(_table) = ui.begin_table_header_with_flags("##bindings", [
+                    TableColumnSetup::new("Action"),
+                    TableColumnSetup::new("Bindings"),
+                    TableColumnSetup::new(""),
+                ], flags) {
+                    for action in Action::ALL {
+                        self.draw_action_row(ui, action);
+                    }
+                }
+
+                if ui.button("Save") {
+                    self.status = match self.bindings.borrow().save() {
+                        Ok(()) => format!("Saved to {}", KeyBindings::CONFIG_FILE),
+                        Err(err) => format!("Failed to save: {:?}", err),
+                    };
+                }
+                ui.same_line();
+                if ui.button("Restore defaults") {
+                    *self.bindings.borrow_mut() = KeyBindings::default();
+                    self.status.clear();
+                }
+                ui.text(&self.status);
+            });
+    }
+
+    fn draw_action_row(&mut self, ui: &Ui, action: Action) {
+        let _id = ui.push_id(action.name());
+        let mut bindings = self.bindings.borrow_mut();
+
+        ui.table_next_row();
+        ui.table_next_column();
+        ui.text(action.label());
+
+        ui.table_next_column();
+        if bindings.capturing == Some(action) {
+            ui.text_disabled("press a key or mouse button...");
+        } else {
+            let text: Vec<String> = bindings.bindings_for(action).map(|binding| binding.to_string()).collect();
+            ui.text_wrapped(text.join(", "));
+        }
+
+        ui.table_next_column();
+        if ui.small_button("Add") {
+            bindings.capturing = Some(action);
+        }
+        ui.same_line();
+        if ui.small_button("Clear") {
+            bindings.clear_bindings(action);
+        }
+    }
+}
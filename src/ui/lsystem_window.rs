@@ -0,0 +1,156 @@
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+use imgui::{Drag, Ui};
+use crate::lsystem::{self, LSystem};
+use crate::renderer::{Camera, LineRenderer};
+use crate::scene::Scene;
+use crate::ui::event_observer::{Observable, Observer, ObserverEvent};
+
+/// Panel editing Lindenmayer systems, which are drawn as lines in the main view instead of the fractal
+/// the lines are panned and zoomed with the camera of the scene
+pub struct LSystemWindow {
+    system: LSystem,
+    /// rules being edited, kept apart from the system so they can be invalid while typing
+    rules_text: String,
+    rules_error: Option<String>,
+    /// whether the main view shows the lines while the window is open
+    show: bool,
+    line_color: [f32; 3],
+    svg_path: String,
+
+    /// system the lines were last generated from
+    generated: Option<LSystem>,
+    segments: Vec<[[f64; 2]; 2]>,
+    error: Option<String>,
+    status: String,
+    observers: Vec<Rc<RefCell<dyn Observer>>>,
+}
+
+impl Default for LSystemWindow {
+    fn default() -> Self {
+        let [system, ..] = LSystem::presets();
+        Self {
+            rules_text: system.rules_text(),
+            rules_error: None,
+            system,
+            show: true,
+            line_color: [1.0, 1.0, 1.0],
+            svg_path: String::from("lsystem.svg"),
+
+            generated: None,
+            segments: Vec::new(),
+            error: None,
+            status: String::new(),
+            observers: Vec::new(),
+        }
+    }
+}
+
+impl LSystemWindow {
+    /// longer side of exported SVG images in pixels
+    const SVG_SIZE: f64 = 1000.0;
+
+    /// whether the main view shows the lines instead of the fractal
+    pub fn shows_lines(&self) -> bool {
+        self.show
+    }
+
+    pub fn line_color(&self) -> [f32; 3] {
+        self.line_color
+    }
+
+    /// generate the lines again if the system changed, called once per frame while the window is open
+    pub fn update(&mut self, renderer: &mut LineRenderer) {
+        if self.generated.as_ref() == Some(&self.system) {
+            return;
+        }
+
+        match self.system.segments() {
+            Ok(segments) => {
+                self.segments = segments;
+                self.error = None;
+            },
+            Err(message) => {
+                self.segments.clear();
+                self.error = Some(message);
+            },
+        }
+        renderer.set_lines(&self.segments);
+        self.generated = Some(self.system.clone());
+    }
+
+    /// draw the panel, `scene` is the current view which is kept when fitting the camera to the lines
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool, scene: &Scene) {
+        ui.window("L-systems")
+            .size([380.0, 520.0], imgui::Condition::FirstUseEver)
+            .opened(opened)
+            .build(|| {
+                for (index, preset) in LSystem::presets().into_iter().enumerate() {
+                    if index % 3 != 0 {
+                        ui.same_line();
+                    }
+                    if ui.button(&preset.name) {
+                        self.rules_text = preset.rules_text();
+                        self.rules_error = None;
+                        self.system = preset;
+                    }
+                }
+                ui.separator();
+
+                let _item_width_stack_token = ui.push_item_width(-90.0);
+                ui.input_text("Axiom", &mut self.system.axiom).build();
+                if ui.input_text_multiline("Rules", &mut self.rules_text, [0.0, 80.0]).build() {
+                    match LSystem::parse_rules(&self.rules_text) {
+                        Ok(rules) => {
+                            self.system.rules = rules;
+                            self.rules_error = None;
+                        },
+                        Err(message) => self.rules_error = Some(message),
+                    }
+                }
+                Drag::new("Angle").range(-360.0, 360.0).speed(0.1).display_format("%.2f deg").build(ui, &mut self.system.angle);
+                Drag::new("Heading").range(-360.0, 360.0).speed(0.5).display_format("%.2f deg").build(ui, &mut self.system.heading);
+                ui.slider("Iterations", 0, 16, &mut self.system.iterations);
+                ui.text_disabled("F and G draw, f moves, + and - turn, | turns around, [ and ] push and pop");
+
+                if let Some(message) = &self.rules_error {
+                    ui.text_colored([1.0, 0.4, 0.4, 1.0], message);
+                }
+                match &self.error {
+                    Some(message) => ui.text_colored([1.0, 0.4, 0.4, 1.0], message),
+                    None => ui.text(format!("{} lines", self.segments.len())),
+                }
+                ui.separator();
+
+                ui.checkbox("Show in main view", &mut self.show);
+                ui.color_edit3("Line color", &mut self.line_color);
+                if ui.button("Fit view") {
+                    // the lines are scaled to fit the default camera
+                    self.notify_observers(ObserverEvent::RestoreScene(Scene { camera: Camera::default(), ..*scene }));
+                }
+
+                ui.input_text("SVG file", &mut self.svg_path).build();
+                if ui.button("Export SVG") {
+                    let svg = lsystem::to_svg(&self.segments, Self::SVG_SIZE, self.line_color);
+                    self.status = match fs::write(&self.svg_path, svg) {
+                        Ok(()) => format!("Saved {}", self.svg_path),
+                        Err(err) => format!("Saving failed: {}", err),
+                    };
+                }
+                ui.text_wrapped(&self.status);
+            });
+    }
+}
+
+impl Observable<'_> for LSystemWindow {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer>>) {
+        self.observers.push(observer)
+    }
+
+    fn notify_observers(&mut self, event: ObserverEvent) {
+        for observer in self.observers.iter() {
+            observer.borrow_mut().notify(&event)
+        }
+    }
+}
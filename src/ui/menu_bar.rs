@@ -0,0 +1,77 @@
+use imgui::Ui;
+use crate::ui::event_observer::{Observer, ObserverEvent};
+
+/// Main menu bar of the application
+/// keeps track of which panels are open and whether the UI is shown at all
+pub struct MenuBar {
+    pub ui_visible: bool,
+    pub key_bindings_open: bool,
+    pub history_open: bool,
+    pub bookmarks_open: bool,
+    pub export_open: bool,
+    pub animation_open: bool,
+    pub julia_morph_open: bool,
+    pub buddhabrot_open: bool,
+    pub flames_open: bool,
+    pub lsystem_open: bool,
+    pub fractal_3d_open: bool,
+    pub height_field_open: bool,
+    pub explorer_open: bool,
+    pub orbit_overlay: bool,
+    pub minimap_open: bool,
+}
+
+impl Default for MenuBar {
+    fn default() -> Self {
+        Self {
+            ui_visible: true,
+            key_bindings_open: false,
+            history_open: false,
+            bookmarks_open: false,
+            export_open: false,
+            animation_open: false,
+            julia_morph_open: false,
+            buddhabrot_open: false,
+            flames_open: false,
+            lsystem_open: false,
+            fractal_3d_open: false,
+            height_field_open: false,
+            explorer_open: false,
+            orbit_overlay: false,
+            minimap_open: false,
+        }
+    }
+}
+
+impl MenuBar {
+    pub fn draw(&mut self, ui: &Ui) {
+        if let Some(_menu_bar) = ui.begin_main_menu_bar() {
+            if let Some(_menu) = ui.begin_menu("Windows") {
+                ui.menu_item_config("Bookmarks").build_with_ref(&mut self.bookmarks_open);
+                ui.menu_item_config("History").build_with_ref(&mut self.history_open);
+                ui.menu_item_config("Key bindings").build_with_ref(&mut self.key_bindings_open);
+                ui.menu_item_config("Export").build_with_ref(&mut self.export_open);
+                ui.menu_item_config("Animation").build_with_ref(&mut self.animation_open);
+                ui.menu_item_config("Julia morph").build_with_ref(&mut self.julia_morph_open);
+                ui.menu_item_config("Buddhabrot").build_with_ref(&mut self.buddhabrot_open);
+                ui.menu_item_config("Flames").build_with_ref(&mut self.flames_open);
+                ui.menu_item_config("L-systems").build_with_ref(&mut self.lsystem_open);
+                ui.menu_item_config("3D fractals").build_with_ref(&mut self.fractal_3d_open);
+                ui.menu_item_config("Height field").build_with_ref(&mut self.height_field_open);
+                ui.menu_item_config("Auto explore").build_with_ref(&mut self.explorer_open);
+            }
+            if let Some(_menu) = ui.begin_menu("View") {
+                ui.menu_item_config("Orbit overlay").build_with_ref(&mut self.orbit_overlay);
+                ui.menu_item_config("Minimap").build_with_ref(&mut self.minimap_open);
+            }
+        }
+    }
+}
+
+impl Observer for MenuBar {
+    fn notify(&mut self, event: &ObserverEvent) {
+        if let ObserverEvent::ToggleUi = event {
+            self.ui_visible = !self.ui_visible;
+        }
+    }
+}
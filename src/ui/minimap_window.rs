@@ -0,0 +1,172 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use imgui::{TextureId, Ui};
+use crate::renderer::{Camera, FractalRenderer, RenderTarget};
+use crate::scene::{Formula, FractalKind, Scene};
+use crate::ui::event_observer::{Observable, Observer, ObserverEvent};
+
+/// Small overview of the whole fractal with a rectangle marking the current view
+/// dragging moves the view, scrolling zooms it and clicking outside the rectangle jumps there
+///
+/// once the view is too small to be seen on the overview, a breadcrumb of insets follows,
+/// each zoomed in further around the view and marking the next inset, so the view can always be found
+#[derive(Default)]
+pub struct MinimapWindow {
+    /// render target and the scene it was last rendered with, for the overview and every inset
+    insets: Vec<(RenderTarget, Scene)>,
+    observers: Vec<Rc<RefCell<dyn Observer>>>,
+}
+
+impl MinimapWindow {
+    /// size of the overview in pixels
+    const OVERVIEW_SIZE: i32 = 200;
+    /// size of the breadcrumb insets in pixels
+    const INSET_SIZE: i32 = 96;
+    /// zoom between two neighboring insets
+    const INSET_ZOOM: f64 = 32.0;
+    /// only the deepest insets are shown
+    const MAX_INSETS: usize = 3;
+    /// zoom factor for every step of the mouse wheel
+    const WHEEL_ZOOM: f64 = 1.2;
+
+    /// camera showing the whole fractal
+    fn overview_camera(scene: &Scene) -> Camera {
+        match (scene.fractal, scene.formula) {
+            (FractalKind::Mandelbrot | FractalKind::Julia, Formula::MagnetI | Formula::MagnetII) => scene.home_camera(),
+            (FractalKind::Mandelbrot, _) => Camera { focus: [-0.6, 0.0], width: 3.0, height: 3.0, ..Camera::default() },
+            (FractalKind::Julia, _) => Camera { focus: [0.0, 0.0], width: 3.5, height: 3.5, ..Camera::default() },
+            (FractalKind::Lyapunov, _) => Camera { focus: [2.0, 2.0], width: 4.0, height: 4.0, ..Camera::default() },
+        }
+    }
+
+    /// the scenes of the overview followed by the insets leading down to the view of `scene`
+    fn inset_scenes(scene: &Scene) -> Vec<Scene> {
+        let overview = Self::overview_camera(scene);
+        let view_width = scene.camera.width.min(scene.camera.height) / scene.camera.zoom;
+        let depth = ((overview.width / view_width).ln() / Self::INSET_ZOOM.ln()).floor().max(0.0) as i32;
+
+        let mut cameras = vec![overview];
+        for level in (1..=depth).skip((depth as usize).saturating_sub(Self::MAX_INSETS)) {
+            cameras.push(Camera { focus: scene.camera.focus, zoom: Self::INSET_ZOOM.powi(level), ..overview });
+        }
+        cameras.into_iter().map(|camera| Scene { camera, ..*scene }).collect()
+    }
+
+    /// render the overview and insets whose scene changed, called once per frame before drawing into the window
+    pub fn update(&mut self, renderer: &FractalRenderer, scene: &Scene) {
+        let scenes = Self::inset_scenes(scene);
+        self.insets.truncate(scenes.len());
+
+        for (index, inset_scene) in scenes.into_iter().enumerate() {
+            let size = if index == 0 { Self::OVERVIEW_SIZE } else { Self::INSET_SIZE };
+            if let Some((target, rendered)) = self.insets.get_mut(index) {
+                if *rendered != inset_scene {
+                    renderer.render_scene_to(&inset_scene, target);
+                    *rendered = inset_scene;
+                }
+                continue;
+            }
+
+            match RenderTarget::new(size, size) {
+                Ok(target) => {
+                    renderer.render_scene_to(&inset_scene, &target);
+                    self.insets.push((target, inset_scene));
+                },
+                Err(err) => {
+                    eprintln!("Error creating minimap: {:?}", err);
+                    return;
+                },
+            }
+        }
+    }
+
+    /// draw the minimap, `scene` is the current view
+    pub fn draw(&mut self, ui: &Ui, opened: &mut bool, scene: &Scene) {
+        let mut camera = scene.camera;
+
+        ui.window("Minimap")
+            .always_auto_resize(true)
+            .opened(opened)
+            .build(|| {
+                for index in 0..self.insets.len() {
+                    if index > 0 {
+                        ui.same_line();
+                    }
+                    let _id = ui.push_id_usize(index);
+                    let next = self.insets.get(index + 1).map(|(_, scene)| scene.camera);
+                    self.draw_inset(ui, index, next, &mut camera);
+                }
+            });
+
+        if camera != scene.camera {
+            self.notify_observers(ObserverEvent::RestoreScene(Scene { camera, ..*scene }));
+        }
+    }
+
+    /// draw one inset with the rectangle of the next inset, or of the view for the last one,
+    /// and move `camera` according to the mouse
+    fn draw_inset(&self, ui: &Ui, index: usize, next: Option<Camera>, camera: &mut Camera) {
+        let (target, inset_scene) = &self.insets[index];
+        let inset = &inset_scene.camera;
+        let size = [target.width() as f32, target.height() as f32];
+        let [width, height] = [size[0] as f64, size[1] as f64];
+
+        let min = ui.cursor_screen_pos();
+        ui.invisible_button("##inset", size);
+        let to_complex = |position: [f32; 2]| inset.screen_to_complex((position[0] - min[0]) as f64, (position[1] - min[1]) as f64, width, height);
+        let to_screen = |point: [f64; 2]| {
+            let [x, y] = inset.complex_to_screen(point, width, height);
+            [min[0] + x as f32, min[1] + y as f32]
+        };
+
+        let mouse = ui.io().mouse_pos;
+        if ui.is_item_clicked() {
+            // jump to points outside of the view, grabbing the view otherwise
+            let [u, v] = camera.complex_to_screen(to_complex(mouse), 1.0, 1.0);
+            if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+                camera.focus = to_complex(mouse);
+            }
+        } else if ui.is_item_active() {
+            let delta = ui.io().mouse_delta;
+            let from = to_complex([mouse[0] - delta[0], mouse[1] - delta[1]]);
+            let to = to_complex(mouse);
+            camera.focus = [camera.focus[0] + to[0] - from[0], camera.focus[1] + to[1] - from[1]];
+        }
+        if ui.is_item_hovered() && ui.io().mouse_wheel != 0.0 {
+            camera.zoom *= Self::WHEEL_ZOOM.powf(ui.io().mouse_wheel as f64);
+        }
+
+        let draw_list = ui.get_window_draw_list();
+        draw_list.add_image(TextureId::new(target.texture() as usize), min, [min[0] + size[0], min[1] + size[1]])
+            // the texture is stored bottom to top
+            .uv_min([0.0, 1.0])
+            .uv_max([1.0, 0.0])
+            .build();
+
+        let (marked, color) = match next {
+            Some(next) => (next, [1.0, 1.0, 0.0, 0.8]),
+            None => (*camera, [1.0, 1.0, 1.0, 1.0]),
+        };
+        let corners: Vec<[f32; 2]> = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0], [0.0, 0.0]]
+            .into_iter()
+            .map(|[u, v]| to_screen(marked.transform_point(u, v)))
+            .collect();
+        draw_list.add_polyline(corners, color).thickness(1.5).build();
+
+        // mark the center as well in case the rectangle is too small to be seen
+        let center = to_screen(marked.focus);
+        draw_list.add_circle(center, 3.0, color).build();
+    }
+}
+
+impl Observable<'_> for MinimapWindow {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer>>) {
+        self.observers.push(observer)
+    }
+
+    fn notify_observers(&mut self, event: ObserverEvent) {
+        for observer in self.observers.iter() {
+            observer.borrow_mut().notify(&event)
+        }
+    }
+}
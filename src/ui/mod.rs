@@ -2,3 +2,26 @@ pub mod properties_window;
 pub mod input_handler;
 pub mod event_observer;
 pub mod window;
+pub mod camera_controller;
+pub mod dual_view;
+pub mod key_bindings;
+pub mod key_bindings_window;
+pub mod menu_bar;
+pub mod history;
+pub mod history_window;
+pub mod bookmarks;
+pub mod bookmarks_window;
+pub mod export_window;
+pub mod export_runner;
+pub mod animation_window;
+pub mod julia_morph_window;
+pub mod orbit_overlay;
+pub mod status_bar;
+pub mod minimap_window;
+pub mod buddhabrot_window;
+pub mod flame_window;
+pub mod lsystem_window;
+pub mod fractal_3d_window;
+pub mod height_field_window;
+pub mod auto_iterations;
+pub mod explorer_window;
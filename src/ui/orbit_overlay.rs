@@ -0,0 +1,99 @@
+use imgui::Ui;
+use crate::orbit::Orbit;
+use crate::scene::Scene;
+use crate::ui::event_observer::{Observer, ObserverEvent};
+
+/// Overlay drawing the orbit of the point under the cursor as a line over the fractal
+/// clicking pins the orbit of the clicked point, clicking again follows the cursor again
+#[derive(Default)]
+pub struct OrbitOverlay {
+    /// point of the complex plane whose orbit stays shown
+    pinned: Option<[f64; 2]>,
+    /// window pixel of a click that still has to be turned into a point
+    clicked: Option<[f32; 2]>,
+    window_hovered: bool,
+}
+
+impl OrbitOverlay {
+    /// at most this many iterates are drawn, long bounded orbits would only repeat themselves
+    const MAX_DRAWN_POINTS: usize = 2000;
+
+    /// draw the orbit of the hovered or pinned point, `view_size` is the size of the main view in pixels
+    /// nothing is drawn and the pinned point is forgotten while the overlay is disabled
+    pub fn draw(&mut self, ui: &Ui, enabled: bool, scene: &Scene, view_size: [f64; 2]) {
+        let clicked = self.clicked.take();
+        self.window_hovered = ui.io().want_capture_mouse;
+        // the Lyapunov fractal has no orbits to show
+        if !enabled || !scene.fractal.is_complex() {
+            self.pinned = None;
+            return;
+        }
+
+        let [width, height] = view_size;
+        let to_complex = |[x, y]: [f32; 2]| scene.camera.screen_to_complex(x as f64, y as f64, width, height);
+        if let Some(position) = clicked {
+            self.pinned = match self.pinned {
+                Some(_) => None,
+                None => Some(to_complex(position)),
+            };
+        }
+
+        let mouse = ui.io().mouse_pos;
+        let hovering = !self.window_hovered && mouse[0] >= 0.0 && (mouse[0] as f64) < width && mouse[1] >= 0.0 && (mouse[1] as f64) < height;
+        let start = match self.pinned {
+            Some(point) => point,
+            None if hovering => to_complex(mouse),
+            None => return,
+        };
+
+        let orbit = Orbit::compute(scene, start);
+        self.draw_orbit(ui, &orbit, scene, view_size);
+        if hovering {
+            ui.tooltip(|| Self::draw_tooltip(ui, &orbit, start));
+        }
+    }
+
+    fn draw_orbit(&self, ui: &Ui, orbit: &Orbit, scene: &Scene, [width, height]: [f64; 2]) {
+        let to_screen = |point: &[f64; 2]| {
+            let [x, y] = scene.camera.complex_to_screen(*point, width, height);
+            [x as f32, y as f32]
+        };
+
+        let draw_list = ui.get_background_draw_list();
+        let points: Vec<[f32; 2]> = orbit.points.iter().take(Self::MAX_DRAWN_POINTS).map(to_screen).collect();
+        draw_list.add_polyline(points.clone(), [1.0, 1.0, 1.0, 0.8]).thickness(1.5).build();
+        for point in points.iter().take(64) {
+            draw_list.add_circle(*point, 2.5, [1.0, 1.0, 1.0, 0.8]).filled(true).build();
+        }
+
+        draw_list.add_circle(points[0], 5.0, [0.2, 1.0, 0.2, 1.0]).thickness(2.0).build();
+        for point in orbit.cycle() {
+            draw_list.add_circle(to_screen(point), 5.0, [1.0, 0.8, 0.0, 1.0]).filled(true).build();
+        }
+    }
+
+    fn draw_tooltip(ui: &Ui, orbit: &Orbit, start: [f64; 2]) {
+        ui.text(format!("z0 = {:.10} {:+.10}i", start[0], start[1]));
+        if orbit.escaped {
+            ui.text(format!("Escaped after {} iterations", orbit.iterations));
+        } else if orbit.converged {
+            ui.text(format!("Converged to 1 after {} iterations", orbit.iterations));
+        } else {
+            ui.text(format!("Bounded for all {} iterations", orbit.iterations));
+            match orbit.period {
+                Some(period) => ui.text(format!("Period {}", period)),
+                None => ui.text_disabled("No period detected"),
+            }
+        }
+    }
+}
+
+impl Observer for OrbitOverlay {
+    fn notify(&mut self, event: &ObserverEvent) {
+        if let ObserverEvent::Click{x, y} = event {
+            if !self.window_hovered {
+                self.clicked = Some([*x as f32, *y as f32]);
+            }
+        }
+    }
+}
@@ -1,84 +1,189 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 use imgui::{ColorPickerMode, Drag, Ui};
-use crate::ui::event_observer::FractalType::{Julia, Mandelbrot};
-use crate::ui::event_observer::{Observable, Observer, ObserverEvent::{FractalIterations, FractalChoice, FractalAxisRange}, ObserverEvent};
-use crate::ui::event_observer::ObserverEvent::{FractalHSVScaleChange, FractalTerminalColorChange};
+use sdl2::clipboard::ClipboardUtil;
+use crate::ui::event_observer::FractalType::{Julia, Lyapunov, Mandelbrot};
+use crate::location_code;
+use crate::lyapunov::LyapunovSequence;
+use crate::renderer::FractalRenderer;
+use crate::scene::{EscapeNorm, Formula, FractalKind, Scene};
+use crate::ui::auto_iterations::AutoIterations;
+use crate::ui::camera_controller::CameraController;
+use crate::ui::dual_view::DualView;
+use crate::ui::history::History;
+use crate::ui::event_observer::{Observable, Observer, ObserverEvent::{FractalIterations, FractalChoice, FractalCamera}, ObserverEvent};
+use crate::ui::event_observer::ObserverEvent::{FractalHSVScaleChange, FractalJuliaPane, FractalPaletteOffset, FractalTerminalColorChange};
 
 pub struct PropertiesWindow {
-    items: Vec<String>,
-    selected_index: usize,
-
-    julia_constant: [f32; 2],
-    hsv_scale: [f32; 3],
-    terminal_color: [f32; 3],
-    zoom: f32,
-    focus: [f32; 2],
-    camera_width: f32,
-    camera_height: f32,
-
-    real_x_axis_range: [f32; 2],
-    real_y_axis_range: [f32; 2],
-    max_iterations: i32,
+    scene: Scene,
+    camera_controller: CameraController,
+    dual_view: DualView,
+    auto_iterations: AutoIterations,
+    history: Rc<RefCell<History>>,
+    clipboard: ClipboardUtil,
+    location_status: String,
+    /// AB sequence being edited, kept apart from the scene so it can be invalid while typing
+    sequence_text: String,
+    sequence_error: Option<String>,
 
     current_width: i32,
     current_height: i32,
     lock_aspect_ratio: bool,
 
     window_hovered: bool,
+    /// last position of the mouse in window pixels, decides which pane dragging moves
+    mouse_position: [f32; 2],
+    selection: Option<([i32; 2], [i32; 2])>,
 
     observers: Vec<Rc<RefCell<dyn Observer>>>
 }
 
-impl Default for PropertiesWindow {
-    fn default() -> Self {
-        let items = vec!["Mandelbrot".into(), "Julia".into()];
+impl PropertiesWindow {
+    /// create the properties window editing the default scene, changes are recorded in the given history
+    /// location codes are copied to and pasted from the given clipboard
+    pub fn new(history: Rc<RefCell<History>>, clipboard: ClipboardUtil) -> Self {
         Self {
-            selected_index: 0,
-            items,
-
-            julia_constant: [-0.8, 0.156],
-            hsv_scale: [1.0, 1.0, 1.0],
-            terminal_color: [0.0, 0.0, 0.0],
-            zoom: 1.0,
-            focus: [0.0, 0.0],
-            camera_width: 2.0,
-            camera_height: 2.0,
-
-            real_x_axis_range: [-1.0, 1.0],
-            real_y_axis_range: [-1.0, 1.0],
-            max_iterations: 500,
+            scene: Scene::default(),
+            camera_controller: CameraController::default(),
+            dual_view: DualView::default(),
+            auto_iterations: AutoIterations::default(),
+            history,
+            clipboard,
+            location_status: String::new(),
+            sequence_text: LyapunovSequence::default().to_string(),
+            sequence_error: None,
 
             current_width: 800,
             current_height: 600,
             lock_aspect_ratio: true,
 
             window_hovered: false,
+            mouse_position: [0.0, 0.0],
+            selection: None,
 
             observers: Vec::new()
         }
     }
-}
 
-impl PropertiesWindow {
+    /// advance the smooth camera movement by `dt` seconds and record the changes in the history
+    pub fn update(&mut self, dt: f64) {
+        let width = self.view_width() as f64;
+        let height = self.current_height as f64;
+        // the zoom keys zoom around the cursor, or the center while it is outside of the main view
+        let [x, y] = self.mouse_position.map(|value| value as f64);
+        if (0.0..width).contains(&x) && (0.0..height).contains(&y) {
+            self.camera_controller.set_key_zoom_anchor(x, y);
+        } else {
+            self.camera_controller.set_key_zoom_anchor(width / 2.0, height / 2.0);
+        }
+        self.camera_controller.update(dt, &mut self.scene.camera, width, height);
+
+        self.notify_observers(FractalCamera(self.scene.camera));
+        let fractal = match self.scene.fractal {
+            FractalKind::Mandelbrot => Mandelbrot,
+            FractalKind::Julia => Julia(self.scene.julia_constant),
+            FractalKind::Lyapunov => Lyapunov { sequence: self.scene.lyapunov_sequence, warmup: self.scene.lyapunov_warmup },
+        };
+        self.notify_observers(FractalChoice(fractal));
+        self.notify_observers(ObserverEvent::FractalFormula{formula: self.scene.formula, parameter: self.scene.formula_parameter});
+        self.update_julia_pane(dt);
+
+        self.history.borrow_mut().record(&self.scene, dt);
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    /// size of the main view in pixels, the left pane while the window is split
+    pub fn view_size(&self) -> [f64; 2] {
+        [self.view_width() as f64, self.current_height as f64]
+    }
+
+    /// width of the main view in pixels, the left pane while the window is split
+    fn view_width(&self) -> i32 {
+        if self.dual_view.enabled {
+            DualView::split(self.current_width)
+        } else {
+            self.current_width
+        }
+    }
+
+    /// whether the window pixel column `x` belongs to the Julia pane
+    fn in_julia_pane(&self, x: f32) -> bool {
+        self.dual_view.enabled && x >= DualView::split(self.current_width) as f32
+    }
+
+    /// move the camera of the Julia pane and pass the scene of the pane on to the renderer
+    fn update_julia_pane(&mut self, dt: f64) {
+        if !self.dual_view.enabled {
+            self.notify_observers(FractalJuliaPane(None));
+            return;
+        }
+
+        let width = (self.current_width - DualView::split(self.current_width)) as f64;
+        let height = self.current_height as f64;
+        let dual_view = &mut self.dual_view;
+        dual_view.camera.width = width / height * dual_view.camera.height;
+        dual_view.camera_controller.update(dt, &mut dual_view.camera, width, height);
+
+        let scene = self.dual_view.scene(&self.scene);
+        self.notify_observers(FractalJuliaPane(Some(scene)));
+    }
+
+    /// queue a wheel zoom in the pane under the given window pixel
+    fn add_zoom(&mut self, factor: f64, x: i32, y: i32) {
+        if self.in_julia_pane(x as f32) {
+            self.dual_view.camera_controller.add_zoom(factor, x - DualView::split(self.current_width), y);
+        } else {
+            self.camera_controller.add_zoom(factor, x, y);
+        }
+    }
+
+    /// probe the current view and return the maximum iterations the auto mode picks, if they should change
+    /// the new value is applied with `set_max_iterations` once the renderer is no longer borrowed
+    pub fn probe_iterations(&mut self, fractal_renderer: &FractalRenderer) -> Option<i32> {
+        self.auto_iterations.probe(fractal_renderer, &self.scene)
+    }
+
+    pub fn set_max_iterations(&mut self, max_iterations: i32) {
+        self.scene.max_iterations = max_iterations;
+        self.notify_observers(FractalIterations(max_iterations));
+    }
+
+    /// replace the whole scene, used when stepping through the history or opening a bookmark
+    pub fn restore_scene(&mut self, scene: Scene) {
+        self.camera_controller.stop();
+        self.scene = scene;
+        self.sequence_text = scene.lyapunov_sequence.to_string();
+        self.sequence_error = None;
+        if self.lock_aspect_ratio {
+            self.scene.camera.width = self.view_width() as f64/self.current_height as f64 * self.scene.camera.height
+        }
+
+        self.notify_observers(FractalIterations(scene.max_iterations));
+        self.notify_observers(ObserverEvent::FractalBailout{radius: scene.bailout, norm: scene.escape_norm});
+        self.notify_observers(FractalHSVScaleChange{h: scene.hsv_scale[0], s: scene.hsv_scale[1], v: scene.hsv_scale[2]});
+        self.notify_observers(FractalPaletteOffset(scene.palette_offset));
+        self.notify_observers(FractalTerminalColorChange{r: scene.terminal_color[0], g: scene.terminal_color[1], b: scene.terminal_color[2]});
+    }
+
     pub fn draw(&mut self, ui: &mut Ui) {
         ui.window("Properties")
             .size([350.0, 600.0], imgui::Condition::FirstUseEver)
             .movable(false)
-            .position([self.current_width as f32, 0.0], imgui::Condition::Always)
+            // leave space for the main menu bar
+            .position([self.current_width as f32, ui.frame_height()], imgui::Condition::Always)
             .position_pivot([1.0, 0.0])
             .collapsible(false)
             .resizable(false)
             .build(|| {
-                {
-                    ui.set_next_item_width(-1.0);
-                    if Drag::new("##max_iterations").display_format("Max iterations: %d").speed(1.0).build(ui, &mut self.max_iterations) {
-                        self.notify_observers(FractalIterations(self.max_iterations))
-                    }
-                }
+                self.draw_max_iterations(ui);
 
                 self.draw_fractal_combo(ui);
+                self.draw_formula(ui);
                 self.handle_fractal_constant(ui);
+                self.draw_dual_view(ui);
                 self.draw_hsv_scale(ui);
                 self.draw_terminal_color(ui);
 
@@ -86,65 +191,259 @@ impl PropertiesWindow {
                 {
                     ui.set_next_item_width(-1.0);
                     let _item_width_stack_token = ui.push_item_width(ui.calc_item_width()/2.0);
-                    Drag::new("##focus.x").display_format("X: %f").speed(0.001).build(ui, &mut self.focus[0]);
+                    Drag::new("##focus.x").display_format("X: %f").speed(0.001).build(ui, &mut self.scene.camera.focus[0]);
                     ui.same_line();
-                    Drag::new("##focus.y").display_format("Y: %f").speed(0.001).build(ui, &mut self.focus[1]);
+                    Drag::new("##focus.y").display_format("Y: %f").speed(0.001).build(ui, &mut self.scene.camera.focus[1]);
                 }
 
                 {
                     ui.set_next_item_width(-1.0);
                     ui.text("Zoom level");
                     ui.same_line();
-                    Drag::new("##zoom").display_format("%f").speed(0.1).build(ui, &mut self.zoom);
+                    // the zoom divides the visible width, it has to stay positive also when typed in
+                    Drag::new("##zoom").range(1e-6, 1e300).flags(imgui::SliderFlags::ALWAYS_CLAMP)
+                        .display_format("%f").speed(0.1).build(ui, &mut self.scene.camera.zoom);
+                }
+
+                {
+                    ui.set_next_item_width(-1.0);
+                    ui.text("Rotation");
+                    ui.same_line();
+                    let mut degrees = self.scene.camera.rotation.to_degrees();
+                    if Drag::new("##rotation").display_format("%.2f deg").speed(0.5).build(ui, &mut degrees) {
+                        self.scene.camera.rotation = degrees.to_radians().rem_euclid(std::f64::consts::TAU);
+                    }
                 }
 
                 self.draw_camera_size(ui);
-                self.window_hovered = ui.io().want_capture_mouse;
+                self.draw_location_code(ui);
             });
+    }
 
-        self.real_x_axis_range[0] = self.focus[0] - self.camera_width / 2.0 / self.zoom;
-        self.real_x_axis_range[1] = self.focus[0] + self.camera_width / 2.0 / self.zoom;
-        self.real_y_axis_range[0] = self.focus[1] - self.camera_height / 2.0 / self.zoom;
-        self.real_y_axis_range[1] = self.focus[1] + self.camera_height / 2.0 / self.zoom;
+    /// max iterations with the auto mode, which shows the value it picked and can keep it with the lock
+    fn draw_max_iterations(&mut self, ui: &Ui) {
+        ui.set_next_item_width(-60.0);
+        if Drag::new("##max_iterations").display_format("Max iterations: %d").speed(1.0).build(ui, &mut self.scene.max_iterations) {
+            // a value set by hand is kept until the lock is released
+            self.auto_iterations.locked = self.auto_iterations.enabled;
+            self.notify_observers(FractalIterations(self.scene.max_iterations))
+        }
+        ui.same_line();
+        ui.checkbox("Auto", &mut self.auto_iterations.enabled);
 
-        self.notify_observers(FractalAxisRange{x: self.real_x_axis_range, y: self.real_y_axis_range});
+        if self.auto_iterations.enabled {
+            ui.checkbox("Lock", &mut self.auto_iterations.locked);
+            ui.same_line();
+            if self.scene.fractal.is_complex() {
+                ui.text_disabled(format!("{:.2}% of pixels beyond the limit", self.auto_iterations.beyond_limit * 100.0));
+            } else {
+                ui.text_disabled("only for escape-time fractals");
+            }
+        }
+    }
+
+    /// draw the parts of the UI that stay visible when the panels are hidden
+    pub fn draw_overlay(&mut self, ui: &Ui) {
+        self.window_hovered = ui.io().want_capture_mouse;
+        self.mouse_position = ui.io().mouse_pos;
+        self.draw_selection(ui);
+        self.draw_julia_pane_overlay(ui);
+    }
+
+    fn draw_dual_view(&mut self, ui: &Ui) {
+        if ui.checkbox("Linked Julia view", &mut self.dual_view.enabled) {
+            self.camera_controller.stop();
+            if self.dual_view.enabled {
+                self.scene.fractal = FractalKind::Mandelbrot;
+            }
+            if self.lock_aspect_ratio {
+                self.scene.camera.width = self.view_width() as f64/self.current_height as f64 * self.scene.camera.height
+            }
+        }
+
+        if self.dual_view.enabled {
+            ui.same_line();
+            ui.checkbox("Follow cursor", &mut self.dual_view.follow_cursor);
+            let [x, y] = self.dual_view.julia_constant(&self.scene);
+            ui.text_disabled(format!("c = {:.6} {:+.6}i, click to pin", x, y));
+        }
+    }
+
+    /// track the point under the cursor for the Julia pane and mark it on the Mandelbrot pane
+    fn draw_julia_pane_overlay(&mut self, ui: &Ui) {
+        if !self.dual_view.enabled {
+            return;
+        }
+
+        let [x, y] = self.mouse_position;
+        let split = DualView::split(self.current_width) as f32;
+        // keep the last point while the cursor is over a panel or the Julia pane
+        if !self.window_hovered && (0.0..split).contains(&x) && (0.0..self.current_height as f32).contains(&y) {
+            let point = self.screen_to_complex(x, y);
+            self.dual_view.hovered = Some([point[0] as f32, point[1] as f32]);
+        }
+
+        let draw_list = ui.get_background_draw_list();
+        draw_list.add_line([split, 0.0], [split, self.current_height as f32], [0.0, 0.0, 0.0, 1.0]).thickness(2.0).build();
+
+        if self.scene.fractal == FractalKind::Mandelbrot {
+            let [re, im] = self.dual_view.julia_constant(&self.scene);
+            let [x, y] = self.scene.camera.complex_to_screen([re as f64, im as f64], split as f64, self.current_height as f64);
+            draw_list.add_circle([x as f32, y as f32], 5.0, [1.0, 1.0, 1.0, 1.0]).thickness(1.5).build();
+        }
+    }
+
+    /// pin the julia constant to the point of the Mandelbrot pane that was clicked
+    fn pin_julia_constant(&mut self, x: i32, y: i32) {
+        let point = self.screen_to_complex(x as f32, y as f32);
+        self.scene.julia_constant = [point[0] as f32, point[1] as f32];
+        self.dual_view.follow_cursor = false;
     }
 
     fn draw_fractal_combo(&mut self, ui: &Ui) {
         ui.text("Fractal");
         ui.same_line();
         ui.set_next_item_width(-1.0);
-        if let Some(_cb) = ui.begin_combo("##fractal_combo", &self.items[self.selected_index]) {
-            for cur in &self.items {
-                if &self.items[self.selected_index] == cur {
+        if let Some(_cb) = ui.begin_combo("##fractal_combo", self.scene.fractal.name()) {
+            for cur in FractalKind::ALL {
+                if self.scene.fractal == cur {
                     // Auto-scroll to selected item
                     ui.set_item_default_focus();
                 }
                 // Create a "selectable"
-                let clicked = ui.selectable_config(cur)
-                    .selected(&self.items[self.selected_index] == cur)
+                let clicked = ui.selectable_config(cur.name())
+                    .selected(self.scene.fractal == cur)
                     .build();
                 // When item is clicked, store it
                 if clicked {
-                    self.selected_index = self.items.iter().position(|item| item == cur).unwrap();
+                    self.set_fractal(cur);
                 }
             }
         }
     }
 
+    /// formula combo and its extra parameter, only for the escape-time fractals
+    fn draw_formula(&mut self, ui: &Ui) {
+        if !self.scene.fractal.is_complex() {
+            return;
+        }
+
+        ui.text("Formula");
+        ui.same_line();
+        ui.set_next_item_width(-1.0);
+        if let Some(_cb) = ui.begin_combo("##formula_combo", self.scene.formula.name()) {
+            for cur in Formula::ALL {
+                if self.scene.formula == cur {
+                    ui.set_item_default_focus();
+                }
+                if ui.selectable_config(cur.name()).selected(self.scene.formula == cur).build() && self.scene.formula != cur {
+                    self.set_formula(cur);
+                }
+            }
+        }
+
+        if self.scene.formula.has_parameter() {
+            ui.text("Parameter p");
+
+            ui.set_next_item_width(-1.0);
+            let _item_width_stack_token = ui.push_item_width(ui.calc_item_width() / 2.0);
+            Drag::new("##p.x").display_format("X: %f").speed(0.001).build(ui, &mut self.scene.formula_parameter[0]);
+            ui.same_line();
+            Drag::new("##p.y").display_format("Y: %f").speed(0.001).build(ui, &mut self.scene.formula_parameter[1]);
+        }
+        if self.scene.formula.converges() {
+            ui.text_disabled("points converging to 1 are colored like escaping ones");
+        }
+
+        self.draw_bailout(ui);
+    }
+
+    /// bailout radius and the norm it is measured with
+    fn draw_bailout(&mut self, ui: &Ui) {
+        ui.text("Bailout");
+        ui.same_line();
+        ui.set_next_item_width(-1.0);
+        let _item_width_stack_token = ui.push_item_width(ui.calc_item_width() / 2.0);
+        let mut changed = Drag::new("##bailout").display_format("R: %g").speed(0.01).range(1.0, 1e8).build(ui, &mut self.scene.bailout);
+        ui.same_line();
+        if let Some(_cb) = ui.begin_combo("##escape_norm", self.scene.escape_norm.name()) {
+            for cur in EscapeNorm::ALL {
+                if ui.selectable_config(cur.name()).selected(self.scene.escape_norm == cur).build() {
+                    self.scene.escape_norm = cur;
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            self.notify_observers(ObserverEvent::FractalBailout{radius: self.scene.bailout, norm: self.scene.escape_norm});
+        }
+    }
+
+    /// switch to another formula, with a typical julia constant and the home view of its planes
+    fn set_formula(&mut self, formula: Formula) {
+        self.scene.formula = formula;
+        self.scene.julia_constant = formula.default_julia_constant();
+        self.scene.bailout = formula.default_bailout();
+        self.notify_observers(ObserverEvent::FractalBailout{radius: self.scene.bailout, norm: self.scene.escape_norm});
+        self.reset_view();
+    }
+
+    /// switch to another fractal, moving the camera home when the plane changes between complex numbers and rates
+    fn set_fractal(&mut self, fractal: FractalKind) {
+        let plane_changed = fractal.is_complex() != self.scene.fractal.is_complex();
+        self.scene.fractal = fractal;
+        if !fractal.is_complex() {
+            // the linked Julia view picks its constants from the complex plane
+            self.dual_view.enabled = false;
+        }
+        if plane_changed {
+            self.reset_view();
+        }
+    }
+
+    /// move the camera back to the home view of the fractal
+    fn reset_view(&mut self) {
+        self.camera_controller.stop();
+        self.dual_view.camera_controller.stop();
+        self.dual_view.camera = DualView::home_camera(&self.scene);
+        self.scene.camera = self.scene.home_camera();
+        if self.lock_aspect_ratio {
+            self.scene.camera.width = self.view_width() as f64/self.current_height as f64 * self.scene.camera.height
+        }
+    }
+
     fn handle_fractal_constant(&mut self, ui: &Ui) {
-        if self.items[self.selected_index] == "Julia" {
+        if self.scene.fractal == FractalKind::Julia {
             ui.text("Julia constant");
 
             ui.set_next_item_width(-1.0);
             let _item_width_stack_token = ui.push_item_width(ui.calc_item_width() / 2.0);
-            Drag::new("##c.x").display_format("X: %f").speed(0.001).build(ui, &mut self.julia_constant[0]);
+            Drag::new("##c.x").display_format("X: %f").speed(0.001).build(ui, &mut self.scene.julia_constant[0]);
             ui.same_line();
-            Drag::new("##c.y").display_format("Y: %f").speed(0.001).build(ui, &mut self.julia_constant[1]);
+            Drag::new("##c.y").display_format("Y: %f").speed(0.001).build(ui, &mut self.scene.julia_constant[1]);
+        }
 
-            self.notify_observers(FractalChoice(Julia(self.julia_constant)));
-        } else {
-            self.notify_observers(FractalChoice(Mandelbrot));
+        if self.scene.fractal == FractalKind::Lyapunov {
+            ui.text("Sequence");
+            ui.same_line();
+            ui.set_next_item_width(-1.0);
+            if ui.input_text("##lyapunov_sequence", &mut self.sequence_text).build() {
+                match LyapunovSequence::parse(&self.sequence_text) {
+                    Ok(sequence) => {
+                        self.scene.lyapunov_sequence = sequence;
+                        self.sequence_error = None;
+                    },
+                    Err(message) => self.sequence_error = Some(message),
+                }
+            }
+            if let Some(message) = &self.sequence_error {
+                ui.text_colored([1.0, 0.4, 0.4, 1.0], message);
+            }
+
+            ui.set_next_item_width(-1.0);
+            Drag::new("##lyapunov_warmup").display_format("Warm-up steps: %d").range(0, 10_000).build(ui, &mut self.scene.lyapunov_warmup);
+            ui.text_disabled("x is the rate a, y the rate b");
         }
     }
 
@@ -152,33 +451,128 @@ impl PropertiesWindow {
         ui.text("Camera size");
         if ui.checkbox("Lock aspect ratio", &mut self.lock_aspect_ratio) {
             if self.lock_aspect_ratio {
-                self.camera_height = self.current_height as f32/self.current_width as f32 * self.camera_width
+                self.scene.camera.height = self.current_height as f64/self.view_width() as f64 * self.scene.camera.width
             }
         }
 
         ui.set_next_item_width(-1.0);
         let _item_width_stack_token = ui.push_item_width(ui.calc_item_width()/2.0);
-        if Drag::new("##camera.width").display_format("Width: %f").speed(0.1).build(ui, &mut self.camera_width) && self.lock_aspect_ratio {
-            self.camera_height = self.current_height as f32/self.current_width as f32 * self.camera_width
+        if Drag::new("##camera.width").display_format("Width: %f").speed(0.1).build(ui, &mut self.scene.camera.width) && self.lock_aspect_ratio {
+            self.scene.camera.height = self.current_height as f64/self.view_width() as f64 * self.scene.camera.width
         }
         ui.same_line();
-        if Drag::new("##camera.height").display_format("Height: %f").speed(0.1).build(ui, &mut self.camera_height) && self.lock_aspect_ratio {
-            self.camera_width = self.current_width as f32/self.current_height as f32 * self.camera_height
+        if Drag::new("##camera.height").display_format("Height: %f").speed(0.1).build(ui, &mut self.scene.camera.height) && self.lock_aspect_ratio {
+            self.scene.camera.width = self.view_width() as f64/self.current_height as f64 * self.scene.camera.height
         }
     }
 
+    fn draw_location_code(&mut self, ui: &Ui) {
+        ui.text("Location code");
+        if ui.button("Copy") {
+            self.copy_location();
+        }
+        ui.same_line();
+        if ui.button("Paste") {
+            self.paste_location();
+        }
+        ui.text_wrapped(&self.location_status);
+    }
+
+    /// put the location code of the current scene on the clipboard
+    fn copy_location(&mut self) {
+        let code = location_code::encode(&self.scene);
+        self.location_status = match self.clipboard.set_clipboard_text(&code) {
+            Ok(()) => format!("Copied {}", code),
+            Err(err) => format!("Failed to copy: {}", err),
+        };
+    }
+
+    /// navigate to the location code on the clipboard
+    fn paste_location(&mut self) {
+        let decoded = self.clipboard.clipboard_text()
+            .map_err(|err| format!("Failed to paste: {}", err))
+            .and_then(|text| location_code::decode(&text).map_err(|err| format!("Invalid location code: {}", err)));
+
+        match decoded {
+            Ok(scene) => {
+                self.restore_scene(scene);
+                self.location_status = "Pasted location".to_string();
+            },
+            Err(message) => self.location_status = message,
+        }
+    }
+
+    /// draw the rubber-band selection overlay
+    /// the outline marks the dragged rectangle, the filled area marks the region that will be shown after zooming
+    fn draw_selection(&self, ui: &Ui) {
+        if let Some((from, to)) = self.selection {
+            let draw_list = ui.get_background_draw_list();
+            let (min, max) = self.fit_selection(from, to);
+
+            draw_list.add_rect(min, max, [1.0, 1.0, 1.0, 0.15]).filled(true).build();
+            draw_list.add_rect(min, max, [1.0, 1.0, 1.0, 0.6]).build();
+            draw_list.add_rect(
+                [from[0].min(to[0]) as f32, from[1].min(to[1]) as f32],
+                [from[0].max(to[0]) as f32, from[1].max(to[1]) as f32],
+                [1.0, 1.0, 0.0, 1.0]).thickness(1.5).build();
+        }
+    }
+
+    /// expand the selected rectangle so it matches the aspect ratio of the window
+    /// returns the top left and bottom right corners of the fitted rectangle in screen pixels
+    fn fit_selection(&self, from: [i32; 2], to: [i32; 2]) -> ([f32; 2], [f32; 2]) {
+        let center = [(from[0] + to[0]) as f32 / 2.0, (from[1] + to[1]) as f32 / 2.0];
+        let mut width = (to[0] - from[0]).abs().max(1) as f32;
+        let mut height = (to[1] - from[1]).abs().max(1) as f32;
+
+        let aspect = self.view_width() as f32 / self.current_height as f32;
+        if width / height < aspect {
+            width = height * aspect;
+        } else {
+            height = width / aspect;
+        }
+
+        (
+            [center[0] - width / 2.0, center[1] - height / 2.0],
+            [center[0] + width / 2.0, center[1] + height / 2.0]
+        )
+    }
+
+    /// convert a position in window pixels (origin in the top left corner) to a point on the complex plane
+    fn screen_to_complex(&self, x: f32, y: f32) -> [f64; 2] {
+        self.scene.camera.screen_to_complex(x as f64, y as f64, self.view_width() as f64, self.current_height as f64)
+    }
+
+    /// zoom so that the selected rectangle fills the window
+    fn zoom_to_selection(&mut self, from: [i32; 2], to: [i32; 2]) {
+        // ignore accidental clicks
+        if (to[0] - from[0]).abs() < 4 || (to[1] - from[1]).abs() < 4 {
+            return;
+        }
+
+        let (min, max) = self.fit_selection(from, to);
+        self.camera_controller.stop();
+        self.scene.camera.focus = self.screen_to_complex((min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0);
+        self.scene.camera.zoom *= self.view_width() as f64 / (max[0] - min[0]) as f64;
+    }
+
     fn draw_hsv_scale(&mut self, ui: &Ui) {
         ui.text("HSV scale");
         ui.set_next_item_width(-1.0);
         let _item_width_stack_token = ui.push_item_width(ui.calc_item_width() / 3.0);
-        let h = Drag::new("##h_scale").display_format("H: %f").speed(0.01).range(0.0, 1.0).build(ui, &mut self.hsv_scale[0]);
+        let h = Drag::new("##h_scale").display_format("H: %f").speed(0.01).range(0.0, 1.0).build(ui, &mut self.scene.hsv_scale[0]);
         ui.same_line();
-        let s = Drag::new("##s_scale").display_format("S: %f").speed(0.01).range(0.0, 1.0).build(ui, &mut self.hsv_scale[1]);
+        let s = Drag::new("##s_scale").display_format("S: %f").speed(0.01).range(0.0, 1.0).build(ui, &mut self.scene.hsv_scale[1]);
         ui.same_line();
-        let v = Drag::new("##v_scale").display_format("V: %f").speed(0.01).range(0.0, 1.0).build(ui, &mut self.hsv_scale[2]);
+        let v = Drag::new("##v_scale").display_format("V: %f").speed(0.01).range(0.0, 1.0).build(ui, &mut self.scene.hsv_scale[2]);
 
         if h || s || v {
-            self.notify_observers(FractalHSVScaleChange{h: self.hsv_scale[0], s: self.hsv_scale[1], v: self.hsv_scale[2]});
+            self.notify_observers(FractalHSVScaleChange{h: self.scene.hsv_scale[0], s: self.scene.hsv_scale[1], v: self.scene.hsv_scale[2]});
+        }
+
+        ui.set_next_item_width(-1.0);
+        if Drag::new("##palette_offset").display_format("Palette offset: %.3f").speed(0.005).build(ui, &mut self.scene.palette_offset) {
+            self.notify_observers(FractalPaletteOffset(self.scene.palette_offset));
         }
     }
 
@@ -186,8 +580,8 @@ impl PropertiesWindow {
         ui.set_next_item_width(-1.0);
         ui.text("Terminal color");
 
-        if ui.color_picker3_config("##terminal_color", &mut self.terminal_color).mode(ColorPickerMode::HueWheel).build() {
-            self.notify_observers(FractalTerminalColorChange{r: self.terminal_color[0], g: self.terminal_color[1], b: self.terminal_color[2]});
+        if ui.color_picker3_config("##terminal_color", &mut self.scene.terminal_color).mode(ColorPickerMode::HueWheel).build() {
+            self.notify_observers(FractalTerminalColorChange{r: self.scene.terminal_color[0], g: self.scene.terminal_color[1], b: self.scene.terminal_color[2]});
         }
     }
 }
@@ -196,18 +590,60 @@ impl Observer for PropertiesWindow {
     fn notify(&mut self, event: &ObserverEvent) {
 
         match event {
-            ObserverEvent::Zoom(zoom) if !self.window_hovered => self.zoom *= zoom,
-            ObserverEvent::UnZoom(zoom) if !self.window_hovered  => self.zoom /= zoom,
+            ObserverEvent::Zoom{factor, x, y} if !self.window_hovered => self.add_zoom(*factor as f64, *x, *y),
+            ObserverEvent::UnZoom{factor, x, y} if !self.window_hovered  => self.add_zoom(1.0 / *factor as f64, *x, *y),
+            ObserverEvent::NavigationInput{x, y, zoom, rotation} => self.camera_controller.set_input(*x, *y, *zoom, *rotation),
+            ObserverEvent::SelectionChanged{from, to} if !self.window_hovered && !self.in_julia_pane(from[0] as f32) => self.selection = Some((*from, *to)),
+            ObserverEvent::ZoomToSelection{from, to} => {
+                self.selection = None;
+                if !self.window_hovered && !self.in_julia_pane(from[0] as f32) {
+                    self.zoom_to_selection(*from, *to);
+                }
+            },
+            ObserverEvent::Click{x, y} if !self.window_hovered && self.dual_view.enabled && !self.in_julia_pane(*x as f32) => {
+                self.pin_julia_constant(*x, *y);
+            },
+            ObserverEvent::Translate{xrel, yrel} if !self.window_hovered && self.in_julia_pane(self.mouse_position[0]) => {
+                let width = (self.current_width - DualView::split(self.current_width)) as f64;
+                self.dual_view.camera.translate_pixels(*xrel as f64, *yrel as f64, width, self.current_height as f64);
+            },
             ObserverEvent::Translate{xrel, yrel} if !self.window_hovered  => {
-                self.focus[0] += *xrel as f32 / self.current_width as f32 / self.zoom;
-                self.focus[1] += *yrel as f32 / self.current_height as f32 / self.zoom;
+                self.scene.camera.translate_pixels(*xrel as f64, *yrel as f64, self.view_width() as f64, self.current_height as f64);
+            },
+            ObserverEvent::Rotate(angle) if !self.window_hovered && self.in_julia_pane(self.mouse_position[0]) => self.dual_view.camera.rotate(*angle as f64),
+            ObserverEvent::Rotate(angle) if !self.window_hovered => self.scene.camera.rotate(*angle as f64),
+            ObserverEvent::ResetView => self.reset_view(),
+            ObserverEvent::NextFractal => {
+                let index = FractalKind::ALL.iter().position(|kind| *kind == self.scene.fractal).unwrap_or(0);
+                self.set_fractal(FractalKind::ALL[(index + 1) % FractalKind::ALL.len()]);
+            },
+            ObserverEvent::Undo => {
+                let scene = self.history.borrow_mut().undo(&self.scene);
+                if let Some(scene) = scene {
+                    self.restore_scene(scene);
+                }
+            },
+            ObserverEvent::Redo => {
+                let scene = self.history.borrow_mut().redo();
+                if let Some(scene) = scene {
+                    self.restore_scene(scene);
+                }
+            },
+            ObserverEvent::HistoryJump(index) => {
+                let scene = self.history.borrow_mut().jump_to(*index);
+                if let Some(scene) = scene {
+                    self.restore_scene(scene);
+                }
             },
+            ObserverEvent::CopyLocation => self.copy_location(),
+            ObserverEvent::PasteLocation => self.paste_location(),
+            ObserverEvent::RestoreScene(scene) => self.restore_scene(*scene),
             ObserverEvent::WindowSizeChanged {width, height} => {
                 self.current_width = *width;
                 self.current_height = *height;
 
                 if self.lock_aspect_ratio {
-                    self.camera_width = self.current_width as f32/self.current_height as f32 * self.camera_height
+                    self.scene.camera.width = self.view_width() as f64/self.current_height as f64 * self.scene.camera.height
                 }
             }
             _ => {}
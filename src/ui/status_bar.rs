@@ -0,0 +1,149 @@
+use imgui::Ui;
+use sdl2::clipboard::ClipboardUtil;
+use crate::lyapunov;
+use crate::orbit::Orbit;
+use crate::scene::Scene;
+
+/// Bar along the bottom of the window showing where the view is and how fast it renders
+/// clicking a field copies its value to the clipboard
+pub struct StatusBar {
+    clipboard: ClipboardUtil,
+    /// frame time in seconds, smoothed over the last frames so it can be read
+    frame_time: f64,
+    /// result of the last copy and for how many more seconds it is shown
+    copied: Option<(String, f64)>,
+    /// point of the complex plane last under the cursor, kept while the cursor is over the panels or this bar
+    cursor: Option<[f64; 2]>,
+}
+
+impl StatusBar {
+    /// how fast the shown frame time follows the measured one
+    const FRAME_TIME_SMOOTHING: f64 = 0.05;
+    /// seconds the copy confirmation stays visible
+    const COPIED_DURATION: f64 = 2.0;
+
+    pub fn new(clipboard: ClipboardUtil) -> Self {
+        Self {
+            clipboard,
+            frame_time: 0.0,
+            copied: None,
+            cursor: None,
+        }
+    }
+
+    /// record the time the last frame took
+    pub fn update(&mut self, dt: f64) {
+        self.frame_time += (dt - self.frame_time) * Self::FRAME_TIME_SMOOTHING;
+
+        if let Some((_, remaining)) = &mut self.copied {
+            *remaining -= dt;
+            if *remaining <= 0.0 {
+                self.copied = None;
+            }
+        }
+    }
+
+    /// draw the bar, `view_size` is the size of the main view and `resolution` the size of the window in pixels
+    pub fn draw(&mut self, ui: &Ui, scene: &Scene, view_size: [f64; 2], resolution: [u32; 2]) {
+        let [display_width, display_height] = ui.io().display_size;
+        let height = ui.frame_height_with_spacing();
+
+        ui.window("##status_bar")
+            .position([0.0, display_height - height], imgui::Condition::Always)
+            .size([display_width, height], imgui::Condition::Always)
+            .no_decoration()
+            .movable(false)
+            .bring_to_front_on_focus(false)
+            .build(|| {
+                let camera = &scene.camera;
+                let mouse = ui.io().mouse_pos;
+                let [width, height] = view_size;
+                let in_view = (0.0..width).contains(&(mouse[0] as f64)) && (0.0..height).contains(&(mouse[1] as f64));
+                // moving the cursor onto this bar to copy the point must not change it
+                if in_view && !ui.io().want_capture_mouse {
+                    self.cursor = Some(camera.screen_to_complex(mouse[0] as f64, mouse[1] as f64, width, height));
+                }
+
+                match self.cursor {
+                    Some(point) if scene.fractal.is_complex() => {
+                        self.draw_field(ui, "point", "", format!("{} {:+}i", point[0], point[1]));
+
+                        let orbit = Orbit::compute(scene, point);
+                        let label = if orbit.escaped || orbit.converged { "Iterations:" } else { "Iterations (bounded):" };
+                        self.draw_field(ui, "iterations", label, orbit.iterations.to_string());
+                    },
+                    Some(point) => {
+                        self.draw_field(ui, "point", "", format!("{}, {}", point[0], point[1]));
+
+                        let exponent = lyapunov::exponent(scene.lyapunov_sequence, scene.lyapunov_warmup, scene.max_iterations, point);
+                        self.draw_field(ui, "exponent", "Exponent:", format!("{:.4}", exponent));
+                    },
+                    None => self.draw_field(ui, "point", "", String::from("-")),
+                }
+
+                self.draw_field(ui, "zoom", "Zoom:", format!("{:.6e}", camera.zoom));
+                self.draw_field(ui, "width", "log10 width:", format!("{:.4}", (camera.width / camera.zoom).log10()));
+                self.draw_field(ui, "frame_time", "Frame time (ms):", format!("{:.2}", self.frame_time * 1000.0));
+                self.draw_field(ui, "resolution", "", format!("{}x{}", resolution[0], resolution[1]));
+
+                if let Some((message, _)) = &self.copied {
+                    ui.text_disabled(message);
+                }
+            });
+    }
+
+    /// draw one field followed by a separator, clicking it copies only its value, not the label
+    /// the point under the cursor is shown at full precision, so the copy can be pasted into other programs
+    fn draw_field(&mut self, ui: &Ui, name: &str, label: &str, value: String) {
+        let text = if label.is_empty() { value.clone() } else { format!("{} {}", label, value) };
+        if field_selectable(ui, name, &text) {
+            let message = match self.clipboard.set_clipboard_text(&value) {
+                Ok(()) => format!("Copied {}", value),
+                Err(err) => format!("Failed to copy: {}", err),
+            };
+            self.copied = Some((message, Self::COPIED_DURATION));
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text("Click to copy");
+        }
+        ui.same_line();
+        ui.text_disabled("|");
+        ui.same_line();
+    }
+}
+
+/// draw the text of a field as a selectable, returns whether it was clicked
+/// its id is the name of the field, so a click still counts while the value changes between pressing and releasing
+fn field_selectable(ui: &Ui, name: &str, text: &str) -> bool {
+    let size = ui.calc_text_size(text);
+    ui.selectable_config(format!("{}###{}", text, name)).size(size).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn click_on_changing_field() {
+        let mut imgui = imgui::Context::create();
+        imgui.set_ini_filename(None);
+        imgui.io_mut().display_size = [400.0, 100.0];
+        imgui.fonts().build_rgba32_texture();
+
+        // the value changes every frame while the button is pressed and released
+        let mut clicked = false;
+        for frame in 0..6 {
+            let io = imgui.io_mut();
+            io.delta_time = 1.0 / 60.0;
+            io.mouse_pos = [12.0, 12.0];
+            io.mouse_down[0] = (2..4).contains(&frame);
+
+            let ui = imgui.new_frame();
+            ui.window("status").position([0.0, 0.0], imgui::Condition::Always).no_decoration().build(|| {
+                clicked |= field_selectable(ui, "zoom", &format!("Zoom: {:.6e}", 1.0 + frame as f64));
+            });
+            imgui.render();
+        }
+        assert!(clicked);
+    }
+}
@@ -3,6 +3,7 @@ use imgui_glow_renderer::{AutoRenderer, glow};
 use imgui_sdl2_support::SdlPlatform;
 use sdl2::video::{GLContext, GLProfile, WindowBuildError};
 use sdl2::{EventPump, Sdl, VideoSubsystem};
+use sdl2::clipboard::ClipboardUtil;
 use sdl2::event::Event;
 
 const WIDTH: u32 = 800;
@@ -14,12 +15,12 @@ pub struct Window {
     pub platform: SdlPlatform,
     pub renderer: AutoRenderer,
     pub event_pump: EventPump,
+    video_subsystem: VideoSubsystem,
 
     /**
         References that must exist but are not used
      */
     #[allow(dead_code)] sdl_context: Sdl,
-    #[allow(dead_code)] video_subsystem: VideoSubsystem,
     #[allow(dead_code)] gl_context: GLContext,
 }
 
@@ -34,6 +35,8 @@ impl Window {
 
         gl_attr.set_context_version(4, 4);
         gl_attr.set_context_profile(GLProfile::Core);
+        // the height field mesh is drawn with depth testing
+        gl_attr.set_depth_size(24);
 
         let window = create_window(&video_subsystem)
             .map_err(|e| e.to_string())
@@ -65,6 +68,11 @@ impl Window {
 
         events
     }
+
+    /// access to the system clipboard
+    pub fn clipboard(&self) -> ClipboardUtil {
+        self.video_subsystem.clipboard()
+    }
 }
 
 fn create_window(video_subsystem: &VideoSubsystem) -> Result<sdl2::video::Window, WindowBuildError> {